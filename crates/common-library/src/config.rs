@@ -1,8 +1,11 @@
 //! Configuration management for the common library
 
 use crate::error::{Error, Result};
-use config::{Config, ConfigError, Environment, File, FileFormat};
+use config::{Config, Environment, File, FileFormat};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Configuration manager for the common library
 pub struct ConfigManager {
@@ -16,6 +19,11 @@ pub struct AppConfig {
     pub http: HttpConfig,
     pub logging: LoggingConfig,
     pub storage: StorageConfig,
+    #[serde(default)]
+    pub package_managers: HashMap<String, PackageManagerConfig>,
+    /// Push internal metrics to a collector; disabled when unset
+    #[serde(default)]
+    pub metrics_export: Option<MetricsExportConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,10 +35,157 @@ pub struct DatabaseConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpConfig {
+    /// Total time budget for a request, including reading the body
     pub timeout_seconds: u64,
+    /// Time allowed to establish a connection
+    #[serde(default)]
+    pub connect_timeout_seconds: Option<u64>,
+    /// Maximum idle time between reads of the response
+    #[serde(default)]
+    pub read_timeout_seconds: Option<u64>,
+    /// Largest response body accepted when buffering
+    #[serde(default)]
+    pub max_response_bytes: Option<u64>,
     pub max_retries: u32,
     pub rate_limit_per_minute: u32,
     pub user_agent: String,
+    /// Explicit proxy; when unset, `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` are honored
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub pool: ConnectionPoolConfig,
+    /// Custom CAs, client certificates, and verification settings
+    #[serde(default)]
+    pub tls: TlsConfig,
+    #[serde(default)]
+    pub politeness: PolitenessConfig,
+    /// File rate limiter state is saved to, so restarts keep spent quota
+    #[serde(default)]
+    pub rate_limit_state_path: Option<String>,
+}
+
+/// Crawl politeness for sources scraped without a formal API
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolitenessConfig {
+    /// Fetch `robots.txt` per host and refuse disallowed paths
+    #[serde(default)]
+    pub respect_robots_txt: bool,
+    /// Spacing between requests to a host whose robots.txt sets no `Crawl-delay`
+    #[serde(default)]
+    pub default_crawl_delay_seconds: Option<f64>,
+    /// User-Agent overrides keyed by host
+    #[serde(default)]
+    pub user_agents: HashMap<String, String>,
+}
+
+/// Connection reuse settings for the HTTP client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionPoolConfig {
+    /// Idle connections kept open per host
+    pub max_idle_per_host: usize,
+    /// How long an idle connection is kept before being closed
+    pub idle_timeout_seconds: Option<u64>,
+    /// TCP keep-alive probe interval
+    pub tcp_keepalive_seconds: Option<u64>,
+    /// Speak HTTP/2 without negotiation; only for servers known to support it
+    pub http2_prior_knowledge: bool,
+    /// Interval for HTTP/2 keep-alive pings
+    pub http2_keep_alive_interval_seconds: Option<u64>,
+}
+
+impl Default for ConnectionPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 32,
+            idle_timeout_seconds: Some(90),
+            tcp_keepalive_seconds: Some(60),
+            http2_prior_knowledge: false,
+            http2_keep_alive_interval_seconds: None,
+        }
+    }
+}
+
+/// Outbound proxy settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.corp:3128` or `socks5://proxy.corp:1080`
+    pub url: String,
+    /// Hosts, domains (`.corp.example`), or CIDR ranges that bypass the proxy
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Create a proxy configuration without authentication or exclusions
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            no_proxy: Vec::new(),
+            username: None,
+            password: None,
+        }
+    }
+
+    /// Set proxy credentials
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+}
+
+/// TLS settings for registries behind private CAs or requiring mTLS
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// PEM files with additional trusted root certificates
+    pub ca_certificates: Vec<PathBuf>,
+    /// PEM client certificate presented for mutual TLS
+    pub client_certificate: Option<PathBuf>,
+    /// PEM PKCS#8 private key for `client_certificate`
+    pub client_key: Option<PathBuf>,
+    /// Accept any server certificate; only for trusted internal mirrors
+    pub insecure_skip_verify: bool,
+}
+
+/// Token bucket rate limit for a single host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub requests_per_minute: u32,
+    /// Maximum burst size; defaults to `requests_per_minute`
+    #[serde(default)]
+    pub burst: Option<u32>,
+}
+
+/// Per-registry settings for a package manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManagerConfig {
+    /// Host the registry API is served from (e.g. `registry.npmjs.org`)
+    pub host: String,
+    pub rate_limit: RateLimitConfig,
+    /// User-Agent sent to this registry instead of `http.user_agent`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Retry behavior for this registry, overriding the global settings
+    #[serde(default)]
+    pub retry: Option<RegistryRetryConfig>,
+}
+
+/// Retry overrides for one registry; unset fields keep the global value
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegistryRetryConfig {
+    pub max_retries: Option<u32>,
+    /// Response statuses to retry, replacing the default 5xx matrix
+    pub retry_statuses: Option<Vec<u16>>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+    pub multiplier: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +195,40 @@ pub struct LoggingConfig {
     pub output: String,
 }
 
+/// Wire protocol for pushing metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetricsProtocol {
+    /// StatsD lines over UDP to `host:port`
+    Statsd,
+    /// OTLP/HTTP JSON to a collector base URL
+    Otlp,
+}
+
+/// Periodic metrics push settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsExportConfig {
+    pub protocol: MetricsProtocol,
+    /// `host:port` for StatsD, e.g. `127.0.0.1:8125`; base URL for OTLP, e.g. `http://collector:4318`
+    pub endpoint: String,
+    #[serde(default = "default_metrics_interval")]
+    pub interval_seconds: u64,
+    /// Prepended to metric names as `prefix.name`
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// `service.name` resource attribute sent with OTLP metrics
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+fn default_metrics_interval() -> u64 {
+    10
+}
+
+fn default_service_name() -> String {
+    "common-library".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub base_path: String,
@@ -57,9 +246,17 @@ impl Default for AppConfig {
             },
             http: HttpConfig {
                 timeout_seconds: 30,
+                connect_timeout_seconds: Some(10),
+                read_timeout_seconds: Some(30),
+                max_response_bytes: Some(100 * 1024 * 1024),
                 max_retries: 3,
                 rate_limit_per_minute: 60,
                 user_agent: "common-library/0.1.0".to_string(),
+                proxy: None,
+                pool: ConnectionPoolConfig::default(),
+                tls: TlsConfig::default(),
+                politeness: PolitenessConfig::default(),
+                rate_limit_state_path: None,
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
@@ -71,6 +268,8 @@ impl Default for AppConfig {
                 backup_enabled: true,
                 compression_enabled: false,
             },
+            package_managers: HashMap::new(),
+            metrics_export: None,
         }
     }
 }
@@ -140,6 +339,70 @@ impl ConfigManager {
             return Err(Error::config("rate_limit_per_minute must be > 0"));
         }
 
+        if let Some(proxy) = &app_config.http.proxy {
+            let scheme = proxy.url.split("://").next().unwrap_or_default();
+            if !["http", "https", "socks5", "socks5h"].contains(&scheme)
+                || !proxy.url.contains("://")
+            {
+                return Err(Error::config(format!("invalid proxy url: {}", proxy.url)));
+            }
+        }
+
+        if let Some(delay) = app_config.http.politeness.default_crawl_delay_seconds
+            && Duration::try_from_secs_f64(delay).is_err()
+        {
+            return Err(Error::config(format!(
+                "http.politeness.default_crawl_delay_seconds must be finite and >= 0, got {}",
+                delay
+            )));
+        }
+
+        let tls = &app_config.http.tls;
+        if tls.client_certificate.is_some() != tls.client_key.is_some() {
+            return Err(Error::config(
+                "http.tls.client_certificate and http.tls.client_key must be set together",
+            ));
+        }
+
+        for (name, package_manager) in &app_config.package_managers {
+            if package_manager.rate_limit.requests_per_minute == 0 {
+                return Err(Error::config(format!(
+                    "package_managers.{}.rate_limit.requests_per_minute must be > 0",
+                    name
+                )));
+            }
+            if let Some(multiplier) = package_manager.retry.as_ref().and_then(|r| r.multiplier)
+                && !(multiplier.is_finite() && multiplier >= 1.0)
+            {
+                return Err(Error::config(format!(
+                    "package_managers.{}.retry.multiplier must be a finite number >= 1",
+                    name
+                )));
+            }
+        }
+
+        if let Some(export) = &app_config.metrics_export {
+            if export.interval_seconds == 0 {
+                return Err(Error::config("metrics_export.interval_seconds must be > 0"));
+            }
+            let valid = match export.protocol {
+                MetricsProtocol::Statsd => export
+                    .endpoint
+                    .rsplit_once(':')
+                    .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok()),
+                MetricsProtocol::Otlp => {
+                    export.endpoint.starts_with("http://")
+                        || export.endpoint.starts_with("https://")
+                }
+            };
+            if !valid {
+                return Err(Error::config(format!(
+                    "invalid metrics_export.endpoint: {}",
+                    export.endpoint
+                )));
+            }
+        }
+
         // Validate logging configuration
         let valid_levels = ["trace", "debug", "info", "warn", "error"];
         if !valid_levels.contains(&app_config.logging.level.as_str()) {
@@ -195,7 +458,7 @@ impl ConfigManager {
     }
 
     /// Set a configuration value (runtime configuration changes)
-    pub fn set<T>(&mut self, key: &str, value: T) -> Result<()>
+    pub fn set<T>(&mut self, _key: &str, _value: T) -> Result<()>
     where
         T: serde::Serialize,
     {
@@ -207,3 +470,31 @@ impl ConfigManager {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_pool_config_uses_defaults() {
+        // Test: A pool table setting one field keeps the defaults for the rest
+        let pool: ConnectionPoolConfig = Config::builder()
+            .add_source(File::from_str(
+                "max_idle_per_host = 4\nhttp2_prior_knowledge = true",
+                FileFormat::Toml,
+            ))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(pool.max_idle_per_host, 4);
+        assert!(pool.http2_prior_knowledge);
+        assert_eq!(
+            pool.idle_timeout_seconds,
+            ConnectionPoolConfig::default().idle_timeout_seconds,
+            "Unset fields should fall back to defaults"
+        );
+        assert_eq!(pool.tcp_keepalive_seconds, Some(60));
+    }
+}
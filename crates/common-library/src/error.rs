@@ -11,6 +11,9 @@ pub enum Error {
     #[error("HTTP error: {0}")]
     Http(String),
 
+    #[error("HTTP error: response from {url} exceeded {limit} bytes")]
+    ResponseTooLarge { url: String, limit: u64 },
+
     #[error("Database error: {0}")]
     Database(String),
 
@@ -50,6 +53,14 @@ impl Error {
         Self::Http(msg.into())
     }
 
+    /// Create an error for a response body over the configured size limit
+    pub fn response_too_large(url: impl Into<String>, limit: u64) -> Self {
+        Self::ResponseTooLarge {
+            url: url.into(),
+            limit,
+        }
+    }
+
     /// Create a new database error
     pub fn database(msg: impl Into<String>) -> Self {
         Self::Database(msg.into())
@@ -97,6 +108,12 @@ mod tests {
         let http_error = Error::http("test http error");
         assert!(matches!(http_error, Error::Http(_)));
 
+        let too_large_error = Error::response_too_large("https://example.com", 10);
+        assert!(matches!(
+            too_large_error,
+            Error::ResponseTooLarge { limit: 10, .. }
+        ));
+
         let database_error = Error::database("test database error");
         assert!(matches!(database_error, Error::Database(_)));
 
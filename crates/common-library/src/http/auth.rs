@@ -0,0 +1,921 @@
+//! Authentication for HTTP requests
+//!
+//! Supports static tokens, tokens read from a file or command that are
+//! re-read when rejected, OAuth2 (client credentials and device code
+//! flows) with automatic refresh before the access token expires, and
+//! per-request signing with AWS SigV4 or HMAC.
+
+use super::client::{HttpClientConfig, build_http_client};
+use super::signing::{self, HmacSigningConfig, SigV4Config};
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Cap on lifetimes and intervals sent by servers, so huge values cannot overflow time arithmetic
+const MAX_SERVER_DURATION: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Shown in place of secrets by `Debug` implementations
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Authentication configuration
+#[derive(Clone, Default)]
+pub enum AuthConfig {
+    /// No authentication
+    #[default]
+    None,
+    /// Static bearer token
+    Bearer(String),
+    /// Static token using the `token <value>` scheme (GitHub personal tokens)
+    Token(String),
+    /// Bearer token read from a rotating source
+    BearerFrom(TokenSource),
+    /// `token <value>` scheme with a token read from a rotating source
+    TokenFrom(TokenSource),
+    /// OAuth2 client credentials grant
+    OAuth2ClientCredentials(OAuth2Config),
+    /// OAuth2 device authorization grant
+    OAuth2DeviceCode(OAuth2Config),
+    /// AWS Signature Version 4, e.g. for S3-compatible storage
+    SigV4(SigV4Config),
+    /// HMAC-SHA256 request signature with a shared secret
+    Hmac(HmacSigningConfig),
+}
+
+/// Source of a token that may be rotated while the process runs
+///
+/// The token is read on first use and re-read whenever a request is
+/// rejected with 401.
+#[derive(Clone)]
+pub enum TokenSource {
+    /// File containing the token, e.g. a mounted secret
+    File(PathBuf),
+    /// Program and arguments printing the token on stdout
+    Command(Vec<String>),
+}
+
+impl TokenSource {
+    /// Read the current token, trimming surrounding whitespace
+    pub async fn read(&self) -> Result<String> {
+        let token = match self {
+            TokenSource::File(path) => tokio::fs::read_to_string(path).await.map_err(|e| {
+                Error::config(format!(
+                    "Failed to read token file {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            TokenSource::Command(command) => {
+                let (program, args) = command
+                    .split_first()
+                    .ok_or_else(|| Error::config("Token command is empty"))?;
+                let output = tokio::process::Command::new(program)
+                    .args(args)
+                    .output()
+                    .await
+                    .map_err(|e| {
+                        Error::config(format!("Failed to run token command {}: {}", program, e))
+                    })?;
+                if !output.status.success() {
+                    return Err(Error::config(format!(
+                        "Token command {} exited with {}",
+                        program, output.status
+                    )));
+                }
+                String::from_utf8(output.stdout)
+                    .map_err(|_| Error::config("Token command printed invalid UTF-8"))?
+            }
+        };
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(Error::config("Token source returned an empty token"));
+        }
+        Ok(token.to_string())
+    }
+}
+
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthConfig::None => f.write_str("None"),
+            AuthConfig::Bearer(_) => f.debug_tuple("Bearer").field(&REDACTED).finish(),
+            AuthConfig::Token(_) => f.debug_tuple("Token").field(&REDACTED).finish(),
+            AuthConfig::BearerFrom(source) => f.debug_tuple("BearerFrom").field(source).finish(),
+            AuthConfig::TokenFrom(source) => f.debug_tuple("TokenFrom").field(source).finish(),
+            AuthConfig::OAuth2ClientCredentials(oauth) => f
+                .debug_tuple("OAuth2ClientCredentials")
+                .field(oauth)
+                .finish(),
+            AuthConfig::OAuth2DeviceCode(oauth) => {
+                f.debug_tuple("OAuth2DeviceCode").field(oauth).finish()
+            }
+            AuthConfig::SigV4(config) => f.debug_tuple("SigV4").field(config).finish(),
+            AuthConfig::Hmac(config) => f.debug_tuple("Hmac").field(config).finish(),
+        }
+    }
+}
+
+/// Command arguments may carry credentials, so only the program is shown
+impl fmt::Debug for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            TokenSource::Command(command) => f
+                .debug_struct("Command")
+                .field("program", &command.first())
+                .field("args", &REDACTED)
+                .finish(),
+        }
+    }
+}
+
+/// OAuth2 client configuration
+#[derive(Clone)]
+pub struct OAuth2Config {
+    pub token_url: String,
+    /// Required for the device code flow
+    pub device_authorization_url: Option<String>,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    /// File used to persist tokens between runs
+    pub token_store: Option<PathBuf>,
+    /// Refresh the access token this long before it expires
+    pub refresh_margin: Duration,
+}
+
+impl OAuth2Config {
+    /// Create a configuration for the given token endpoint and client
+    pub fn new(token_url: impl Into<String>, client_id: impl Into<String>) -> Self {
+        Self {
+            token_url: token_url.into(),
+            device_authorization_url: None,
+            client_id: client_id.into(),
+            client_secret: None,
+            scopes: Vec::new(),
+            token_store: None,
+            refresh_margin: Duration::from_secs(60),
+        }
+    }
+}
+
+impl fmt::Debug for OAuth2Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Config")
+            .field("token_url", &self.token_url)
+            .field("device_authorization_url", &self.device_authorization_url)
+            .field("client_id", &self.client_id)
+            .field(
+                "client_secret",
+                &self.client_secret.as_ref().map(|_| REDACTED),
+            )
+            .field("scopes", &self.scopes)
+            .field("token_store", &self.token_store)
+            .field("refresh_margin", &self.refresh_margin)
+            .finish()
+    }
+}
+
+/// An OAuth2 access token
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OAuth2Token {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub token_type: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl OAuth2Token {
+    /// Check whether the token expires within `margin`
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => {
+                let margin = chrono::Duration::from_std(margin).unwrap_or(chrono::Duration::zero());
+                Utc::now() + margin >= expires_at
+            }
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for OAuth2Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OAuth2Token")
+            .field("access_token", &REDACTED)
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| REDACTED),
+            )
+            .field("token_type", &self.token_type)
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Device authorization details to show to the user
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    #[serde(default = "default_poll_interval")]
+    pub interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+/// Callback used to show the device code to the user
+pub type DevicePrompt = Arc<dyn Fn(&DeviceAuthorization) + Send + Sync>;
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    token_type: Option<String>,
+    expires_in: Option<u64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Manages credentials and produces `Authorization` headers
+pub struct AuthManager {
+    config: AuthConfig,
+    client: reqwest::Client,
+    token: Mutex<Option<OAuth2Token>>,
+    /// Last token read from a `TokenSource`
+    source_token: Mutex<Option<String>>,
+    device_prompt: Option<DevicePrompt>,
+    /// Total time allowed for each token endpoint request
+    timeout: Duration,
+}
+
+impl AuthManager {
+    /// Create a new authentication manager
+    ///
+    /// Token endpoints are called with default HTTP settings; use
+    /// [`AuthManager::with_http_config`] to apply a proxy, TLS, or timeouts.
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            token: Mutex::new(None),
+            source_token: Mutex::new(None),
+            device_prompt: None,
+            timeout: HttpClientConfig::default().timeout,
+        }
+    }
+
+    /// Call token endpoints with the same connection settings as an [`APIClient`]
+    ///
+    /// [`APIClient`]: super::APIClient
+    pub fn with_http_config(self, http: &HttpClientConfig) -> Result<Self> {
+        Ok(self.with_client(build_http_client(http)?, http.timeout))
+    }
+
+    /// Share an already configured HTTP client for token requests
+    pub(crate) fn with_client(mut self, client: reqwest::Client, timeout: Duration) -> Self {
+        self.client = client;
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the callback that shows the device code during the device flow
+    pub fn with_device_prompt(mut self, prompt: DevicePrompt) -> Self {
+        self.device_prompt = Some(prompt);
+        self
+    }
+
+    /// Get the authentication configuration
+    pub fn config(&self) -> &AuthConfig {
+        &self.config
+    }
+
+    /// Add authentication headers to a request about to be sent
+    ///
+    /// Signing schemes cover the method, URL, and body, so this must be
+    /// called again for every attempt.
+    pub async fn authorize(
+        &self,
+        method: &Method,
+        url: &str,
+        headers: &mut HeaderMap,
+        body: &[u8],
+    ) -> Result<()> {
+        match &self.config {
+            AuthConfig::SigV4(config) => {
+                signing::sign_sigv4(config, method, url, headers, body, Utc::now())
+            }
+            AuthConfig::Hmac(config) => {
+                signing::sign_hmac(config, method, url, headers, body, Utc::now())
+            }
+            _ => {
+                if let Some(value) = self.authorization_header().await? {
+                    let value = HeaderValue::from_str(&value)
+                        .map_err(|e| Error::http(format!("Invalid authorization header: {}", e)))?;
+                    headers.insert(AUTHORIZATION, value);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the `Authorization` header value, obtaining or refreshing tokens as needed
+    ///
+    /// Returns `None` for signing schemes, which depend on the request; use
+    /// [`AuthManager::authorize`] for those.
+    pub async fn authorization_header(&self) -> Result<Option<String>> {
+        match &self.config {
+            AuthConfig::None | AuthConfig::SigV4(_) | AuthConfig::Hmac(_) => Ok(None),
+            AuthConfig::Bearer(token) => Ok(Some(format!("Bearer {}", token))),
+            AuthConfig::Token(token) => Ok(Some(format!("token {}", token))),
+            AuthConfig::BearerFrom(source) => {
+                Ok(Some(format!("Bearer {}", self.source_token(source).await?)))
+            }
+            AuthConfig::TokenFrom(source) => {
+                Ok(Some(format!("token {}", self.source_token(source).await?)))
+            }
+            AuthConfig::OAuth2ClientCredentials(oauth) | AuthConfig::OAuth2DeviceCode(oauth) => {
+                let token = self.valid_token(oauth).await?;
+                Ok(Some(format!("Bearer {}", token.access_token)))
+            }
+        }
+    }
+
+    /// Discard the current token after the server rejected it
+    ///
+    /// Returns `true` when a new token can be obtained, i.e. retrying the
+    /// request is worthwhile.
+    pub async fn invalidate(&self) -> bool {
+        match &self.config {
+            AuthConfig::OAuth2ClientCredentials(_) | AuthConfig::OAuth2DeviceCode(_) => {
+                let mut token = self.token.lock().await;
+                if let Some(current) = token.as_mut() {
+                    current.expires_at = Some(Utc::now());
+                }
+                true
+            }
+            AuthConfig::BearerFrom(_) | AuthConfig::TokenFrom(_) => {
+                *self.source_token.lock().await = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the current OAuth2 token, if any
+    pub async fn current_token(&self) -> Option<OAuth2Token> {
+        self.token.lock().await.clone()
+    }
+
+    async fn source_token(&self, source: &TokenSource) -> Result<String> {
+        let mut guard = self.source_token.lock().await;
+        if let Some(token) = guard.as_ref() {
+            return Ok(token.clone());
+        }
+        debug!("Reading token from {:?}", source);
+        let token = source.read().await?;
+        *guard = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn valid_token(&self, oauth: &OAuth2Config) -> Result<OAuth2Token> {
+        let mut guard = self.token.lock().await;
+
+        if guard.is_none() {
+            *guard = load_token(oauth)?;
+        }
+
+        if let Some(token) = guard.as_ref()
+            && !token.expires_within(oauth.refresh_margin)
+        {
+            return Ok(token.clone());
+        }
+
+        let refreshed = match guard.as_ref().and_then(|t| t.refresh_token.clone()) {
+            Some(refresh_token) => match self.refresh_token(oauth, &refresh_token).await {
+                Ok(token) => Some(token),
+                Err(e) => {
+                    debug!("Token refresh failed, requesting a new token: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let token = match refreshed {
+            Some(token) => token,
+            None => match &self.config {
+                AuthConfig::OAuth2DeviceCode(_) => self.device_code_flow(oauth).await?,
+                _ => self.client_credentials_flow(oauth).await?,
+            },
+        };
+
+        save_token(oauth, &token)?;
+        *guard = Some(token.clone());
+        Ok(token)
+    }
+
+    async fn client_credentials_flow(&self, oauth: &OAuth2Config) -> Result<OAuth2Token> {
+        let mut params = vec![
+            ("grant_type", "client_credentials".to_string()),
+            ("client_id", oauth.client_id.clone()),
+        ];
+        if let Some(secret) = &oauth.client_secret {
+            params.push(("client_secret", secret.clone()));
+        }
+        if !oauth.scopes.is_empty() {
+            params.push(("scope", oauth.scopes.join(" ")));
+        }
+
+        let response = self.request_token(oauth, &params).await?;
+        into_token(response, None)
+    }
+
+    async fn refresh_token(
+        &self,
+        oauth: &OAuth2Config,
+        refresh_token: &str,
+    ) -> Result<OAuth2Token> {
+        let mut params = vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("refresh_token", refresh_token.to_string()),
+            ("client_id", oauth.client_id.clone()),
+        ];
+        if let Some(secret) = &oauth.client_secret {
+            params.push(("client_secret", secret.clone()));
+        }
+
+        let response = self.request_token(oauth, &params).await?;
+        into_token(response, Some(refresh_token))
+    }
+
+    async fn device_code_flow(&self, oauth: &OAuth2Config) -> Result<OAuth2Token> {
+        let device_url = oauth
+            .device_authorization_url
+            .as_deref()
+            .ok_or_else(|| Error::config("device_authorization_url is required for device flow"))?;
+
+        let mut params = vec![("client_id", oauth.client_id.clone())];
+        if !oauth.scopes.is_empty() {
+            params.push(("scope", oauth.scopes.join(" ")));
+        }
+        let authorization: DeviceAuthorization = self
+            .client
+            .post(device_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&params)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| Error::http(format!("Device authorization request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| Error::http(format!("Invalid device authorization response: {}", e)))?;
+
+        match &self.device_prompt {
+            Some(prompt) => prompt(&authorization),
+            None => info!(
+                "To authorize, visit {} and enter code {}",
+                authorization.verification_uri, authorization.user_code
+            ),
+        }
+
+        let deadline = tokio::time::Instant::now()
+            .checked_add(server_duration(authorization.expires_in))
+            .ok_or_else(|| Error::http("Device code lifetime is out of range"))?;
+        let mut interval = server_duration(authorization.interval);
+        let poll_params = vec![
+            ("grant_type", DEVICE_CODE_GRANT.to_string()),
+            ("device_code", authorization.device_code.clone()),
+            ("client_id", oauth.client_id.clone()),
+        ];
+
+        loop {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(Error::http("Device code expired before authorization"));
+            }
+
+            let response = self.request_token(oauth, &poll_params).await?;
+            match response.error.as_deref() {
+                None => return into_token(response, None),
+                Some("authorization_pending") => {}
+                Some("slow_down") => {
+                    interval = (interval + Duration::from_secs(5)).min(MAX_SERVER_DURATION)
+                }
+                Some(error) => {
+                    return Err(Error::http(format!(
+                        "Device authorization failed: {}",
+                        response.error_description.as_deref().unwrap_or(error)
+                    )));
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    async fn request_token(
+        &self,
+        oauth: &OAuth2Config,
+        params: &[(&str, String)],
+    ) -> Result<TokenResponse> {
+        self.client
+            .post(&oauth.token_url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(params)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| Error::http(format!("Token request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| Error::http(format!("Invalid token response: {}", e)))
+    }
+}
+
+impl fmt::Debug for AuthManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match &self.config {
+            AuthConfig::None => "none",
+            AuthConfig::Bearer(_) => "bearer",
+            AuthConfig::Token(_) => "token",
+            AuthConfig::BearerFrom(_) => "bearer-from-source",
+            AuthConfig::TokenFrom(_) => "token-from-source",
+            AuthConfig::OAuth2ClientCredentials(_) => "oauth2-client-credentials",
+            AuthConfig::OAuth2DeviceCode(_) => "oauth2-device-code",
+            AuthConfig::SigV4(_) => "sigv4",
+            AuthConfig::Hmac(_) => "hmac",
+        };
+        f.debug_struct("AuthManager")
+            .field("kind", &kind)
+            .finish_non_exhaustive()
+    }
+}
+
+fn into_token(response: TokenResponse, previous_refresh: Option<&str>) -> Result<OAuth2Token> {
+    if let Some(error) = response.error {
+        return Err(Error::http(format!(
+            "Token endpoint returned error: {}",
+            response.error_description.unwrap_or(error)
+        )));
+    }
+    let access_token = response
+        .access_token
+        .ok_or_else(|| Error::http("Token response is missing access_token"))?;
+
+    Ok(OAuth2Token {
+        access_token,
+        refresh_token: response
+            .refresh_token
+            .or_else(|| previous_refresh.map(str::to_string)),
+        token_type: response.token_type.unwrap_or_else(|| "bearer".to_string()),
+        expires_at: response.expires_in.and_then(|secs| {
+            let lifetime = chrono::Duration::try_seconds(server_duration(secs).as_secs() as i64)?;
+            Utc::now().checked_add_signed(lifetime)
+        }),
+    })
+}
+
+/// A server-provided number of seconds, capped at [`MAX_SERVER_DURATION`]
+fn server_duration(secs: u64) -> Duration {
+    Duration::from_secs(secs).min(MAX_SERVER_DURATION)
+}
+
+fn load_token(oauth: &OAuth2Config) -> Result<Option<OAuth2Token>> {
+    match &oauth.token_store {
+        Some(path) if path.exists() => {
+            let data = std::fs::read(path)?;
+            Ok(Some(serde_json::from_slice(&data)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn save_token(oauth: &OAuth2Config, token: &OAuth2Token) -> Result<()> {
+    if let Some(path) = &oauth.token_store {
+        if let Some(parent) = path.parent() {
+            crate::utils::fs::ensure_dir(parent)?;
+        }
+        crate::utils::fs::write_file_atomic(path, &serde_json::to_vec_pretty(token)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_static_tokens() {
+        // Test: Static tokens produce the expected header schemes
+        let bearer = AuthManager::new(AuthConfig::Bearer("abc".to_string()));
+        assert_eq!(
+            bearer.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer abc")
+        );
+
+        let token = AuthManager::new(AuthConfig::Token("abc".to_string()));
+        assert_eq!(
+            token.authorization_header().await.unwrap().as_deref(),
+            Some("token abc")
+        );
+
+        let none = AuthManager::new(AuthConfig::None);
+        assert!(none.authorization_header().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_token_source() {
+        // Test: Command output is trimmed and cached until invalidated
+        let source = TokenSource::Command(vec!["echo".to_string(), "rotated".to_string()]);
+        let auth = AuthManager::new(AuthConfig::TokenFrom(source));
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("token rotated")
+        );
+        assert!(auth.invalidate().await, "Source tokens can be re-read");
+
+        let failing = AuthManager::new(AuthConfig::BearerFrom(TokenSource::Command(vec![
+            "false".to_string(),
+        ])));
+        assert!(failing.authorization_header().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_signing_schemes_authorize_each_request() {
+        // Test: Signing configurations add request-specific headers instead of a static token
+        let auth = AuthManager::new(AuthConfig::SigV4(SigV4Config::s3(
+            "AKIDEXAMPLE",
+            "secret",
+            "us-east-1",
+        )));
+        assert!(auth.authorization_header().await.unwrap().is_none());
+
+        let mut headers = HeaderMap::new();
+        auth.authorize(
+            &Method::PUT,
+            "https://bucket.s3.amazonaws.com/snapshots/npm.json",
+            &mut headers,
+            b"{}",
+        )
+        .await
+        .unwrap();
+        let authorization = headers[AUTHORIZATION].to_str().unwrap();
+        assert!(authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(
+            headers.contains_key("x-amz-content-sha256"),
+            "S3 requests should carry the payload hash"
+        );
+
+        let bearer = AuthManager::new(AuthConfig::Bearer("abc".to_string()));
+        let mut headers = HeaderMap::new();
+        bearer
+            .authorize(&Method::GET, "https://example.com", &mut headers, b"")
+            .await
+            .unwrap();
+        assert_eq!(headers[AUTHORIZATION], "Bearer abc");
+    }
+
+    #[tokio::test]
+    async fn test_client_credentials_refresh_and_persistence() {
+        // Test: Tokens are obtained, refreshed before expiry, and persisted
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=client_credentials"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "first",
+                "refresh_token": "refresh-1",
+                "token_type": "bearer",
+                "expires_in": 30
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "second",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let store = std::env::temp_dir().join(format!(
+            "oauth-token-{}.json",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let mut oauth = OAuth2Config::new(format!("{}/token", server.uri()), "client");
+        oauth.client_secret = Some("secret".to_string());
+        oauth.token_store = Some(store.clone());
+        oauth.refresh_margin = Duration::from_secs(0);
+
+        let auth = AuthManager::new(AuthConfig::OAuth2ClientCredentials(oauth.clone()));
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer first")
+        );
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer first"),
+            "Valid token should be reused"
+        );
+
+        // A larger margin than the remaining lifetime forces a refresh
+        oauth.refresh_margin = Duration::from_secs(120);
+        let auth = AuthManager::new(AuthConfig::OAuth2ClientCredentials(oauth));
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer second"),
+            "Persisted token close to expiry should be refreshed"
+        );
+        let token = auth.current_token().await.unwrap();
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh-1"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&store).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "Token store should be owner-only");
+        }
+        std::fs::remove_file(store).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_token_requests_use_http_config() {
+        // Test: Token endpoints are reached through the configured proxy
+        let proxy = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "proxied",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let oauth = OAuth2Config::new("http://auth.invalid/token", "client");
+        let http = HttpClientConfig {
+            proxy: Some(crate::config::ProxyConfig::new(proxy.uri())),
+            ..HttpClientConfig::default()
+        };
+        let auth = AuthManager::new(AuthConfig::OAuth2ClientCredentials(oauth))
+            .with_http_config(&http)
+            .unwrap();
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer proxied")
+        );
+    }
+
+    #[test]
+    fn test_debug_output_redacts_secrets() {
+        // Test: Tokens, client secrets, and signing keys never appear in Debug output
+        let mut oauth = OAuth2Config::new("https://auth.example/token", "client");
+        oauth.client_secret = Some("hunter2".to_string());
+        let token = OAuth2Token {
+            access_token: "hunter2".to_string(),
+            refresh_token: Some("hunter2".to_string()),
+            token_type: "bearer".to_string(),
+            expires_at: None,
+        };
+        let command =
+            TokenSource::Command(vec!["vault".to_string(), "--token=hunter2".to_string()]);
+        let rendered = [
+            format!("{:?}", AuthConfig::Bearer("hunter2".to_string())),
+            format!("{:?}", AuthConfig::OAuth2ClientCredentials(oauth)),
+            format!("{:?}", AuthConfig::TokenFrom(command)),
+            format!("{:?}", token),
+            format!(
+                "{:?}",
+                AuthConfig::SigV4(SigV4Config::s3("AKID", "hunter2", "us-east-1"))
+            ),
+            format!("{:?}", AuthConfig::Hmac(HmacSigningConfig::new("hunter2"))),
+        ];
+        for output in rendered {
+            assert!(!output.contains("hunter2"), "Secret leaked: {}", output);
+            assert!(
+                output.contains(REDACTED),
+                "Secret should be marked: {}",
+                output
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_device_code_flow() {
+        // Test: The device flow polls until authorization completes
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/device"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "dev",
+                "user_code": "ABCD-1234",
+                "verification_uri": "https://example.com/device",
+                "expires_in": 60,
+                "interval": 0
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(serde_json::json!({"error": "authorization_pending"})),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"access_token": "device-token"})),
+            )
+            .mount(&server)
+            .await;
+
+        let mut oauth = OAuth2Config::new(format!("{}/token", server.uri()), "client");
+        oauth.device_authorization_url = Some(format!("{}/device", server.uri()));
+
+        let prompted = Arc::new(std::sync::Mutex::new(None));
+        let seen = prompted.clone();
+        let auth = AuthManager::new(AuthConfig::OAuth2DeviceCode(oauth)).with_device_prompt(
+            Arc::new(move |d: &DeviceAuthorization| {
+                *seen.lock().unwrap() = Some(d.user_code.clone());
+            }),
+        );
+
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer device-token")
+        );
+        assert_eq!(prompted.lock().unwrap().as_deref(), Some("ABCD-1234"));
+    }
+
+    #[tokio::test]
+    async fn test_huge_server_lifetimes_are_capped() {
+        // Test: Absurd expires_in and interval values neither panic nor overflow
+        let token = into_token(
+            TokenResponse {
+                access_token: Some("long-lived".to_string()),
+                refresh_token: None,
+                token_type: None,
+                expires_in: Some(u64::MAX),
+                error: None,
+                error_description: None,
+            },
+            None,
+        )
+        .unwrap();
+        let expires_at = token
+            .expires_at
+            .expect("Expiry should be capped, not dropped");
+        assert!(expires_at <= Utc::now() + chrono::Duration::days(366));
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/device"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "device_code": "dev",
+                "user_code": "ABCD-1234",
+                "verification_uri": "https://example.com/device",
+                "expires_in": u64::MAX,
+                "interval": u64::MAX
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "device-token",
+                "expires_in": u64::MAX
+            })))
+            .mount(&server)
+            .await;
+
+        let mut oauth = OAuth2Config::new(format!("{}/token", server.uri()), "client");
+        oauth.device_authorization_url = Some(format!("{}/device", server.uri()));
+        let auth = AuthManager::new(AuthConfig::OAuth2DeviceCode(oauth))
+            .with_device_prompt(Arc::new(|_: &DeviceAuthorization| {}));
+        assert_eq!(
+            auth.authorization_header().await.unwrap().as_deref(),
+            Some("Bearer device-token")
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Bounded-concurrency batch requests
+
+use super::client::{APIClient, ApiResponse};
+use crate::error::Result;
+use futures::StreamExt;
+
+impl APIClient {
+    /// GET many URLs with at most `concurrency` requests in flight
+    ///
+    /// Every request still goes through the host rate limiter, retries, and
+    /// cache. Results are returned in the order of `urls`; a failed request
+    /// does not abort the rest of the batch.
+    pub async fn get_many<I, S>(&self, urls: I, concurrency: usize) -> Vec<Result<ApiResponse>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        futures::stream::iter(urls)
+            .map(|url| async move { self.get(url.as_ref()).await })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use crate::http::retry::RetryConfig;
+    use std::time::{Duration, Instant};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_client() -> APIClient {
+        APIClient::new(HttpClientConfig {
+            rate_limit_per_minute: 6000,
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_many_preserves_order_with_partial_failures() {
+        // Test: Results follow input order and failures do not abort the batch
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("slow")
+                    .set_delay(Duration::from_millis(50)),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/fast"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("fast"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let urls: Vec<String> = ["slow", "missing", "fast"]
+            .iter()
+            .map(|p| format!("{}/{}", server.uri(), p))
+            .collect();
+        let results = test_client().get_many(&urls, 3).await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().text().unwrap(), "slow");
+        assert!(results[1].is_err(), "404 should be reported in place");
+        assert_eq!(results[2].as_ref().unwrap().text().unwrap(), "fast");
+    }
+
+    #[tokio::test]
+    async fn test_get_many_runs_requests_concurrently() {
+        // Test: Requests overlap up to the concurrency limit
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(100)))
+            .mount(&server)
+            .await;
+
+        let urls: Vec<String> = (0..4)
+            .map(|i| format!("{}/pkg/{}", server.uri(), i))
+            .collect();
+        let started = Instant::now();
+        let results = test_client().get_many(&urls, 4).await;
+
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert!(
+            started.elapsed() < Duration::from_millis(350),
+            "Four concurrent 100ms requests should not run serially"
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Response caching for conditional HTTP requests
+//!
+//! Cached responses are keyed by URL and carry the validators (`ETag`,
+//! `Last-Modified`) needed to issue `If-None-Match` / `If-Modified-Since`
+//! requests. For a cache that survives restarts, use a [`StoreCache`] over a
+//! [`FileCacheStore`](crate::storage::FileCacheStore).
+
+use crate::error::{Error, Result};
+use crate::storage::cache::base64_bytes;
+use crate::storage::{CacheStore, CacheStoreExt};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// A cached response body together with its validators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    #[serde(with = "base64_bytes")]
+    pub body: Vec<u8>,
+    pub stored_at: DateTime<Utc>,
+}
+
+impl CacheEntry {
+    /// Check whether the entry has any validator usable for a conditional request
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+}
+
+/// Pluggable storage backend for cached responses
+pub trait ResponseCache: Send + Sync {
+    /// Look up the cached entry for a URL
+    fn get(&self, url: &str) -> Result<Option<CacheEntry>>;
+
+    /// Store an entry, replacing any previous entry for the same URL
+    fn put(&self, entry: CacheEntry) -> Result<()>;
+
+    /// Remove the entry for a URL
+    fn remove(&self, url: &str) -> Result<()>;
+
+    /// Remove all entries
+    fn clear(&self) -> Result<()>;
+}
+
+/// In-memory response cache
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: RwLock<HashMap<String, CacheEntry>>,
+}
+
+impl MemoryCache {
+    /// Create an empty in-memory cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Check whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, url: &str) -> Result<Option<CacheEntry>> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| Error::http("response cache lock poisoned"))?;
+        Ok(entries.get(url).cloned())
+    }
+
+    fn put(&self, entry: CacheEntry) -> Result<()> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| Error::http("response cache lock poisoned"))?;
+        entries.insert(entry.url.clone(), entry);
+        Ok(())
+    }
+
+    fn remove(&self, url: &str) -> Result<()> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| Error::http("response cache lock poisoned"))?;
+        entries.remove(url);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| Error::http("response cache lock poisoned"))?;
+        entries.clear();
+        Ok(())
+    }
+}
+
+/// Response cache backed by a [`CacheStore`], expiring entries after `ttl`
+#[derive(Clone)]
+pub struct StoreCache {
+    store: Arc<dyn CacheStore>,
+    ttl: Option<Duration>,
+}
+
+impl StoreCache {
+    /// Cache responses in `store`, keeping them for `ttl` if given
+    pub fn new(store: Arc<dyn CacheStore>, ttl: Option<Duration>) -> Self {
+        Self { store, ttl }
+    }
+}
+
+impl std::fmt::Debug for StoreCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreCache")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResponseCache for StoreCache {
+    fn get(&self, url: &str) -> Result<Option<CacheEntry>> {
+        self.store.get_json(url)
+    }
+
+    fn put(&self, entry: CacheEntry) -> Result<()> {
+        self.store.set_json(&entry.url, &entry, self.ttl)
+    }
+
+    fn remove(&self, url: &str) -> Result<()> {
+        self.store.delete(url)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.store.clear()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str) -> CacheEntry {
+        CacheEntry {
+            url: url.to_string(),
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: b"{\"ok\":true}".to_vec(),
+            stored_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_memory_cache_roundtrip() {
+        // Test: Memory cache stores, returns and removes entries
+        let cache = MemoryCache::new();
+        cache.put(entry("https://example.com/a")).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        let cached = cache.get("https://example.com/a").unwrap();
+        assert_eq!(cached.unwrap().etag.as_deref(), Some("\"abc\""));
+
+        cache.remove("https://example.com/a").unwrap();
+        assert!(cache.is_empty(), "Entry should be removed");
+    }
+
+    #[test]
+    fn test_store_cache_expires_entries() {
+        // Test: Responses cached in a store disappear once their TTL has passed
+        let store = Arc::new(crate::storage::MemoryCacheStore::new());
+        let fresh = StoreCache::new(store.clone(), None);
+        fresh.put(entry("https://example.com/c")).unwrap();
+        let cached = fresh.get("https://example.com/c").unwrap().unwrap();
+        assert_eq!(cached.etag.as_deref(), Some("\"abc\""));
+
+        let expiring = StoreCache::new(store, Some(Duration::ZERO));
+        expiring.put(entry("https://example.com/d")).unwrap();
+        assert!(expiring.get("https://example.com/d").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_file_store_cache_persists_and_skips_corrupt_entries() {
+        // Test: A file-backed store cache survives reopening and treats corrupt files as misses
+        use crate::storage::FileCacheStore;
+
+        let dir = std::env::temp_dir().join(format!(
+            "http-cache-{}",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let cache = StoreCache::new(Arc::new(FileCacheStore::new(&dir).unwrap()), None);
+        cache.put(entry("https://example.com/b")).unwrap();
+
+        let store = Arc::new(FileCacheStore::new(&dir).unwrap());
+        let reopened = StoreCache::new(store.clone(), None);
+        let cached = reopened.get("https://example.com/b").unwrap().unwrap();
+        assert_eq!(cached.body, b"{\"ok\":true}".to_vec());
+        assert!(reopened.get("https://example.com/other").unwrap().is_none());
+
+        for file in std::fs::read_dir(&dir).unwrap() {
+            std::fs::write(file.unwrap().path(), b"{truncated").unwrap();
+        }
+        assert!(
+            reopened.get("https://example.com/b").unwrap().is_none(),
+            "Corrupt entries should be misses"
+        );
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            0,
+            "Corrupt entry should be removed"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
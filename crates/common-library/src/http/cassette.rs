@@ -0,0 +1,359 @@
+//! Record-and-replay of HTTP interactions
+//!
+//! In record mode every buffered response is appended to a JSON cassette
+//! file after secrets have been scrubbed. In replay mode requests are
+//! answered from the cassette without touching the network, so collector
+//! tests can run deterministically in CI against real registry payloads.
+
+use super::client::ApiResponse;
+use super::middleware::MiddlewareRequest;
+use crate::error::{Error, Result};
+use crate::storage::cache::base64_bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Placeholder written in place of scrubbed values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// How the client uses its cassette
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CassetteMode {
+    /// Send requests normally and append each interaction to the cassette
+    Record,
+    /// Answer requests from the cassette and fail on unrecorded requests
+    Replay,
+    /// Ignore the cassette entirely
+    #[default]
+    Passthrough,
+}
+
+/// Cassette configuration for [`HttpClientConfig`](super::HttpClientConfig)
+#[derive(Debug, Clone)]
+pub struct CassetteConfig {
+    pub path: PathBuf,
+    pub mode: CassetteMode,
+    /// Header names whose values are redacted (case-insensitive)
+    pub scrub_headers: Vec<String>,
+    /// Query parameters whose values are redacted
+    pub scrub_query_params: Vec<String>,
+    /// Literal secret values redacted wherever they appear
+    pub secrets: Vec<String>,
+}
+
+impl CassetteConfig {
+    /// Create a configuration with the default scrubbing rules
+    pub fn new(path: impl Into<PathBuf>, mode: CassetteMode) -> Self {
+        Self {
+            path: path.into(),
+            mode,
+            scrub_headers: [
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+                "x-api-key",
+                "x-auth-token",
+            ]
+            .map(String::from)
+            .to_vec(),
+            scrub_query_params: [
+                "access_token",
+                "api_key",
+                "apikey",
+                "client_secret",
+                "key",
+                "token",
+            ]
+            .map(String::from)
+            .to_vec(),
+            secrets: Vec::new(),
+        }
+    }
+
+    /// Redact an additional literal secret
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secrets.push(secret.into());
+        self
+    }
+}
+
+/// A recorded request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// A recorded response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    #[serde(with = "base64_bytes")]
+    pub body: Vec<u8>,
+}
+
+/// One request/response pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+/// A cassette bound to a file
+#[derive(Debug)]
+pub struct Cassette {
+    config: CassetteConfig,
+    interactions: Mutex<Vec<Interaction>>,
+    /// Replay cursor per `METHOD url` key
+    cursors: Mutex<HashMap<String, usize>>,
+}
+
+impl Cassette {
+    /// Open a cassette
+    ///
+    /// Replay mode requires the file to exist; record mode starts a fresh
+    /// cassette, overwriting any previous recording.
+    pub fn open(config: CassetteConfig) -> Result<Self> {
+        let interactions = match config.mode {
+            CassetteMode::Replay => {
+                let content = std::fs::read_to_string(&config.path).map_err(|e| {
+                    Error::http(format!(
+                        "Failed to read cassette {}: {}",
+                        config.path.display(),
+                        e
+                    ))
+                })?;
+                serde_json::from_str::<CassetteFile>(&content)?.interactions
+            }
+            CassetteMode::Record | CassetteMode::Passthrough => Vec::new(),
+        };
+        Ok(Self {
+            config,
+            interactions: Mutex::new(interactions),
+            cursors: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The cassette mode
+    pub fn mode(&self) -> CassetteMode {
+        self.config.mode
+    }
+
+    /// Recorded interactions
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.interactions.lock().unwrap().clone()
+    }
+
+    /// Answer a request from the cassette
+    ///
+    /// Repeated requests for the same URL are answered in recording order;
+    /// once exhausted, the last recorded response is reused.
+    pub fn replay(&self, request: &MiddlewareRequest) -> Result<ApiResponse> {
+        let method = request.method.to_string();
+        let url = self.scrub(&self.scrub_url(&request.url));
+        let key = format!("{} {}", method, url);
+
+        let interactions = self.interactions.lock().unwrap();
+        let matches: Vec<&Interaction> = interactions
+            .iter()
+            .filter(|i| i.request.method == method && i.request.url == url)
+            .collect();
+        if matches.is_empty() {
+            return Err(Error::http(format!(
+                "No recorded interaction for {} in cassette {}",
+                key,
+                self.config.path.display()
+            )));
+        }
+
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(key).or_insert(0);
+        let interaction = matches[(*cursor).min(matches.len() - 1)];
+        *cursor += 1;
+        debug!("Replaying {} {} from cassette", method, url);
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &interaction.response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.append(name, value);
+            }
+        }
+        Ok(ApiResponse {
+            status: interaction.response.status,
+            headers,
+            body: interaction.response.body.clone(),
+            from_cache: false,
+        })
+    }
+
+    /// Scrub and append an interaction, then rewrite the cassette file
+    pub fn record(&self, request: &MiddlewareRequest, response: &ApiResponse) -> Result<()> {
+        let interaction = Interaction {
+            request: RecordedRequest {
+                method: request.method.to_string(),
+                url: self.scrub(&self.scrub_url(&request.url)),
+                headers: self.scrub_headers(&request.headers),
+            },
+            response: RecordedResponse {
+                status: response.status,
+                headers: self.scrub_headers(&response.headers),
+                body: self.scrub_body(&response.body),
+            },
+        };
+
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(interaction);
+        let file = CassetteFile {
+            interactions: interactions.clone(),
+        };
+        if let Some(parent) = self.config.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.config.path, serde_json::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+
+    fn scrub_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self
+                    .config
+                    .scrub_headers
+                    .iter()
+                    .any(|scrubbed| scrubbed.eq_ignore_ascii_case(name.as_str()))
+                {
+                    REDACTED.to_string()
+                } else {
+                    self.scrub(&String::from_utf8_lossy(value.as_bytes()))
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    fn scrub_url(&self, url: &str) -> String {
+        let Ok(mut parsed) = reqwest::Url::parse(url) else {
+            return url.to_string();
+        };
+        if parsed.query().is_none() {
+            return url.to_string();
+        }
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if self.config.scrub_query_params.iter().any(|p| *p == name) {
+                    REDACTED.to_string()
+                } else {
+                    value.into_owned()
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        parsed.to_string()
+    }
+
+    fn scrub_body(&self, body: &[u8]) -> Vec<u8> {
+        match std::str::from_utf8(body) {
+            Ok(text) => self.scrub(text).into_bytes(),
+            Err(_) => body.to_vec(),
+        }
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        self.config
+            .secrets
+            .iter()
+            .filter(|secret| !secret.is_empty())
+            .fold(text.to_string(), |text, secret| {
+                text.replace(secret.as_str(), REDACTED)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::auth::AuthConfig;
+    use crate::http::client::{APIClient, HttpClientConfig};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn cassette_path() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "cassette-{}.json",
+            crate::utils::crypto::generate_uuid()
+        ))
+    }
+
+    fn client(path: &PathBuf, mode: CassetteMode) -> APIClient {
+        APIClient::new(HttpClientConfig {
+            cassette: Some(CassetteConfig::new(path, mode).with_secret("s3cr3t")),
+            ..HttpClientConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        // Test: Recorded interactions are scrubbed and replayed without the network
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Set-Cookie", "session=abc")
+                    .set_body_string("{\"owner\":\"s3cr3t\"}"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let path = cassette_path();
+        let url = format!("{}/pkg?token=s3cr3t&page=2", server.uri());
+        let mut recorder = client(&path, CassetteMode::Record);
+        recorder.set_auth(AuthConfig::Bearer("s3cr3t".to_string()));
+        recorder.get(&url).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("s3cr3t"), "Secrets should be scrubbed");
+        assert!(
+            !content.contains("session=abc"),
+            "Cookies should be scrubbed"
+        );
+
+        drop(server);
+        let replayer = client(&path, CassetteMode::Replay);
+        let response = replayer.get(&url).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.text().unwrap(), "{\"owner\":\"[REDACTED]\"}");
+
+        let missing = replayer.get("http://127.0.0.1:9/unrecorded").await;
+        assert!(
+            missing.is_err(),
+            "Unrecorded requests should fail in replay"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_requires_cassette_file() {
+        // Test: Replay mode fails fast when the cassette does not exist
+        let config = CassetteConfig::new(cassette_path(), CassetteMode::Replay);
+        assert!(Cassette::open(config).is_err());
+    }
+}
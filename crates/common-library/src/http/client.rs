@@ -0,0 +1,1681 @@
+//! HTTP client wrapper with rate limiting, retries, and response caching
+
+use super::auth::{AuthConfig, AuthManager};
+use super::cache::{CacheEntry, ResponseCache};
+use super::cassette::{Cassette, CassetteConfig, CassetteMode};
+use super::compression::RequestCompression;
+use super::dedupe::InFlightRequests;
+use super::metrics::{HttpMetricsRecorder, RequestMetrics};
+use super::middleware::{HttpMiddleware, MiddlewareRequest};
+use super::rate_limiter::{HostRateLimiter, RateLimitBackend, RateLimitStatus};
+use super::retry::{CircuitBreaker, ErrorClass, RetryConfig};
+use super::robots::RobotsCache;
+use super::scheduler::{RequestPriority, RequestScheduler, SchedulerPermit};
+use crate::config::{
+    ConnectionPoolConfig, HttpConfig, PackageManagerConfig, PolitenessConfig, ProxyConfig,
+    RateLimitConfig, TlsConfig,
+};
+use crate::error::{Error, Result};
+use crate::logging::CorrelationId;
+use reqwest::Method;
+use reqwest::header::{
+    CACHE_CONTROL, ETAG, HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, USER_AGENT,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// HTTP client configuration
+#[derive(Debug, Clone)]
+pub struct HttpClientConfig {
+    /// Total time budget for a buffered request, including reading the body
+    ///
+    /// Streams are bounded by `read_timeout` only, so long downloads can run
+    /// for as long as data keeps arriving.
+    pub timeout: Duration,
+    /// Time allowed to establish a connection
+    pub connect_timeout: Option<Duration>,
+    /// Maximum idle time between reads of the response
+    pub read_timeout: Option<Duration>,
+    /// Largest body accepted by buffered requests; streams are not limited
+    pub max_response_size: Option<u64>,
+    pub user_agent: String,
+    pub rate_limit_per_minute: u32,
+    /// Rate limits for specific hosts, overriding `rate_limit_per_minute`
+    pub host_rate_limits: HashMap<String, RateLimitConfig>,
+    pub retry: RetryConfig,
+    /// Retry settings for specific hosts, overriding `retry`
+    pub host_retries: HashMap<String, RetryConfig>,
+    /// Longest server-requested pause (`Retry-After`, quota reset) to wait out
+    ///
+    /// Requests to a host paused for longer fail immediately until the pause ends.
+    pub max_rate_limit_wait: Duration,
+    /// Record responses to, or replay them from, a cassette file
+    pub cassette: Option<CassetteConfig>,
+    /// Explicit proxy; when unset, `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` are honored
+    pub proxy: Option<ProxyConfig>,
+    /// Coalesce concurrent GETs for the same URL into one upstream request
+    pub dedupe: bool,
+    /// Connection reuse and HTTP/2 settings
+    pub pool: ConnectionPoolConfig,
+    /// Custom CAs, client certificates, and verification settings
+    pub tls: TlsConfig,
+    /// robots.txt, crawl-delay, and per-host User-Agent settings
+    pub politeness: PolitenessConfig,
+    /// Compress large request bodies before sending
+    pub request_compression: Option<RequestCompression>,
+    /// Header carrying the current [`CorrelationId`]; `None` disables it
+    pub correlation_id_header: Option<String>,
+    /// File rate limiter state is loaded from and saved to
+    pub rate_limit_state: Option<PathBuf>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            max_response_size: Some(100 * 1024 * 1024),
+            user_agent: "common-library/0.1.0".to_string(),
+            rate_limit_per_minute: 60,
+            host_rate_limits: HashMap::new(),
+            retry: RetryConfig::default(),
+            host_retries: HashMap::new(),
+            max_rate_limit_wait: Duration::from_secs(15 * 60),
+            cassette: None,
+            proxy: None,
+            dedupe: false,
+            pool: ConnectionPoolConfig::default(),
+            tls: TlsConfig::default(),
+            politeness: PolitenessConfig::default(),
+            request_compression: None,
+            correlation_id_header: Some("X-Correlation-ID".to_string()),
+            rate_limit_state: None,
+        }
+    }
+}
+
+impl From<&HttpConfig> for HttpClientConfig {
+    fn from(config: &HttpConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_seconds),
+            connect_timeout: config.connect_timeout_seconds.map(Duration::from_secs),
+            read_timeout: config.read_timeout_seconds.map(Duration::from_secs),
+            max_response_size: config.max_response_bytes,
+            user_agent: config.user_agent.clone(),
+            rate_limit_per_minute: config.rate_limit_per_minute,
+            host_rate_limits: HashMap::new(),
+            retry: RetryConfig {
+                max_retries: config.max_retries,
+                ..RetryConfig::default()
+            },
+            host_retries: HashMap::new(),
+            max_rate_limit_wait: Duration::from_secs(15 * 60),
+            cassette: None,
+            proxy: config.proxy.clone(),
+            dedupe: false,
+            pool: config.pool.clone(),
+            tls: config.tls.clone(),
+            politeness: config.politeness.clone(),
+            request_compression: None,
+            correlation_id_header: Some("X-Correlation-ID".to_string()),
+            rate_limit_state: config.rate_limit_state_path.as_ref().map(PathBuf::from),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// Set the rate limit for a single host
+    pub fn with_host_rate_limit(mut self, host: impl Into<String>, limit: RateLimitConfig) -> Self {
+        self.host_rate_limits.insert(host.into(), limit);
+        self
+    }
+
+    /// Set the retry behavior for a single host
+    pub fn with_host_retry(mut self, host: impl Into<String>, retry: RetryConfig) -> Self {
+        self.host_retries.insert(host.into(), retry);
+        self
+    }
+
+    /// Retry settings used for requests to `host`
+    pub fn retry_for(&self, host: &str) -> &RetryConfig {
+        self.host_retries.get(host).unwrap_or(&self.retry)
+    }
+
+    /// Apply the rate limits, User-Agents, and retry overrides declared for each registry
+    pub fn with_package_managers(
+        mut self,
+        package_managers: &HashMap<String, PackageManagerConfig>,
+    ) -> Self {
+        for package_manager in package_managers.values() {
+            self.host_rate_limits.insert(
+                package_manager.host.clone(),
+                package_manager.rate_limit.clone(),
+            );
+            if let Some(user_agent) = &package_manager.user_agent {
+                self.politeness
+                    .user_agents
+                    .insert(package_manager.host.clone(), user_agent.clone());
+            }
+            if let Some(retry) = &package_manager.retry {
+                self.host_retries.insert(
+                    package_manager.host.clone(),
+                    self.retry.with_overrides(retry),
+                );
+            }
+        }
+        self
+    }
+
+    fn host_rate_limiter(&self) -> HostRateLimiter {
+        HostRateLimiter::with_overrides(
+            RateLimitConfig {
+                requests_per_minute: self.rate_limit_per_minute,
+                burst: None,
+            },
+            self.host_rate_limits.clone(),
+        )
+    }
+}
+
+/// A fully buffered HTTP response
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// Whether the body was served from the response cache
+    pub from_cache: bool,
+}
+
+impl ApiResponse {
+    /// Check whether the status code is 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Get a header value as a string
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// Deserialize the body as JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).map_err(Error::from)
+    }
+
+    /// Get the body as UTF-8 text
+    pub fn text(&self) -> Result<String> {
+        String::from_utf8(self.body.clone())
+            .map_err(|e| Error::http(format!("Response body is not valid UTF-8: {}", e)))
+    }
+}
+
+/// HTTP API client with rate limiting, retry logic, and conditional caching
+pub struct APIClient {
+    client: reqwest::Client,
+    config: HttpClientConfig,
+    rate_limiter: Arc<HostRateLimiter>,
+    cache: Option<Arc<dyn ResponseCache>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    auth: Option<Arc<AuthManager>>,
+    middleware: Vec<Arc<dyn HttpMiddleware>>,
+    cassette: Option<Arc<Cassette>>,
+    scheduler: Option<Arc<RequestScheduler>>,
+    in_flight: InFlightRequests,
+    metrics: Option<Arc<dyn HttpMetricsRecorder>>,
+    robots: RobotsCache,
+}
+
+impl APIClient {
+    /// Create a new API client
+    pub fn new(config: HttpClientConfig) -> Result<Self> {
+        let client = build_http_client(&config)?;
+        let cassette = match &config.cassette {
+            Some(cassette) if cassette.mode != CassetteMode::Passthrough => {
+                Some(Arc::new(Cassette::open(cassette.clone())?))
+            }
+            _ => None,
+        };
+
+        let rate_limiter = config.host_rate_limiter();
+        if let Some(path) = &config.rate_limit_state
+            && let Err(e) = rate_limiter.load_state(path)
+        {
+            warn!("Ignoring rate limiter state in {}: {}", path.display(), e);
+        }
+
+        Ok(Self {
+            rate_limiter: Arc::new(rate_limiter),
+            client,
+            config,
+            cache: None,
+            circuit_breaker: None,
+            auth: None,
+            middleware: Vec::new(),
+            cassette,
+            scheduler: None,
+            in_flight: InFlightRequests::default(),
+            metrics: None,
+            robots: RobotsCache::default(),
+        })
+    }
+
+    /// Enable response caching with conditional requests
+    pub fn with_cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Fail fast against hosts that keep returning server errors or timing out
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Get the circuit breaker, if one is configured
+    pub fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.circuit_breaker.as_ref()
+    }
+
+    /// Authenticate requests with a shared authentication manager
+    pub fn with_auth(mut self, auth: Arc<AuthManager>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Configure authentication
+    pub fn set_auth(&mut self, config: AuthConfig) {
+        let auth = AuthManager::new(config).with_client(self.client.clone(), self.config.timeout);
+        self.auth = Some(Arc::new(auth));
+    }
+
+    /// Report per-request latency, status, retries, and bytes
+    pub fn with_metrics(mut self, recorder: Arc<dyn HttpMetricsRecorder>) -> Self {
+        self.metrics = Some(recorder);
+        self
+    }
+
+    /// Admit requests through a shared priority scheduler
+    pub fn with_scheduler(mut self, scheduler: Arc<RequestScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// Get the request scheduler, if one is configured
+    pub fn scheduler(&self) -> Option<&Arc<RequestScheduler>> {
+        self.scheduler.as_ref()
+    }
+
+    /// Append a middleware to the request pipeline
+    ///
+    /// Middleware runs in registration order for requests and in reverse
+    /// order for responses.
+    pub fn with_middleware(mut self, middleware: Arc<dyn HttpMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Append a middleware to the request pipeline of an existing client
+    pub fn add_middleware(&mut self, middleware: Arc<dyn HttpMiddleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Get the client configuration
+    pub fn config(&self) -> &HttpClientConfig {
+        &self.config
+    }
+
+    /// The underlying reqwest client, bypassing rate limits and middleware
+    pub(crate) fn http_client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub(crate) fn robots(&self) -> &RobotsCache {
+        &self.robots
+    }
+
+    /// Get the per-host rate limiter shared by all requests from this client
+    pub fn rate_limiter(&self) -> &Arc<HostRateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Save rate limiter state to `rate_limit_state`, if configured
+    ///
+    /// Called automatically when a server pauses a host; call it on shutdown
+    /// so the next run resumes with the quota already spent.
+    pub fn save_rate_limit_state(&self) -> Result<()> {
+        match &self.config.rate_limit_state {
+            Some(path) => self.rate_limiter.save_state(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Adjust the default rate limit for hosts without an explicit limit
+    pub fn set_rate_limit(&mut self, requests_per_minute: u32) {
+        self.config.rate_limit_per_minute = requests_per_minute;
+        let backend = self.rate_limiter.backend().cloned();
+        self.rebuild_rate_limiter(backend);
+    }
+
+    /// Share per-host rate limits with other instances through a backend
+    pub fn with_rate_limit_backend(mut self, backend: Arc<dyn RateLimitBackend>) -> Self {
+        self.rebuild_rate_limiter(Some(backend));
+        self
+    }
+
+    /// Replace the rate limiter, keeping spent tokens and server pauses
+    fn rebuild_rate_limiter(&mut self, backend: Option<Arc<dyn RateLimitBackend>>) {
+        let mut rate_limiter = self.config.host_rate_limiter();
+        if let Some(backend) = backend {
+            rate_limiter = rate_limiter.with_backend(backend);
+        }
+        rate_limiter.restore(&self.rate_limiter.snapshot());
+        self.rate_limiter = Arc::new(rate_limiter);
+        // Crawl delays live in the old limiter; re-derive them on next contact
+        self.robots = RobotsCache::default();
+    }
+
+    /// Make a GET request
+    ///
+    /// When a cache is configured, stored validators are sent as
+    /// `If-None-Match` / `If-Modified-Since` and a `304 Not Modified` answer
+    /// is served from the cache without consuming rate limit budget.
+    pub async fn get(&self, url: &str) -> Result<ApiResponse> {
+        self.get_with_priority(url, RequestPriority::Normal).await
+    }
+
+    /// Make a GET request at the given scheduler priority
+    ///
+    /// Priorities only take effect when a [`RequestScheduler`] is configured.
+    pub async fn get_with_priority(
+        &self,
+        url: &str,
+        priority: RequestPriority,
+    ) -> Result<ApiResponse> {
+        if self.config.dedupe {
+            self.in_flight.run(url, || self.fetch(url, priority)).await
+        } else {
+            self.fetch(url, priority).await
+        }
+    }
+
+    /// Number of distinct deduplicated requests currently in flight
+    pub fn in_flight_requests(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    async fn fetch(&self, url: &str, priority: RequestPriority) -> Result<ApiResponse> {
+        let cached = match &self.cache {
+            Some(cache) => cache.get(url)?,
+            None => None,
+        };
+
+        let mut headers = HeaderMap::new();
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                headers.insert(IF_NONE_MATCH, header_value(etag)?);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                headers.insert(IF_MODIFIED_SINCE, header_value(last_modified)?);
+            }
+        }
+
+        let response = self
+            .execute_with_priority(Method::GET, url, headers, None, priority)
+            .await?;
+
+        if response.status == 304
+            && let Some(entry) = cached
+        {
+            self.rate_limiter.limiter_for(&host_of(url)?).refund();
+            return Ok(ApiResponse {
+                status: 200,
+                headers: response.headers,
+                body: entry.body,
+                from_cache: true,
+            });
+        }
+
+        if !response.is_success() {
+            return Err(Error::http(format!(
+                "GET {} failed with status {}",
+                url, response.status
+            )));
+        }
+
+        if let Some(cache) = &self.cache {
+            self.store_in_cache(cache.as_ref(), url, &response)?;
+        }
+
+        Ok(response)
+    }
+
+    /// Make a GET request and deserialize the JSON body
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.get(url).await?.json()
+    }
+
+    /// Make a POST request with a JSON body
+    pub async fn post_json<B: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        body: &B,
+    ) -> Result<ApiResponse> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let body = serde_json::to_vec(body)?;
+
+        let response = self.execute(Method::POST, url, headers, Some(body)).await?;
+        if !response.is_success() {
+            return Err(Error::http(format!(
+                "POST {} failed with status {}",
+                url, response.status
+            )));
+        }
+        Ok(response)
+    }
+
+    /// Drop the cached response for a URL
+    pub fn invalidate_cache(&self, url: &str) -> Result<()> {
+        match &self.cache {
+            Some(cache) => cache.remove(url),
+            None => Ok(()),
+        }
+    }
+
+    /// Drop all cached responses
+    pub fn clear_cache(&self) -> Result<()> {
+        match &self.cache {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
+        }
+    }
+
+    /// Send a request with rate limiting and retries, buffering the body
+    pub(crate) async fn execute(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        body: Option<Vec<u8>>,
+    ) -> Result<ApiResponse> {
+        self.execute_with_priority(method, url, headers, body, RequestPriority::Normal)
+            .await
+    }
+
+    /// Send a request at the given scheduler priority, buffering the body
+    pub(crate) async fn execute_with_priority(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        body: Option<Vec<u8>>,
+        priority: RequestPriority,
+    ) -> Result<ApiResponse> {
+        let mut request = MiddlewareRequest {
+            method,
+            url: url.to_string(),
+            headers,
+        };
+        let (mut response, ran) = match self.apply_request_middleware(&mut request)? {
+            Some(short_circuit) => short_circuit,
+            None => (
+                self.dispatch(&request, body, priority).await?,
+                self.middleware.len(),
+            ),
+        };
+
+        for middleware in self.middleware[..ran].iter().rev() {
+            middleware.on_response(&request, &mut response)?;
+        }
+        Ok(response)
+    }
+
+    /// Answer a request from the replay cassette or the network
+    async fn dispatch(
+        &self,
+        request: &MiddlewareRequest,
+        body: Option<Vec<u8>>,
+        priority: RequestPriority,
+    ) -> Result<ApiResponse> {
+        if let Some(response) = self.replay(request)? {
+            return Ok(response);
+        }
+
+        // Held until the body is buffered
+        let _permit = self.schedule(priority).await;
+
+        let started = Instant::now();
+        let mut retries = 0;
+        let result = async {
+            let response = self
+                .send(
+                    request.method.clone(),
+                    &request.url,
+                    request.headers.clone(),
+                    body,
+                    Some(self.config.timeout),
+                    &mut retries,
+                )
+                .await?;
+            let status = response.status().as_u16();
+            let headers = response.headers().clone();
+            let body = self.read_body(&request.url, response).await?;
+            Ok(ApiResponse {
+                status,
+                headers,
+                body,
+                from_cache: false,
+            })
+        }
+        .await;
+        self.record_metrics(
+            request,
+            started,
+            retries,
+            match &result {
+                Ok(response) => Ok((response.status, Some(response.body.len() as u64))),
+                Err(e) => Err(e),
+            },
+        );
+        let response = result?;
+
+        if let Some(cassette) = &self.cassette
+            && cassette.mode() == CassetteMode::Record
+        {
+            cassette.record(request, &response)?;
+        }
+        Ok(response)
+    }
+
+    /// Report a completed request to the metrics recorder
+    ///
+    /// `outcome` carries the status and buffered body size, or the error.
+    pub(crate) fn record_metrics(
+        &self,
+        request: &MiddlewareRequest,
+        started: Instant,
+        retries: u32,
+        outcome: std::result::Result<(u16, Option<u64>), &Error>,
+    ) {
+        let Some(recorder) = &self.metrics else {
+            return;
+        };
+        let (status, bytes, error) = match outcome {
+            Ok((status, bytes)) => (Some(status), bytes, None),
+            Err(e) => (None, None, Some(e.to_string())),
+        };
+        recorder.record(&RequestMetrics {
+            method: request.method.to_string(),
+            host: host_of(&request.url).unwrap_or_default(),
+            url: request.url.clone(),
+            status,
+            latency: started.elapsed(),
+            retries,
+            bytes,
+            error,
+        });
+    }
+
+    /// Buffer a response body, enforcing `max_response_size`
+    async fn read_body(&self, url: &str, mut response: reqwest::Response) -> Result<Vec<u8>> {
+        let limit = self.config.max_response_size;
+        if let (Some(limit), Some(length)) = (limit, response.content_length())
+            && length > limit
+        {
+            return Err(Error::response_too_large(url, limit));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::http(format!("Failed to read response body: {}", e)))?
+        {
+            if let Some(limit) = limit
+                && (body.len() + chunk.len()) as u64 > limit
+            {
+                return Err(Error::response_too_large(url, limit));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Wait for a scheduler slot, if a scheduler is configured
+    pub(crate) async fn schedule(&self, priority: RequestPriority) -> Option<SchedulerPermit> {
+        match &self.scheduler {
+            Some(scheduler) => Some(scheduler.acquire(priority).await),
+            None => None,
+        }
+    }
+
+    /// Replay a request from the cassette when in replay mode
+    pub(crate) fn replay(&self, request: &MiddlewareRequest) -> Result<Option<ApiResponse>> {
+        match &self.cassette {
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => {
+                cassette.replay(request).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Run request middleware in order
+    ///
+    /// When a middleware short-circuits, returns its response together with
+    /// the number of middleware whose response hooks should still run.
+    pub(crate) fn apply_request_middleware(
+        &self,
+        request: &mut MiddlewareRequest,
+    ) -> Result<Option<(ApiResponse, usize)>> {
+        for (index, middleware) in self.middleware.iter().enumerate() {
+            if let Some(response) = middleware.on_request(request)? {
+                return Ok(Some((response, index)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Send a request with rate limiting and retries, returning the unread response
+    ///
+    /// `timeout` bounds each attempt including reading the body; streaming
+    /// callers pass `None`. `retries` is incremented for every retry made.
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        mut headers: HeaderMap,
+        mut body: Option<Vec<u8>>,
+        timeout: Option<Duration>,
+        retries: &mut u32,
+    ) -> Result<reqwest::Response> {
+        let host = host_of(url)?;
+        let retry = self.config.retry_for(&host);
+        self.check_politeness(&host, url).await?;
+        if let Some(user_agent) = self.config.politeness.user_agents.get(&host)
+            && !headers.contains_key(USER_AGENT)
+        {
+            let value = HeaderValue::from_str(user_agent)
+                .map_err(|e| Error::http(format!("Invalid User-Agent {}: {}", user_agent, e)))?;
+            headers.insert(USER_AGENT, value);
+        }
+        if let Some(name) = &self.config.correlation_id_header
+            && let Some(id) = CorrelationId::current()
+            && !headers.contains_key(name.as_str())
+        {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::http(format!("Invalid correlation header {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(id.as_str())
+                .map_err(|e| Error::http(format!("Invalid correlation ID {}: {}", id, e)))?;
+            headers.insert(name, value);
+        }
+        if let (Some(compression), Some(body)) = (&self.config.request_compression, &mut body) {
+            compression.apply(&mut headers, body)?;
+        }
+        let attempt = retries;
+        let mut reauthenticated = false;
+
+        loop {
+            // A pause past the cap would stall every request until it ends
+            if let Some(paused) = self.rate_limiter.limiter_for(&host).paused_for()
+                && paused > self.config.max_rate_limit_wait
+            {
+                return Err(Error::http(format!(
+                    "{} {} rate limited for {:?}, exceeding the maximum wait",
+                    method, url, paused
+                )));
+            }
+            self.rate_limiter.acquire(&host).await;
+
+            let mut request_headers = headers.clone();
+            if let Some(auth) = &self.auth {
+                auth.authorize(
+                    &method,
+                    url,
+                    &mut request_headers,
+                    body.as_deref().unwrap_or_default(),
+                )
+                .await?;
+            }
+            // Checked last so a request abandoned while waiting never holds a trial slot
+            let permit = match &self.circuit_breaker {
+                Some(breaker) => Some(breaker.check(&host)?),
+                None => None,
+            };
+            let mut request = self
+                .client
+                .request(method.clone(), url)
+                .headers(request_headers);
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+
+            match request.send().await {
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    let limits = RateLimitStatus::from_headers(response.headers());
+                    let limiter = self.rate_limiter.limiter_for(&host);
+                    limiter.update_limits(&limits);
+                    if limits.wait_time().is_some()
+                        && let Some(path) = &self.config.rate_limit_state
+                    {
+                        self.rate_limiter.save_state_in_background(path.clone());
+                    }
+                    if let Some(permit) = permit {
+                        if (500..600).contains(&status) {
+                            permit.record_failure();
+                        } else {
+                            permit.record_success();
+                        }
+                    }
+                    if status == 401
+                        && !reauthenticated
+                        && let Some(auth) = &self.auth
+                        && auth.invalidate().await
+                    {
+                        reauthenticated = true;
+                        continue;
+                    }
+                    let rate_limited = status == 429
+                        || (status == 403
+                            && (limits.retry_after.is_some() || limits.remaining == Some(0)));
+                    if rate_limited && retry.should_retry(ErrorClass::RateLimited, *attempt) {
+                        let wait = limits
+                            .wait_time()
+                            .unwrap_or_else(|| retry.calculate_backoff(*attempt + 1));
+                        if wait > self.config.max_rate_limit_wait {
+                            return Err(Error::http(format!(
+                                "{} {} rate limited for {:?}, exceeding the maximum wait",
+                                method, url, wait
+                            )));
+                        }
+                        warn!("Rate limited by {}, waiting {:?}", host, wait);
+                        limiter.pause_for(wait);
+                        *attempt += 1;
+                        continue;
+                    }
+                    if retry.should_retry_status(status, *attempt) {
+                        *attempt += 1;
+                        tokio::time::sleep(retry.calculate_backoff(*attempt)).await;
+                        continue;
+                    }
+
+                    return Ok(response);
+                }
+                Err(e) => {
+                    if let Some(permit) = permit {
+                        permit.record_failure();
+                    }
+                    if !retry.should_retry(ErrorClass::Network, *attempt) {
+                        return Err(Error::http(format!("{} {} failed: {}", method, url, e)));
+                    }
+                    *attempt += 1;
+                    tokio::time::sleep(retry.calculate_backoff(*attempt)).await;
+                }
+            }
+        }
+    }
+
+    fn store_in_cache(
+        &self,
+        cache: &dyn ResponseCache,
+        url: &str,
+        response: &ApiResponse,
+    ) -> Result<()> {
+        let no_store = response
+            .header(CACHE_CONTROL.as_str())
+            .is_some_and(|v| v.contains("no-store"));
+        let entry = CacheEntry {
+            url: url.to_string(),
+            etag: response.header(ETAG.as_str()).map(str::to_string),
+            last_modified: response.header(LAST_MODIFIED.as_str()).map(str::to_string),
+            body: response.body.clone(),
+            stored_at: chrono::Utc::now(),
+        };
+
+        if no_store || !entry.has_validators() {
+            return cache.remove(url);
+        }
+        cache.put(entry)
+    }
+}
+
+/// Build the underlying client with the proxy, TLS, pool, and connection settings
+///
+/// The total `timeout` is not set here; buffered requests apply it per request.
+pub(crate) fn build_http_client(config: &HttpClientConfig) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().user_agent(config.user_agent.clone());
+    if let Some(connect_timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(read_timeout) = config.read_timeout {
+        builder = builder.read_timeout(read_timeout);
+    }
+    let pool = &config.pool;
+    builder = builder
+        .pool_max_idle_per_host(pool.max_idle_per_host)
+        .pool_idle_timeout(pool.idle_timeout_seconds.map(Duration::from_secs))
+        .tcp_keepalive(pool.tcp_keepalive_seconds.map(Duration::from_secs))
+        .http2_keep_alive_interval(
+            pool.http2_keep_alive_interval_seconds
+                .map(Duration::from_secs),
+        );
+    if pool.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(build_proxy(proxy)?);
+    }
+    builder = apply_tls(builder, &config.tls)?;
+    builder
+        .build()
+        .map_err(|e| Error::http(format!("Failed to build HTTP client: {}", e)))
+}
+
+/// Build a reqwest proxy from configuration
+///
+/// Credentials are sent as `Proxy-Authorization` for HTTP proxies and
+/// embedded in the URL for SOCKS5 proxies.
+fn build_proxy(config: &ProxyConfig) -> Result<reqwest::Proxy> {
+    let invalid =
+        |e: &dyn std::fmt::Display| Error::http(format!("Invalid proxy url {}: {}", config.url, e));
+    let mut url = reqwest::Url::parse(&config.url).map_err(|e| invalid(&e))?;
+    let socks = url.scheme().starts_with("socks");
+    if socks && let Some(username) = &config.username {
+        url.set_username(username)
+            .map_err(|_| invalid(&"cannot set username"))?;
+        url.set_password(config.password.as_deref())
+            .map_err(|_| invalid(&"cannot set password"))?;
+    }
+
+    let mut proxy = reqwest::Proxy::all(url).map_err(|e| invalid(&e))?;
+    if !socks && let Some(username) = &config.username {
+        proxy = proxy.basic_auth(username, config.password.as_deref().unwrap_or_default());
+    }
+    if !config.no_proxy.is_empty() {
+        proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&config.no_proxy.join(",")));
+    }
+    Ok(proxy)
+}
+
+fn apply_tls(
+    mut builder: reqwest::ClientBuilder,
+    config: &TlsConfig,
+) -> Result<reqwest::ClientBuilder> {
+    let read = |path: &PathBuf| {
+        std::fs::read(path)
+            .map_err(|e| Error::config(format!("Failed to read {}: {}", path.display(), e)))
+    };
+    for path in &config.ca_certificates {
+        let certificates = reqwest::Certificate::from_pem_bundle(&read(path)?)
+            .map_err(|e| Error::config(format!("Invalid CA bundle {}: {}", path.display(), e)))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    match (&config.client_certificate, &config.client_key) {
+        (Some(certificate), Some(key)) => {
+            let identity = reqwest::Identity::from_pkcs8_pem(&read(certificate)?, &read(key)?)
+                .map_err(|e| {
+                    Error::config(format!(
+                        "Invalid client certificate {}: {}",
+                        certificate.display(),
+                        e
+                    ))
+                })?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(Error::config(
+                "A client certificate and key must be configured together",
+            ));
+        }
+    }
+    if config.insecure_skip_verify {
+        warn!("TLS certificate verification is disabled");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder)
+}
+
+/// Extract the host component of a URL
+pub(crate) fn host_of(url: &str) -> Result<String> {
+    let parsed =
+        reqwest::Url::parse(url).map_err(|e| Error::http(format!("Invalid URL {}: {}", url, e)))?;
+    parsed
+        .host_str()
+        .map(str::to_string)
+        .ok_or_else(|| Error::http(format!("URL has no host: {}", url)))
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| Error::http(format!("Invalid header value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::auth::TokenSource;
+    use crate::http::cache::MemoryCache;
+    use futures::StreamExt;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_config() -> HttpClientConfig {
+        HttpClientConfig {
+            retry: RetryConfig {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(1),
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_retries_server_errors() {
+        // Test: 5xx responses are retried until a successful response arrives
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(test_config()).unwrap();
+        let response = client
+            .get(&format!("{}/flaky", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(response.text().unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_served_from_cache() {
+        // Test: A 304 answer returns the cached body and refunds the rate limit token
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repo"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repo"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_string("{\"stars\":42}"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(MemoryCache::new());
+        let client = APIClient::new(test_config())
+            .unwrap()
+            .with_cache(cache.clone());
+        let url = format!("{}/repo", server.uri());
+
+        let first = client.get(&url).await.unwrap();
+        assert!(
+            !first.from_cache,
+            "First response should come from the server"
+        );
+        assert_eq!(cache.len(), 1, "Response with ETag should be cached");
+
+        let remaining = client.rate_limiter().remaining("127.0.0.1");
+        let second = client.get(&url).await.unwrap();
+        assert!(
+            second.from_cache,
+            "304 response should be served from cache"
+        );
+        assert_eq!(second.body, first.body);
+        assert_eq!(
+            client.rate_limiter().remaining("127.0.0.1"),
+            remaining,
+            "304 response should not consume rate limit budget"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast() {
+        // Test: Once the circuit opens, requests fail without reaching the server
+        use crate::http::retry::{CircuitBreakerConfig, CircuitState};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+            half_open_max_requests: 1,
+        }));
+        let client = APIClient::new(test_config())
+            .unwrap()
+            .with_circuit_breaker(breaker.clone());
+        let url = format!("{}/down", server.uri());
+
+        let result = client.get(&url).await;
+        assert!(
+            result.is_err(),
+            "Request should fail once the circuit opens"
+        );
+        assert_eq!(breaker.state("127.0.0.1"), CircuitState::Open);
+        assert!(
+            client.get(&url).await.is_err(),
+            "Open circuit should fail fast"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_half_open_probe_frees_circuit() {
+        // Test: A half-open probe dropped mid-request does not leave the circuit stuck
+        use crate::http::retry::{CircuitBreakerConfig, CircuitState};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown: Duration::ZERO,
+            half_open_max_requests: 1,
+        }));
+        let client = APIClient::new(HttpClientConfig {
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..test_config()
+        })
+        .unwrap()
+        .with_circuit_breaker(breaker.clone());
+        let url = format!("{}/flaky", server.uri());
+
+        assert!(client.get(&url).await.is_err());
+        assert_eq!(breaker.state("127.0.0.1"), CircuitState::Open);
+        let probe = tokio::time::timeout(Duration::from_millis(200), client.get(&url)).await;
+        assert!(probe.is_err(), "Probe should be cancelled while in flight");
+        assert_eq!(breaker.state("127.0.0.1"), CircuitState::HalfOpen);
+
+        assert!(
+            client.get(&url).await.is_ok(),
+            "Next request should take the freed trial slot"
+        );
+        assert_eq!(breaker.state("127.0.0.1"), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_static_auth_header() {
+        // Test: Configured credentials are sent with every request
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Authorization", "token secret"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = APIClient::new(test_config()).unwrap();
+        client.set_auth(AuthConfig::Token("secret".to_string()));
+        client.get(&format!("{}/user", server.uri())).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_is_sent_within_scope() {
+        // Test: Requests inside a correlation scope carry its ID; others do not
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("X-Correlation-ID", "job-42"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("traced"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("untraced"))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(test_config()).unwrap();
+        let url = format!("{}/job", server.uri());
+        let traced = CorrelationId::from_string("job-42")
+            .scope(async { client.get(&url).await })
+            .await
+            .unwrap();
+        assert_eq!(traced.text().unwrap(), "traced");
+
+        let untraced = client
+            .get(&format!("{}/other", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(untraced.text().unwrap(), "untraced");
+    }
+
+    #[tokio::test]
+    async fn test_rotated_token_file_is_reread_on_401() {
+        // Test: A 401 re-reads the token file so a rotated token is picked up
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Authorization", "Bearer new"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let path =
+            std::env::temp_dir().join(format!("token-{}", crate::utils::crypto::generate_uuid()));
+        std::fs::write(&path, "old\n").unwrap();
+        let mut client = APIClient::new(test_config()).unwrap();
+        client.set_auth(AuthConfig::BearerFrom(TokenSource::File(path.clone())));
+
+        let url = format!("{}/user", server.uri());
+        assert!(
+            client.get(&url).await.is_err(),
+            "Unchanged token should still be rejected"
+        );
+
+        std::fs::write(&path, "new\n").unwrap();
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.status, 200);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_registry_retry_overrides_apply_per_host() {
+        // Test: A registry's retry matrix replaces the global one for its host only
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let mut package_managers = HashMap::new();
+        package_managers.insert(
+            "flaky".to_string(),
+            PackageManagerConfig {
+                host: "127.0.0.1".to_string(),
+                rate_limit: RateLimitConfig {
+                    requests_per_minute: 6000,
+                    burst: None,
+                },
+                user_agent: None,
+                retry: Some(crate::config::RegistryRetryConfig {
+                    max_retries: Some(3),
+                    retry_statuses: Some(vec![404]),
+                    initial_backoff_ms: Some(1),
+                    ..Default::default()
+                }),
+            },
+        );
+        let config = test_config().with_package_managers(&package_managers);
+        assert_eq!(config.retry_for("127.0.0.1").max_retries, 3);
+        assert_eq!(config.retry_for("registry.npmjs.org").max_retries, 2);
+
+        let client = APIClient::new(config).unwrap();
+        let response = client.get(&format!("{}/missing", server.uri())).await;
+        assert!(response.is_err(), "404 should still fail after retries");
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_quota_is_saved_for_next_run() {
+        // Test: A server-reported exhausted quota is persisted and restored by a new client
+        let server = MockServer::start().await;
+        let reset = chrono::Utc::now().timestamp() + 3600;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", reset.to_string().as_str()),
+            )
+            .mount(&server)
+            .await;
+
+        let path = std::env::temp_dir().join(format!(
+            "client-rate-limit-{}.json",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let config = HttpClientConfig {
+            rate_limit_state: Some(path.clone()),
+            ..test_config()
+        };
+        let client = APIClient::new(config.clone()).unwrap();
+        client
+            .get(&format!("{}/quota", server.uri()))
+            .await
+            .unwrap();
+        // The state is written on a blocking thread
+        for _ in 0..100 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(
+            path.exists(),
+            "State should be saved when the quota runs out"
+        );
+
+        let restarted = APIClient::new(config).unwrap();
+        assert!(
+            restarted
+                .rate_limiter()
+                .limiter_for("127.0.0.1")
+                .is_paused(),
+            "A restarted client should still wait for the quota reset"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rate_limit_changes_keep_loaded_state() {
+        // Test: Rebuilding the limiter keeps pauses and spent tokens from the state file
+        struct Unlimited;
+        impl RateLimitBackend for Unlimited {
+            fn try_acquire<'a>(
+                &'a self,
+                _host: &'a str,
+                _limit: &'a RateLimitConfig,
+            ) -> futures::future::BoxFuture<'a, Result<Option<Duration>>> {
+                Box::pin(async { Ok(None) })
+            }
+        }
+
+        let client = APIClient::new(test_config()).unwrap();
+        let limiter = client.rate_limiter().limiter_for("api.github.com");
+        limiter.pause_for(Duration::from_secs(3600));
+        let spent = client.rate_limiter().limiter_for("crates.io");
+        while spent.try_acquire() {}
+
+        let mut client = client.with_rate_limit_backend(Arc::new(Unlimited));
+        client.set_rate_limit(600);
+        let rate_limiter = client.rate_limiter();
+        assert!(rate_limiter.backend().is_some());
+        assert!(
+            rate_limiter.limiter_for("api.github.com").is_paused(),
+            "Server pause should survive the rebuild"
+        );
+        assert_eq!(
+            rate_limiter.remaining("crates.io"),
+            0,
+            "Spent tokens should not be refunded"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_secondary_rate_limit_waits_for_retry_after() {
+        // Test: A 403 with Retry-After pauses and retries instead of failing
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).insert_header("x-ratelimit-remaining", "4"))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(test_config()).unwrap();
+        let response = client.get(&format!("{}/search", server.uri())).await;
+        assert!(response.is_ok(), "Request should succeed after waiting");
+        assert_eq!(client.rate_limiter().remaining("127.0.0.1"), 4);
+    }
+
+    #[tokio::test]
+    async fn test_pause_beyond_max_wait_fails_fast() {
+        // Test: After a too-long Retry-After, later requests error instead of hanging
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "3600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            max_rate_limit_wait: Duration::from_secs(60),
+            ..test_config()
+        })
+        .unwrap();
+        let url = format!("{}/search", server.uri());
+        assert!(client.get(&url).await.is_err(), "Long pause should fail");
+
+        let second = tokio::time::timeout(Duration::from_secs(5), client.get(&url)).await;
+        assert!(
+            matches!(second, Ok(Err(_))),
+            "Second request should fail without waiting out the pause"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_without_rate_limit_is_not_retried() {
+        // Test: Plain 403 responses fail immediately
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(test_config()).unwrap();
+        assert!(
+            client
+                .get(&format!("{}/private", server.uri()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_cache() {
+        // Test: Invalidated entries are no longer used for conditional requests
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/pkg"))
+            .respond_with(ResponseTemplate::new(200).insert_header("ETag", "\"v2\""))
+            .mount(&server)
+            .await;
+
+        let cache = Arc::new(MemoryCache::new());
+        let client = APIClient::new(test_config())
+            .unwrap()
+            .with_cache(cache.clone());
+        let url = format!("{}/pkg", server.uri());
+        client.get(&url).await.unwrap();
+        assert_eq!(cache.len(), 1);
+
+        client.invalidate_cache(&url).unwrap();
+        assert!(cache.is_empty(), "Invalidated entry should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy_with_auth() {
+        // Test: Requests are forwarded to the proxy with Proxy-Authorization
+        let proxy = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/pkg"))
+            .and(header("Proxy-Authorization", "Basic dXNlcjpzZWNyZXQ="))
+            .respond_with(ResponseTemplate::new(200).set_body_string("via proxy"))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            proxy: Some(ProxyConfig::new(proxy.uri()).with_auth("user", "secret")),
+            ..test_config()
+        })
+        .unwrap();
+        let response = client.get("http://registry.invalid/pkg").await.unwrap();
+        assert_eq!(response.text().unwrap(), "via proxy");
+    }
+
+    #[tokio::test]
+    async fn test_no_proxy_hosts_bypass_proxy() {
+        // Test: Hosts on the no-proxy list are contacted directly
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("direct"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut proxy = ProxyConfig::new("http://127.0.0.1:9");
+        proxy.no_proxy = vec!["127.0.0.1".to_string()];
+        let client = APIClient::new(HttpClientConfig {
+            proxy: Some(proxy),
+            ..test_config()
+        })
+        .unwrap();
+        let response = client.get(&format!("{}/pkg", server.uri())).await.unwrap();
+        assert_eq!(response.text().unwrap(), "direct");
+    }
+
+    #[test]
+    fn test_invalid_proxy_url_is_rejected() {
+        // Test: Client construction fails for an unparsable proxy url
+        let result = APIClient::new(HttpClientConfig {
+            proxy: Some(ProxyConfig::new("not a url")),
+            ..HttpClientConfig::default()
+        });
+        assert!(result.is_err(), "Invalid proxy url should be rejected");
+    }
+
+    #[test]
+    fn test_tls_settings_are_validated() {
+        // Test: Unreadable or invalid TLS material and unpaired client certs are rejected
+        let garbage =
+            std::env::temp_dir().join(format!("tls-{}.pem", crate::utils::crypto::generate_uuid()));
+        std::fs::write(&garbage, "not a certificate").unwrap();
+        let with_tls = |tls: TlsConfig| {
+            APIClient::new(HttpClientConfig {
+                tls,
+                ..HttpClientConfig::default()
+            })
+        };
+
+        assert!(
+            with_tls(TlsConfig {
+                ca_certificates: vec![garbage.with_extension("missing")],
+                ..TlsConfig::default()
+            })
+            .is_err(),
+            "A missing CA bundle should be rejected"
+        );
+        assert!(
+            with_tls(TlsConfig {
+                client_certificate: Some(garbage.clone()),
+                ..TlsConfig::default()
+            })
+            .is_err(),
+            "A client certificate without a key should be rejected"
+        );
+        assert!(
+            with_tls(TlsConfig {
+                client_certificate: Some(garbage.clone()),
+                client_key: Some(garbage.clone()),
+                ..TlsConfig::default()
+            })
+            .is_err(),
+            "An invalid client identity should be rejected"
+        );
+        assert!(
+            with_tls(TlsConfig {
+                insecure_skip_verify: true,
+                ..TlsConfig::default()
+            })
+            .is_ok()
+        );
+        std::fs::remove_file(&garbage).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oversized_response_is_rejected() {
+        // Test: Bodies over max_response_size fail with ResponseTooLarge
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(64)))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            max_response_size: Some(16),
+            ..test_config()
+        })
+        .unwrap();
+        let result = client.get(&format!("{}/huge", server.uri())).await;
+        assert!(
+            matches!(result, Err(Error::ResponseTooLarge { limit: 16, .. })),
+            "Oversized body should be rejected"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_fails_slow_responses() {
+        // Test: A stalled response fails once the read timeout elapses
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            read_timeout: Some(Duration::from_millis(50)),
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let result = client.get(&format!("{}/slow", server.uri())).await;
+        assert!(result.is_err(), "Slow response should time out");
+    }
+
+    #[tokio::test]
+    async fn test_total_timeout_applies_only_to_buffered_requests() {
+        // Test: A response slower than `timeout` fails buffered but still streams
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(vec![b'x'; 64 * 1024])
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            timeout: Duration::from_millis(100),
+            read_timeout: Some(Duration::from_secs(5)),
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let url = format!("{}/large", server.uri());
+        assert!(
+            client.get(&url).await.is_err(),
+            "Buffered request should hit the total timeout"
+        );
+
+        let mut stream = client.get_stream(&url).await.unwrap();
+        let mut received = 0;
+        while let Some(chunk) = stream.next().await {
+            received += chunk.unwrap().len();
+        }
+        assert_eq!(received, 64 * 1024, "Stream should not be cut off");
+    }
+
+    /// Serve keep-alive HTTP/1.1 responses, counting accepted connections
+    async fn counting_server() -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let connections = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buffer = [0u8; 4096];
+                    let mut pending = Vec::new();
+                    while let Ok(read) = socket.read(&mut buffer).await {
+                        if read == 0 {
+                            break;
+                        }
+                        pending.extend_from_slice(&buffer[..read]);
+                        while let Some(end) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+                            pending.drain(..end + 4);
+                            let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                            if socket.write_all(response).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        (format!("http://{}", address), connections)
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests_reuse_connection() {
+        // Test: Bulk requests to one host share a pooled keep-alive connection
+        let (base, connections) = counting_server().await;
+        let client = APIClient::new(test_config()).unwrap();
+
+        for i in 0..5 {
+            let response = client.get(&format!("{}/pkg/{}", base, i)).await.unwrap();
+            assert_eq!(response.text().unwrap(), "ok");
+        }
+        assert_eq!(
+            connections.load(std::sync::atomic::Ordering::SeqCst),
+            1,
+            "All requests should reuse one connection"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_pool_opens_new_connections() {
+        // Test: With no idle connections kept, every request reconnects
+        let (base, connections) = counting_server().await;
+        let client = APIClient::new(HttpClientConfig {
+            pool: ConnectionPoolConfig {
+                max_idle_per_host: 0,
+                ..ConnectionPoolConfig::default()
+            },
+            ..test_config()
+        })
+        .unwrap();
+
+        for i in 0..3 {
+            client.get(&format!("{}/pkg/{}", base, i)).await.unwrap();
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+}
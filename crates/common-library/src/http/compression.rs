@@ -0,0 +1,143 @@
+//! Request and response compression
+//!
+//! With the `gzip` or `brotli` feature enabled, the client advertises the
+//! codec in `Accept-Encoding` and decodes compressed responses
+//! transparently. Large request bodies, such as GraphQL batches and bulk
+//! exports, can also be compressed before sending.
+
+use crate::error::Result;
+use reqwest::header::{CONTENT_ENCODING, HeaderMap, HeaderValue};
+
+/// Codec used to compress request bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    #[cfg(feature = "gzip")]
+    Gzip,
+    #[cfg(feature = "brotli")]
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// Token sent in `Content-Encoding`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => "gzip",
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// Compress a body with this codec
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "brotli")),
+        allow(unused_variables)
+    )]
+    pub fn encode(self, body: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            ContentEncoding::Gzip => crate::utils::compression::compress_gzip(body),
+            #[cfg(feature = "brotli")]
+            ContentEncoding::Brotli => crate::utils::compression::compress_brotli(body),
+        }
+    }
+}
+
+/// Compression applied to outgoing request bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestCompression {
+    pub encoding: ContentEncoding,
+    /// Bodies smaller than this are sent uncompressed
+    pub min_size: usize,
+}
+
+impl RequestCompression {
+    /// Compress bodies of at least 8 KiB with `encoding`
+    pub fn new(encoding: ContentEncoding) -> Self {
+        Self {
+            encoding,
+            min_size: 8 * 1024,
+        }
+    }
+
+    /// Compress `body` in place if it is large enough and not already encoded
+    pub(crate) fn apply(&self, headers: &mut HeaderMap, body: &mut Vec<u8>) -> Result<()> {
+        if body.len() < self.min_size || headers.contains_key(CONTENT_ENCODING) {
+            return Ok(());
+        }
+        *body = self.encoding.encode(body)?;
+        headers.insert(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(self.encoding.as_str()),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "gzip"))]
+mod tests {
+    use super::*;
+    use crate::http::client::{APIClient, HttpClientConfig};
+    use crate::utils::compression::{compress_gzip, decompress_gzip};
+    use wiremock::matchers::{header, header_exists, method};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_large_request_bodies_are_compressed() {
+        // Test: Bodies above the threshold are gzipped and small bodies are left alone
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("Content-Encoding", "gzip"))
+            .respond_with(|request: &Request| {
+                let body = decompress_gzip(&request.body).unwrap();
+                ResponseTemplate::new(200).set_body_string(body.len().to_string())
+            })
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("plain"))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            request_compression: Some(RequestCompression {
+                encoding: ContentEncoding::Gzip,
+                min_size: 100,
+            }),
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let url = format!("{}/graphql", server.uri());
+
+        let large = client.post_json(&url, &"x".repeat(500)).await.unwrap();
+        assert_eq!(
+            large.text().unwrap(),
+            "502",
+            "Server should see the original body"
+        );
+        let small = client.post_json(&url, &"x").await.unwrap();
+        assert_eq!(small.text().unwrap(), "plain");
+    }
+
+    #[tokio::test]
+    async fn test_compressed_responses_are_decoded() {
+        // Test: gzip responses are advertised for and decoded transparently
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header_exists("Accept-Encoding"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "gzip")
+                    .set_body_bytes(compress_gzip(b"{\"name\":\"left-pad\"}").unwrap()),
+            )
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let response = client
+            .get(&format!("{}/left-pad", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"{\"name\":\"left-pad\"}");
+    }
+}
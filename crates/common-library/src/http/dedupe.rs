@@ -0,0 +1,152 @@
+//! Coalescing of concurrent identical requests
+//!
+//! During dependency graph expansion many tasks ask for the same package at
+//! once. With deduplication enabled, the first caller performs the request
+//! and every concurrent caller for the same key shares its response.
+
+use super::client::ApiResponse;
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+type SharedResult = std::result::Result<ApiResponse, Arc<Error>>;
+
+/// Requests currently in flight, keyed by request identity
+#[derive(Debug, Default)]
+pub(crate) struct InFlightRequests {
+    requests: Mutex<HashMap<String, Arc<OnceCell<SharedResult>>>>,
+}
+
+impl InFlightRequests {
+    /// Run `request`, or join an identical request already in flight
+    ///
+    /// If the caller performing the request is cancelled, one of the
+    /// waiting callers takes over.
+    pub(crate) async fn run<F, Fut>(&self, key: &str, request: F) -> Result<ApiResponse>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ApiResponse>>,
+    {
+        let cell = self
+            .lock_requests()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        let result = cell
+            .get_or_init(|| async { request().await.map_err(Arc::new) })
+            .await;
+
+        let mut requests = self.lock_requests();
+        if requests
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            requests.remove(key);
+        }
+
+        result.clone().map_err(|e| share_error(&e))
+    }
+
+    /// Number of distinct requests in flight
+    pub(crate) fn len(&self) -> usize {
+        self.lock_requests().len()
+    }
+
+    /// The map is valid even if a holder panicked, so a poisoned lock is recovered
+    fn lock_requests(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<String, Arc<OnceCell<SharedResult>>>> {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Copy an error for every caller sharing a failed request
+fn share_error(error: &Error) -> Error {
+    match error {
+        Error::Http(message) => Error::Http(message.clone()),
+        Error::ResponseTooLarge { url, limit } => Error::response_too_large(url.clone(), *limit),
+        other => Error::http(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::{APIClient, HttpClientConfig};
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_concurrent_gets_share_one_request() {
+        // Test: Concurrent GETs for the same URL hit the server once
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("shared")
+                    .set_delay(std::time::Duration::from_millis(50)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            dedupe: true,
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let url = format!("{}/pkg", server.uri());
+        let (a, b, c) = tokio::join!(client.get(&url), client.get(&url), client.get(&url));
+
+        for response in [a, b, c] {
+            assert_eq!(response.unwrap().text().unwrap(), "shared");
+        }
+        assert_eq!(
+            client.in_flight_requests(),
+            0,
+            "Completed requests are forgotten"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_failures_reach_every_caller() {
+        // Test: A failed request is reported to all coalesced callers
+        let in_flight = InFlightRequests::default();
+        let failing = || async { Err(Error::response_too_large("http://x", 1)) };
+        let (a, b) = tokio::join!(in_flight.run("key", failing), in_flight.run("key", failing));
+        assert!(matches!(a, Err(Error::ResponseTooLarge { .. })));
+        assert!(matches!(b, Err(Error::ResponseTooLarge { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_poisoned_lock_does_not_break_requests() {
+        // Test: A panic while the map is locked does not fail later requests
+        let in_flight = InFlightRequests::default();
+        std::thread::scope(|scope| {
+            let _ = scope
+                .spawn(|| {
+                    let _guard = in_flight.requests.lock().unwrap();
+                    panic!("poison the lock");
+                })
+                .join();
+        });
+        assert!(in_flight.requests.is_poisoned());
+
+        let response = in_flight
+            .run("key", || async {
+                Ok(ApiResponse {
+                    status: 200,
+                    headers: Default::default(),
+                    body: b"ok".to_vec(),
+                    from_cache: false,
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(in_flight.len(), 0, "Completed requests are forgotten");
+    }
+}
@@ -0,0 +1,745 @@
+//! Streaming downloads for large responses
+//!
+//! Registry dumps can be several gigabytes, so bodies are exposed as a
+//! stream of chunks instead of being buffered in memory. Interrupted
+//! downloads can be resumed with HTTP `Range` requests, guarded by `If-Range`
+//! so a file that changed on the server is fetched again from the start.
+
+use super::client::{APIClient, ApiResponse, host_of};
+use super::middleware::MiddlewareRequest;
+use super::scheduler::RequestPriority;
+use crate::error::{Error, Result};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use reqwest::Method;
+use reqwest::header::{
+    ACCEPT_ENCODING, CONTENT_RANGE, ETAG, HeaderMap, HeaderValue, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tracing::warn;
+
+/// Download progress reported after every chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub bytes_downloaded: u64,
+    /// Total size from `Content-Length`, when known
+    pub total_bytes: Option<u64>,
+}
+
+/// Callback invoked with download progress
+pub type ProgressCallback = Arc<dyn Fn(&DownloadProgress) + Send + Sync>;
+
+/// A response body delivered as a stream of chunks
+pub struct ResponseStream {
+    status: u16,
+    headers: HeaderMap,
+    content_length: Option<u64>,
+    inner: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    bytes_downloaded: u64,
+    hasher: Option<Sha256>,
+    progress: Option<ProgressCallback>,
+}
+
+impl ResponseStream {
+    fn new(response: reqwest::Response) -> Self {
+        Self {
+            status: response.status().as_u16(),
+            headers: response.headers().clone(),
+            content_length: response.content_length(),
+            inner: Box::pin(response.bytes_stream()),
+            bytes_downloaded: 0,
+            hasher: None,
+            progress: None,
+        }
+    }
+
+    fn from_buffered(response: ApiResponse) -> Self {
+        let body = Bytes::from(response.body);
+        Self {
+            status: response.status,
+            headers: response.headers,
+            content_length: Some(body.len() as u64),
+            inner: Box::pin(futures::stream::once(async move { Ok(body) })),
+            bytes_downloaded: 0,
+            hasher: None,
+            progress: None,
+        }
+    }
+
+    /// Report progress after every chunk
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Compute a SHA-256 checksum of the body while it streams
+    pub fn with_checksum(mut self) -> Self {
+        self.hasher = Some(Sha256::new());
+        self
+    }
+
+    /// Response status code
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Response headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Body size from `Content-Length`, when known
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Bytes received so far
+    pub fn bytes_downloaded(&self) -> u64 {
+        self.bytes_downloaded
+    }
+
+    /// Hex-encoded SHA-256 of the bytes received so far
+    ///
+    /// Returns `None` unless [`ResponseStream::with_checksum`] was called.
+    pub fn sha256_hex(&self) -> Option<String> {
+        self.hasher
+            .as_ref()
+            .map(|hasher| hex::encode(hasher.clone().finalize()))
+    }
+
+    /// Convert the stream into an [`AsyncRead`]
+    ///
+    /// Progress callbacks keep firing, but the checksum is no longer accessible.
+    pub fn into_async_read(self) -> impl AsyncRead + Send + Unpin {
+        tokio_util::io::StreamReader::new(
+            self.map(|chunk| chunk.map_err(|e| std::io::Error::other(e.to_string()))),
+        )
+    }
+}
+
+impl Stream for ResponseStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.bytes_downloaded += chunk.len() as u64;
+                if let Some(hasher) = self.hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+                if let Some(progress) = &self.progress {
+                    progress(&DownloadProgress {
+                        bytes_downloaded: self.bytes_downloaded,
+                        total_bytes: self.content_length,
+                    });
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(Error::http(format!(
+                "Failed to read response stream: {}",
+                e
+            ))))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("status", &self.status)
+            .field("content_length", &self.content_length)
+            .field("bytes_downloaded", &self.bytes_downloaded)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options for [`APIClient::download_to_file`]
+#[derive(Clone, Default)]
+pub struct DownloadOptions {
+    pub progress: Option<ProgressCallback>,
+    /// Fail (and remove the file) if the SHA-256 of the body does not match
+    pub expected_sha256: Option<String>,
+}
+
+impl fmt::Debug for DownloadOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DownloadOptions")
+            .field("progress", &self.progress.is_some())
+            .field("expected_sha256", &self.expected_sha256)
+            .finish()
+    }
+}
+
+/// Result of a completed download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadSummary {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+impl APIClient {
+    /// Make a GET request and stream the response body
+    ///
+    /// Request middleware runs as usual; response hooks are skipped because
+    /// the body is never buffered. Streamed bodies are replayed from a
+    /// cassette but never recorded.
+    pub async fn get_stream(&self, url: &str) -> Result<ResponseStream> {
+        let stream = self.open_stream(url, HeaderMap::new()).await?;
+        if !(200..300).contains(&stream.status()) {
+            return Err(Error::http(format!(
+                "GET {} failed with status {}",
+                url,
+                stream.status()
+            )));
+        }
+        Ok(stream)
+    }
+
+    /// Start a streamed GET without checking the response status
+    async fn open_stream(&self, url: &str, headers: HeaderMap) -> Result<ResponseStream> {
+        let mut request = MiddlewareRequest {
+            method: Method::GET,
+            url: url.to_string(),
+            headers,
+        };
+        if let Some((response, _)) = self.apply_request_middleware(&mut request)? {
+            return Ok(ResponseStream::from_buffered(response));
+        }
+        if let Some(response) = self.replay(&request)? {
+            return Ok(ResponseStream::from_buffered(response));
+        }
+
+        let permit = self.schedule(RequestPriority::Normal).await;
+        let started = Instant::now();
+        let mut retries = 0;
+        let response = self
+            .send(
+                request.method.clone(),
+                &request.url,
+                request.headers.clone(),
+                None,
+                None,
+                &mut retries,
+            )
+            .await;
+        drop(permit);
+        self.record_metrics(
+            &request,
+            started,
+            retries,
+            match &response {
+                Ok(response) => Ok((response.status().as_u16(), None)),
+                Err(e) => Err(e),
+            },
+        );
+        Ok(ResponseStream::new(response?))
+    }
+
+    /// Stream a response body to a file, verifying its checksum on the fly
+    pub async fn download_to_file(
+        &self,
+        url: &str,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<DownloadSummary> {
+        let path = path.as_ref();
+        let mut stream = self.get_stream(url).await?.with_checksum();
+        if let Some(progress) = options.progress {
+            stream = stream.with_progress(progress);
+        }
+
+        let mut file = tokio::fs::File::create(path).await?;
+        while let Some(chunk) = stream.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+
+        let sha256 = stream.sha256_hex().unwrap_or_default();
+        if let Some(expected) = &options.expected_sha256
+            && !expected.eq_ignore_ascii_case(&sha256)
+        {
+            tokio::fs::remove_file(path).await?;
+            return Err(Error::http(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                url, expected, sha256
+            )));
+        }
+
+        Ok(DownloadSummary {
+            path: path.to_path_buf(),
+            bytes: stream.bytes_downloaded(),
+            sha256,
+        })
+    }
+
+    /// Download to a file, resuming a previous partial download
+    ///
+    /// The body is written to `<path>.part`, which is continued with a
+    /// `Range` request if it already exists, and renamed to `path` once the
+    /// size matches the server's and the checksum is verified. The response's
+    /// `ETag` or `Last-Modified` is kept in `<path>.part.validator` and sent
+    /// as `If-Range`, so a changed file restarts from zero. Connections
+    /// dropped mid-body are resumed up to the host's `retry.max_retries` times.
+    pub async fn download_resumable(
+        &self,
+        url: &str,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> Result<DownloadSummary> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+
+        let retry = self.config().retry_for(&host_of(url)?);
+        let mut interruptions = 0;
+        while !self.resume_download(url, &partial, &options).await? {
+            interruptions += 1;
+            if interruptions > retry.max_retries {
+                return Err(Error::http(format!(
+                    "Download of {} interrupted {} times, keeping partial file {}",
+                    url,
+                    interruptions,
+                    partial.display()
+                )));
+            }
+            tokio::time::sleep(retry.calculate_backoff(interruptions)).await;
+        }
+
+        let (bytes, sha256) = file_sha256(&partial).await?;
+        if let Some(expected) = &options.expected_sha256
+            && !expected.eq_ignore_ascii_case(&sha256)
+        {
+            tokio::fs::remove_file(&partial).await?;
+            return Err(Error::http(format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                url, expected, sha256
+            )));
+        }
+        tokio::fs::rename(&partial, path).await?;
+        remove_validator(&partial).await;
+
+        Ok(DownloadSummary {
+            path: path.to_path_buf(),
+            bytes,
+            sha256,
+        })
+    }
+
+    /// Continue a partial download; returns `false` if it was cut short
+    async fn resume_download(
+        &self,
+        url: &str,
+        partial: &Path,
+        options: &DownloadOptions,
+    ) -> Result<bool> {
+        let offset = match tokio::fs::metadata(partial).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        // Byte offsets only line up with the file if the body is not re-encoded
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
+        if offset > 0 {
+            let range = HeaderValue::from_str(&format!("bytes={}-", offset))
+                .map_err(|e| Error::http(format!("Invalid range header: {}", e)))?;
+            headers.insert(RANGE, range);
+            if let Ok(validator) = tokio::fs::read_to_string(validator_path(partial)).await
+                && let Ok(value) = HeaderValue::from_str(validator.trim())
+            {
+                headers.insert(IF_RANGE, value);
+            }
+        }
+
+        let mut stream = self.open_stream(url, headers).await?;
+        let content_range = stream
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_content_range);
+        let (mut file, start, total) = match stream.status() {
+            206 => {
+                let (start, total) = content_range.ok_or_else(|| {
+                    Error::http(format!("Partial response from {} lacks Content-Range", url))
+                })?;
+                if start != Some(offset) {
+                    return Err(Error::http(format!(
+                        "Server resumed {} at the wrong offset: expected {}",
+                        url, offset
+                    )));
+                }
+                let file = tokio::fs::OpenOptions::new()
+                    .append(true)
+                    .open(partial)
+                    .await?;
+                (file, offset, total)
+            }
+            200 => {
+                if offset > 0 {
+                    warn!(
+                        "{} does not support range requests, restarting download",
+                        url
+                    );
+                }
+                let total = stream.content_length();
+                let file = tokio::fs::File::create(partial).await?;
+                save_validator(partial, stream.headers()).await?;
+                (file, 0, total)
+            }
+            416 if offset > 0 => {
+                // Nothing left to fetch if the partial file is already complete
+                if content_range.and_then(|(_, total)| total) == Some(offset) {
+                    return Ok(true);
+                }
+                tokio::fs::remove_file(partial).await?;
+                remove_validator(partial).await;
+                return Ok(false);
+            }
+            status => {
+                return Err(Error::http(format!(
+                    "GET {} failed with status {}",
+                    url, status
+                )));
+            }
+        };
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => file.write_all(&chunk).await?,
+                Err(e) => {
+                    file.flush().await?;
+                    warn!("Download of {} interrupted: {}", url, e);
+                    return Ok(false);
+                }
+            }
+            if let Some(progress) = &options.progress {
+                progress(&DownloadProgress {
+                    bytes_downloaded: start + stream.bytes_downloaded(),
+                    total_bytes: total,
+                });
+            }
+        }
+        file.flush().await?;
+
+        let written = start + stream.bytes_downloaded();
+        Ok(total.is_none_or(|total| written == total))
+    }
+}
+
+/// Sidecar file holding the validator a partial download was started with
+fn validator_path(partial: &Path) -> PathBuf {
+    let mut path = partial.as_os_str().to_owned();
+    path.push(".validator");
+    PathBuf::from(path)
+}
+
+/// Remember the response's strong `ETag`, or else its `Last-Modified` date
+///
+/// Weak ETags cannot be used with `If-Range`; without any validator the
+/// download resumes unguarded.
+async fn save_validator(partial: &Path, headers: &HeaderMap) -> Result<()> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|v: &HeaderValue| v.to_str().ok())
+    };
+    let validator = header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED));
+    match validator {
+        Some(validator) => tokio::fs::write(validator_path(partial), validator).await?,
+        None => remove_validator(partial).await,
+    }
+    Ok(())
+}
+
+async fn remove_validator(partial: &Path) {
+    let _ = tokio::fs::remove_file(validator_path(partial)).await;
+}
+
+/// Parse `bytes <start>-<end>/<total>` or `bytes */<total>`
+fn parse_content_range(value: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = match range {
+        "*" => None,
+        range => Some(range.split_once('-')?.0.trim().parse().ok()?),
+    };
+    Some((start, total.trim().parse().ok()))
+}
+
+/// Size and hex-encoded SHA-256 of a file
+async fn file_sha256(path: &Path) -> Result<(u64, String)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut bytes = 0;
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        bytes += read as u64;
+    }
+    Ok((bytes, hex::encode(hasher.finalize())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const BODY_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    async fn server_with_body() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello world"))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_stream_with_checksum_and_progress() {
+        // Test: Streaming reports progress and computes the body checksum
+        let server = server_with_body().await;
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let seen = Arc::new(AtomicU64::new(0));
+        let recorded = seen.clone();
+
+        let mut stream = client
+            .get_stream(&format!("{}/dump", server.uri()))
+            .await
+            .unwrap()
+            .with_checksum()
+            .with_progress(Arc::new(move |p: &DownloadProgress| {
+                recorded.store(p.bytes_downloaded, Ordering::SeqCst);
+            }));
+
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            body.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(body, b"hello world");
+        assert_eq!(seen.load(Ordering::SeqCst), 11);
+        assert_eq!(stream.sha256_hex().as_deref(), Some(BODY_SHA256));
+    }
+
+    #[tokio::test]
+    async fn test_stream_as_async_read() {
+        // Test: The stream can be consumed through AsyncRead
+        let server = server_with_body().await;
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let stream = client
+            .get_stream(&format!("{}/dump", server.uri()))
+            .await
+            .unwrap();
+
+        let mut reader = stream.into_async_read();
+        let mut body = String::new();
+        reader.read_to_string(&mut body).await.unwrap();
+        assert_eq!(body, "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_verifies_checksum() {
+        // Test: Downloads are written to disk and checksum mismatches are rejected
+        let server = server_with_body().await;
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let url = format!("{}/dump", server.uri());
+        let path = std::env::temp_dir().join(format!(
+            "download-{}.bin",
+            crate::utils::crypto::generate_uuid()
+        ));
+
+        let summary = client
+            .download_to_file(
+                &url,
+                &path,
+                DownloadOptions {
+                    expected_sha256: Some(BODY_SHA256.to_string()),
+                    ..DownloadOptions::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.bytes, 11);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+
+        let result = client
+            .download_to_file(
+                &url,
+                &path,
+                DownloadOptions {
+                    expected_sha256: Some("00".to_string()),
+                    ..DownloadOptions::default()
+                },
+            )
+            .await;
+        assert!(result.is_err(), "Checksum mismatch should fail");
+        assert!(!path.exists(), "Corrupt download should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        // Test: An existing partial file is continued with a Range request and verified
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Range", "bytes=6-"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("Content-Range", "bytes 6-10/11")
+                    .set_body_string("world"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "resume-{}.bin",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let partial = PathBuf::from(format!("{}.part", path.display()));
+        std::fs::write(&partial, "hello ").unwrap();
+
+        let summary = client
+            .download_resumable(
+                &format!("{}/dump", server.uri()),
+                &path,
+                DownloadOptions {
+                    expected_sha256: Some(BODY_SHA256.to_string()),
+                    ..DownloadOptions::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.bytes, 11);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        assert!(!partial.exists(), "Partial file should be renamed");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_without_range_support() {
+        // Test: A full 200 response replaces stale partial content
+        let server = server_with_body().await;
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "resume-{}.bin",
+            crate::utils::crypto::generate_uuid()
+        ));
+        std::fs::write(format!("{}.part", path.display()), "stale").unwrap();
+
+        let summary = client
+            .download_resumable(
+                &format!("{}/dump", server.uri()),
+                &path,
+                DownloadOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(summary.sha256, BODY_SHA256);
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resume_sends_saved_validator_as_if_range() {
+        // Test: A changed file answers If-Range with 200 and the download restarts
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Range", "bytes=5-"))
+            .and(header("If-Range", "\"v1\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v2\"")
+                    .set_body_string("hello world"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "resume-{}.bin",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let partial = PathBuf::from(format!("{}.part", path.display()));
+        std::fs::write(&partial, "HELLO").unwrap();
+        std::fs::write(validator_path(&partial), "\"v1\"").unwrap();
+
+        let summary = client
+            .download_resumable(
+                &format!("{}/dump", server.uri()),
+                &path,
+                DownloadOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            summary.sha256, BODY_SHA256,
+            "Stale bytes should be discarded"
+        );
+        assert!(
+            !validator_path(&partial).exists(),
+            "Validator should be removed once complete"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_validator_is_saved_with_partial_file() {
+        // Test: The ETag of an interrupted download is kept for the next attempt
+        let path = std::env::temp_dir().join(format!(
+            "resume-{}.bin.part",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        save_validator(&path, &headers).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(validator_path(&path)).unwrap(),
+            "\"abc\""
+        );
+
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        save_validator(&path, &headers).await.unwrap();
+        assert_eq!(
+            std::fs::read_to_string(validator_path(&path)).unwrap(),
+            "Wed, 21 Oct 2015 07:28:00 GMT",
+            "Weak ETags fall back to Last-Modified"
+        );
+        remove_validator(&path).await;
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        // Test: Both satisfied and unsatisfied Content-Range forms are parsed
+        assert_eq!(
+            parse_content_range("bytes 6-10/11"),
+            Some((Some(6), Some(11)))
+        );
+        assert_eq!(parse_content_range("bytes */11"), Some((None, Some(11))));
+        assert_eq!(parse_content_range("bytes 0-9/*"), Some((Some(0), None)));
+        assert_eq!(parse_content_range("items 0-9/10"), None);
+    }
+}
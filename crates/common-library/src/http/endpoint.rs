@@ -0,0 +1,314 @@
+//! Typed endpoint definitions
+//!
+//! An [`Endpoint`] pairs a method and path template with the type its
+//! response deserializes to, so collectors declare their API surface once
+//! instead of formatting URLs by hand:
+//!
+//! ```ignore
+//! const PACKAGE: Endpoint<Package> = Endpoint::get("/packages/{name}");
+//!
+//! let package = PACKAGE
+//!     .request("https://registry.example.com")
+//!     .param("name", "@scope/pkg")
+//!     .query("fields", "versions")
+//!     .send(&client)
+//!     .await?;
+//! ```
+
+use super::client::{APIClient, ApiResponse};
+use crate::error::{Error, Result};
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::{Method, Url};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// An API endpoint returning `T`
+///
+/// Path templates use `{name}` placeholders, which are filled in and
+/// percent-encoded per segment.
+pub struct Endpoint<T> {
+    method: Method,
+    template: &'static str,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T> Endpoint<T> {
+    /// Define an endpoint with an arbitrary method
+    pub const fn new(method: Method, template: &'static str) -> Self {
+        Self {
+            method,
+            template,
+            response: PhantomData,
+        }
+    }
+
+    /// Define a GET endpoint
+    pub const fn get(template: &'static str) -> Self {
+        Self::new(Method::GET, template)
+    }
+
+    /// Define a POST endpoint
+    pub const fn post(template: &'static str) -> Self {
+        Self::new(Method::POST, template)
+    }
+
+    /// The path template
+    pub fn template(&self) -> &'static str {
+        self.template
+    }
+
+    /// Start a request against `base_url`
+    pub fn request(&self, base_url: impl Into<String>) -> EndpointRequest<T> {
+        EndpointRequest {
+            method: self.method.clone(),
+            base_url: base_url.into(),
+            template: self.template,
+            params: HashMap::new(),
+            query: Vec::new(),
+            body: None,
+            response: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Endpoint<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.method.clone(), self.template)
+    }
+}
+
+impl<T> fmt::Debug for Endpoint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Endpoint({} {})", self.method, self.template)
+    }
+}
+
+/// A request to an [`Endpoint`] being built
+pub struct EndpointRequest<T> {
+    method: Method,
+    base_url: String,
+    template: &'static str,
+    params: HashMap<&'static str, String>,
+    query: Vec<(String, String)>,
+    body: Option<Result<Vec<u8>>>,
+    response: PhantomData<fn() -> T>,
+}
+
+impl<T> EndpointRequest<T> {
+    /// Fill in a `{name}` path placeholder
+    pub fn param(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.insert(name, value.to_string());
+        self
+    }
+
+    /// Append a query parameter
+    pub fn query(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.query.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Append a query parameter if a value is present
+    pub fn query_opt<V: ToString>(self, name: impl Into<String>, value: Option<V>) -> Self {
+        match value {
+            Some(value) => self.query(name, value),
+            None => self,
+        }
+    }
+
+    /// Send `body` serialized as JSON
+    pub fn json<B: Serialize + ?Sized>(mut self, body: &B) -> Self {
+        self.body = Some(serde_json::to_vec(body).map_err(Error::from));
+        self
+    }
+
+    /// Build the full request URL
+    ///
+    /// Fails if a placeholder has no value or a value has no placeholder.
+    pub fn url(&self) -> Result<String> {
+        let mut url = Url::parse(&self.base_url)
+            .map_err(|e| Error::http(format!("Invalid base URL {}: {}", self.base_url, e)))?;
+        let mut used = HashSet::new();
+        {
+            let mut segments = url.path_segments_mut().map_err(|_| {
+                Error::http(format!("Base URL {} cannot have a path", self.base_url))
+            })?;
+            segments.pop_if_empty();
+            for segment in self.template.split('/').filter(|s| !s.is_empty()) {
+                segments.push(&self.fill(segment, &mut used)?);
+            }
+        }
+        if used.len() != self.params.len() {
+            return Err(Error::http(format!(
+                "Unused path parameters for {}",
+                self.template
+            )));
+        }
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        Ok(url.into())
+    }
+
+    /// Send the request, returning the raw response
+    pub async fn send_raw(self, client: &APIClient) -> Result<ApiResponse> {
+        let url = self.url()?;
+        if self.method == Method::GET && self.body.is_none() {
+            return client.get(&url).await;
+        }
+
+        let mut headers = HeaderMap::new();
+        let body = match self.body {
+            Some(body) => {
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                Some(body?)
+            }
+            None => None,
+        };
+        let response = client
+            .execute(self.method.clone(), &url, headers, body)
+            .await?;
+        if !response.is_success() {
+            return Err(Error::http(format!(
+                "{} {} failed with status {}",
+                self.method, url, response.status
+            )));
+        }
+        Ok(response)
+    }
+
+    /// Replace the `{name}` placeholders in one path segment
+    fn fill(&self, segment: &'static str, used: &mut HashSet<&'static str>) -> Result<String> {
+        let mut filled = String::new();
+        let mut rest = segment;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| Error::http(format!("Unclosed placeholder in {}", self.template)))?;
+            let name = &rest[start + 1..end];
+            let value = self.params.get(name).ok_or_else(|| {
+                Error::http(format!(
+                    "Missing path parameter {} for {}",
+                    name, self.template
+                ))
+            })?;
+            filled.push_str(&rest[..start]);
+            filled.push_str(value);
+            used.insert(name);
+            rest = &rest[end + 1..];
+        }
+        filled.push_str(rest);
+        Ok(filled)
+    }
+}
+
+impl<T: DeserializeOwned> EndpointRequest<T> {
+    /// Send the request and deserialize the JSON response
+    pub async fn send(self, client: &APIClient) -> Result<T> {
+        self.send_raw(client).await?.json()
+    }
+}
+
+impl<T> fmt::Debug for EndpointRequest<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EndpointRequest")
+            .field("method", &self.method)
+            .field("base_url", &self.base_url)
+            .field("template", &self.template)
+            .field("params", &self.params)
+            .field("query", &self.query)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use serde::Deserialize;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Package {
+        name: String,
+    }
+
+    const PACKAGE: Endpoint<Package> = Endpoint::get("/packages/{name}/{version}.json");
+
+    #[test]
+    fn test_url_encodes_params_and_query() {
+        // Test: Placeholders are percent-encoded and missing or extra params are rejected
+        let url = PACKAGE
+            .request("https://registry.example.com/api/")
+            .param("name", "@scope/pkg")
+            .param("version", "1.0.0")
+            .query("fields", "a b")
+            .query_opt("page", None::<u32>)
+            .url()
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://registry.example.com/api/packages/@scope%2Fpkg/1.0.0.json?fields=a+b"
+        );
+
+        let missing = PACKAGE
+            .request("https://registry.example.com")
+            .param("name", "pkg")
+            .url();
+        assert!(missing.is_err(), "Missing placeholders should fail");
+
+        let extra = PACKAGE
+            .request("https://registry.example.com")
+            .param("name", "pkg")
+            .param("version", "1")
+            .param("tag", "latest")
+            .url();
+        assert!(extra.is_err(), "Unknown parameters should fail");
+    }
+
+    #[tokio::test]
+    async fn test_send_deserializes_typed_response() {
+        // Test: GET and POST endpoints send the built request and decode the body
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/packages/left-pad/1.3.0.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "left-pad"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/search"))
+            .and(query_param("limit", "5"))
+            .and(body_json(serde_json::json!({"q": "pad"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "left-pad"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let package = PACKAGE
+            .request(server.uri())
+            .param("name", "left-pad")
+            .param("version", "1.3.0")
+            .send(&client)
+            .await
+            .unwrap();
+        assert_eq!(package.name, "left-pad");
+
+        let search: Endpoint<Package> = Endpoint::post("/search");
+        let found = search
+            .request(server.uri())
+            .query("limit", 5)
+            .json(&serde_json::json!({"q": "pad"}))
+            .send(&client)
+            .await
+            .unwrap();
+        assert_eq!(found.name, "left-pad");
+    }
+}
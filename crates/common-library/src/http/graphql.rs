@@ -0,0 +1,480 @@
+//! GraphQL alias batching
+//!
+//! A [`GraphQLBatcher`] packs many independent lookups into one query by
+//! giving each an alias (`l0: repository(...) { ... }`), so an org-wide scan
+//! costs one request per batch instead of one per repository. Batches are
+//! split to stay under size and cost limits, and a batch rejected as too
+//! large or too costly is halved and retried until it fits. Other failures,
+//! such as bad credentials or a server error, fail every lookup in the batch.
+
+use super::client::APIClient;
+use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use reqwest::Method;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+/// One aliased field in a batched query
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLLookup {
+    /// Field with arguments and selection, e.g. `repository(owner: "a", name: "b") { id }`
+    pub field: String,
+    /// Estimated query cost counted against [`GraphQLBatcher::max_cost`]
+    pub cost: u32,
+}
+
+impl GraphQLLookup {
+    /// A lookup with a cost of 1
+    pub fn new(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            cost: 1,
+        }
+    }
+
+    /// Set the estimated cost
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// GitHub `repository(owner:, name:)` lookup
+    pub fn repository(owner: &str, name: &str, selection: &str) -> Self {
+        Self::new(format!(
+            "repository(owner: {}, name: {}) {{ {} }}",
+            graphql_string(owner),
+            graphql_string(name),
+            selection
+        ))
+    }
+}
+
+/// Packs lookups into aliased GraphQL queries
+#[derive(Debug, Clone)]
+pub struct GraphQLBatcher {
+    /// GraphQL endpoint URL
+    pub endpoint: String,
+    /// Maximum lookups per query
+    pub max_batch_size: usize,
+    /// Maximum summed lookup cost per query
+    pub max_cost: u32,
+}
+
+impl GraphQLBatcher {
+    /// Batch up to 50 lookups per query against `endpoint`
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            max_batch_size: 50,
+            max_cost: 1000,
+        }
+    }
+
+    /// GitHub's GraphQL API
+    pub fn github() -> Self {
+        Self::new("https://api.github.com/graphql")
+    }
+
+    /// Set the maximum lookups per query
+    pub fn with_max_batch_size(mut self, size: usize) -> Self {
+        self.max_batch_size = size.max(1);
+        self
+    }
+
+    /// Set the maximum summed cost per query
+    pub fn with_max_cost(mut self, cost: u32) -> Self {
+        self.max_cost = cost;
+        self
+    }
+
+    /// Group lookups by index into batches within the size and cost limits
+    ///
+    /// A lookup costing more than `max_cost` is sent alone.
+    pub fn plan(&self, lookups: &[GraphQLLookup]) -> Vec<Vec<usize>> {
+        let mut batches = Vec::new();
+        let mut current: Vec<usize> = Vec::new();
+        let mut cost = 0u32;
+        for (index, lookup) in lookups.iter().enumerate() {
+            let over_cost = cost.saturating_add(lookup.cost) > self.max_cost;
+            if !current.is_empty() && (current.len() >= self.max_batch_size || over_cost) {
+                batches.push(std::mem::take(&mut current));
+                cost = 0;
+            }
+            current.push(index);
+            cost = cost.saturating_add(lookup.cost);
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+        batches
+    }
+
+    /// Run all lookups, returning each field's data in input order
+    ///
+    /// Errors reported against one alias fail only that lookup. A batch
+    /// rejected for its size or cost is split in half and retried; any other
+    /// failure is reported for every lookup in the batch.
+    pub async fn execute(
+        &self,
+        client: &APIClient,
+        lookups: &[GraphQLLookup],
+    ) -> Vec<Result<Value>> {
+        let mut results: Vec<Option<Result<Value>>> = lookups.iter().map(|_| None).collect();
+        for batch in self.plan(lookups) {
+            for (index, result) in self.run_batch(client, lookups, batch).await {
+                results[index] = Some(result);
+            }
+        }
+        results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(Error::processing("GraphQL lookup was not run"))))
+            .collect()
+    }
+
+    fn run_batch<'a>(
+        &'a self,
+        client: &'a APIClient,
+        lookups: &'a [GraphQLLookup],
+        batch: Vec<usize>,
+    ) -> BoxFuture<'a, Vec<(usize, Result<Value>)>> {
+        Box::pin(async move {
+            let query = batch_query(batch.iter().map(|&i| &lookups[i]));
+            let response = match self.send(client, &query).await {
+                Ok(response) => response,
+                Err(BatchFailure::TooLarge(e)) if batch.len() > 1 => {
+                    debug!(
+                        "Splitting GraphQL batch of {} after error: {}",
+                        batch.len(),
+                        e
+                    );
+                    let (left, right) = batch.split_at(batch.len() / 2);
+                    let mut results = self.run_batch(client, lookups, left.to_vec()).await;
+                    results.extend(self.run_batch(client, lookups, right.to_vec()).await);
+                    return results;
+                }
+                Err(BatchFailure::TooLarge(e) | BatchFailure::Failed(e)) => {
+                    let message = e.to_string();
+                    return batch
+                        .iter()
+                        .map(|&index| (index, Err(Error::http(message.clone()))))
+                        .collect();
+                }
+            };
+
+            batch
+                .iter()
+                .enumerate()
+                .map(|(position, &index)| {
+                    let alias = alias(position);
+                    let result = match alias_error(&response, &alias) {
+                        Some(message) => Err(Error::http(format!(
+                            "GraphQL lookup {} failed: {}",
+                            lookups[index].field, message
+                        ))),
+                        None => Ok(response["data"][&alias].clone()),
+                    };
+                    (index, result)
+                })
+                .collect()
+        })
+    }
+
+    /// POST a query, failing if the response carries no data at all
+    async fn send(
+        &self,
+        client: &APIClient,
+        query: &str,
+    ) -> std::result::Result<Value, BatchFailure> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        let body = serde_json::to_vec(&serde_json::json!({ "query": query }))
+            .map_err(|e| BatchFailure::Failed(e.into()))?;
+        let response = client
+            .execute(Method::POST, &self.endpoint, headers, Some(body))
+            .await
+            .map_err(BatchFailure::Failed)?;
+        if !response.is_success() {
+            let error = Error::http(format!(
+                "POST {} failed with status {}",
+                self.endpoint, response.status
+            ));
+            return Err(match response.status {
+                413 => BatchFailure::TooLarge(error),
+                _ => BatchFailure::Failed(error),
+            });
+        }
+
+        let response: Value = response.json().map_err(BatchFailure::Failed)?;
+        if response["data"].is_object() {
+            return Ok(response);
+        }
+        let first = response["errors"]
+            .as_array()
+            .and_then(|errors| errors.first());
+        let message = first
+            .and_then(|error| error["message"].as_str())
+            .unwrap_or("response has no data");
+        warn!("GraphQL batch rejected: {}", message);
+        let error = Error::http(format!("GraphQL query failed: {}", message));
+        Err(match first {
+            Some(first) if exceeds_limits(first) => BatchFailure::TooLarge(error),
+            _ => BatchFailure::Failed(error),
+        })
+    }
+}
+
+/// Why a batch query produced no data
+enum BatchFailure {
+    /// Rejected for its size or cost; smaller batches may succeed
+    TooLarge(Error),
+    Failed(Error),
+}
+
+/// Whether a GraphQL error rejects the query for its size or complexity
+fn exceeds_limits(error: &Value) -> bool {
+    const CODES: [&str; 3] = [
+        "MAX_NODE_LIMIT_EXCEEDED",
+        "RESOURCE_LIMITS_EXCEEDED",
+        "QUERY_TOO_COMPLEX",
+    ];
+    const PHRASES: [&str; 4] = ["complexity", "cost", "possible nodes", "too large"];
+
+    let code = error["type"]
+        .as_str()
+        .or_else(|| error["extensions"]["code"].as_str());
+    let message = error["message"]
+        .as_str()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    code.is_some_and(|code| CODES.contains(&code))
+        || PHRASES.iter().any(|phrase| message.contains(phrase))
+}
+
+/// Build `query { l0: ... l1: ... }` from lookups in order
+pub fn batch_query<'a>(lookups: impl IntoIterator<Item = &'a GraphQLLookup>) -> String {
+    let fields: Vec<String> = lookups
+        .into_iter()
+        .enumerate()
+        .map(|(position, lookup)| format!("{}: {}", alias(position), lookup.field))
+        .collect();
+    format!("query {{ {} }}", fields.join(" "))
+}
+
+fn alias(position: usize) -> String {
+    format!("l{}", position)
+}
+
+/// First error message whose `path` starts at `alias`
+fn alias_error(response: &Value, alias: &str) -> Option<String> {
+    response["errors"]
+        .as_array()?
+        .iter()
+        .find(|error| error["path"][0].as_str() == Some(alias))
+        .map(|error| {
+            error["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_string()
+        })
+}
+
+/// Quote a value as a GraphQL string literal
+fn graphql_string(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use crate::http::retry::RetryConfig;
+    use wiremock::matchers::{body_string_contains, method};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    fn test_client() -> APIClient {
+        APIClient::new(HttpClientConfig {
+            rate_limit_per_minute: 6000,
+            retry: RetryConfig {
+                max_retries: 0,
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_respects_size_and_cost() {
+        // Test: Batches close when either the size or the cost limit is reached
+        let batcher = GraphQLBatcher::new("http://localhost/graphql")
+            .with_max_batch_size(3)
+            .with_max_cost(10);
+        let lookups = vec![
+            GraphQLLookup::new("a"),
+            GraphQLLookup::new("b"),
+            GraphQLLookup::new("c"),
+            GraphQLLookup::new("d").with_cost(8),
+            GraphQLLookup::new("e").with_cost(5),
+            GraphQLLookup::new("f").with_cost(20),
+        ];
+        assert_eq!(
+            batcher.plan(&lookups),
+            vec![vec![0, 1, 2], vec![3], vec![4], vec![5]]
+        );
+    }
+
+    #[test]
+    fn test_batch_query_aliases_lookups() {
+        // Test: Each lookup gets a positional alias and arguments are quoted
+        let lookups = [
+            GraphQLLookup::repository("rust-lang", "rust", "stargazerCount"),
+            GraphQLLookup::repository("a\"b", "c", "id"),
+        ];
+        assert_eq!(
+            batch_query(&lookups),
+            "query { l0: repository(owner: \"rust-lang\", name: \"rust\") { stargazerCount } \
+             l1: repository(owner: \"a\\\"b\", name: \"c\") { id } }"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_isolates_alias_and_batch_failures() {
+        // Test: Per-alias errors fail one lookup and rejected batches are re-split
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("broken"))
+            .respond_with(|request: &Request| {
+                let body: Value = serde_json::from_slice(&request.body).unwrap();
+                let query = body["query"].as_str().unwrap();
+                if query.contains("l1:") {
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "errors": [{"message": "Query has complexity over limit"}]
+                    }))
+                } else {
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "data": null,
+                        "errors": [{"message": "broken field"}]
+                    }))
+                }
+            })
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(|request: &Request| {
+                let body: Value = serde_json::from_slice(&request.body).unwrap();
+                let query = body["query"].as_str().unwrap();
+                let mut data = serde_json::Map::new();
+                let mut errors = Vec::new();
+                for (position, field) in query.split(" l").skip(1).enumerate() {
+                    let alias = alias(position);
+                    if field.contains("missing") {
+                        data.insert(alias.clone(), Value::Null);
+                        errors.push(serde_json::json!({
+                            "message": "Could not resolve to a Repository",
+                            "path": [alias]
+                        }));
+                    } else {
+                        data.insert(alias, serde_json::json!({"ok": position}));
+                    }
+                }
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"data": data, "errors": errors}))
+            })
+            .mount(&server)
+            .await;
+
+        let batcher = GraphQLBatcher::new(format!("{}/graphql", server.uri()));
+        let lookups = vec![
+            GraphQLLookup::new("found { id }"),
+            GraphQLLookup::new("missing { id }"),
+            GraphQLLookup::new("broken { id }"),
+            GraphQLLookup::new("other { id }"),
+        ];
+        let results = batcher.execute(&test_client(), &lookups).await;
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].is_ok(), "Healthy lookups should succeed");
+        assert!(
+            results[1].is_err(),
+            "Alias errors should fail only that lookup"
+        );
+        assert!(
+            results[2].is_err(),
+            "The rejected lookup should be isolated"
+        );
+        assert!(
+            results[3].is_ok(),
+            "Lookups batched with a rejected one are retried"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_splits_only_oversized_batches() {
+        // Test: 413 responses split the batch; other failures reach every lookup at once
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("l1:"))
+            .respond_with(ResponseTemplate::new(413))
+            .expect(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": {"l0": {"ok": true}}
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let batcher = GraphQLBatcher::new(format!("{}/graphql", server.uri()));
+        let lookups = vec![
+            GraphQLLookup::new("a { id }"),
+            GraphQLLookup::new("b { id }"),
+        ];
+        let results = batcher.execute(&test_client(), &lookups).await;
+        assert!(results.iter().all(|r| r.is_ok()), "Halves should succeed");
+
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": null,
+                "errors": [{"message": "Bad credentials"}]
+            })))
+            .expect(1)
+            .mount(&failing)
+            .await;
+        let batcher = GraphQLBatcher::new(format!("{}/graphql", failing.uri()));
+        let lookups = vec![
+            GraphQLLookup::new("a { id }"),
+            GraphQLLookup::new("b { id }"),
+            GraphQLLookup::new("c { id }"),
+        ];
+        let results = batcher.execute(&test_client(), &lookups).await;
+        for result in results {
+            let error = result.unwrap_err().to_string();
+            assert!(
+                error.contains("Bad credentials"),
+                "Unexpected error: {}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_exceeds_limits() {
+        // Test: Complexity and node-limit errors are recognized by code or message
+        assert!(exceeds_limits(&serde_json::json!({
+            "type": "MAX_NODE_LIMIT_EXCEEDED",
+            "message": "This query requests up to 1,001,000 possible nodes"
+        })));
+        assert!(exceeds_limits(&serde_json::json!({
+            "message": "Query has complexity of 1500, which exceeds max complexity of 1000"
+        })));
+        assert!(!exceeds_limits(&serde_json::json!({
+            "type": "FORBIDDEN",
+            "message": "Resource not accessible by integration"
+        })));
+    }
+}
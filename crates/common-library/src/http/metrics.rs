@@ -0,0 +1,169 @@
+//! Per-request metrics hooks for the HTTP client
+//!
+//! Every request that reaches the network is reported to an
+//! [`HttpMetricsRecorder`] once it completes, including its retries.
+//! Requests answered by middleware or a replay cassette are not reported.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Metrics for a single completed request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestMetrics {
+    pub method: String,
+    pub url: String,
+    pub host: String,
+    /// Final status code; `None` if the request failed without a response
+    pub status: Option<u16>,
+    /// Time from the first attempt until the body was read
+    pub latency: Duration,
+    /// Retries made before the final attempt
+    pub retries: u32,
+    /// Buffered body size; `None` for streamed or failed responses
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Receives metrics for every request made by an `APIClient`
+pub trait HttpMetricsRecorder: Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+/// Aggregated request metrics for one host
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostMetrics {
+    pub requests: u64,
+    /// Requests that failed without a response or ended with a 4xx/5xx
+    pub failures: u64,
+    pub retries: u64,
+    pub bytes: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+    pub status_counts: BTreeMap<u16, u64>,
+}
+
+impl HostMetrics {
+    /// Mean latency across all requests
+    pub fn average_latency(&self) -> Duration {
+        if self.requests == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / self.requests as u32
+        }
+    }
+}
+
+/// Recorder that aggregates metrics per host in memory
+#[derive(Debug, Default)]
+pub struct InMemoryHttpMetrics {
+    hosts: Mutex<HashMap<String, HostMetrics>>,
+}
+
+impl InMemoryHttpMetrics {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregated metrics for a host
+    pub fn host(&self, host: &str) -> Option<HostMetrics> {
+        self.hosts.lock().unwrap().get(host).cloned()
+    }
+
+    /// Aggregated metrics for every host
+    pub fn snapshot(&self) -> HashMap<String, HostMetrics> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    /// Discard all recorded metrics
+    pub fn reset(&self) {
+        self.hosts.lock().unwrap().clear();
+    }
+}
+
+impl HttpMetricsRecorder for InMemoryHttpMetrics {
+    fn record(&self, metrics: &RequestMetrics) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let host = hosts.entry(metrics.host.clone()).or_default();
+        host.requests += 1;
+        host.retries += u64::from(metrics.retries);
+        host.bytes += metrics.bytes.unwrap_or(0);
+        host.total_latency += metrics.latency;
+        host.max_latency = host.max_latency.max(metrics.latency);
+        match metrics.status {
+            Some(status) => {
+                *host.status_counts.entry(status).or_default() += 1;
+                if status >= 400 {
+                    host.failures += 1;
+                }
+            }
+            None => host.failures += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::{APIClient, HttpClientConfig};
+    use crate::http::retry::RetryConfig;
+    use std::sync::Arc;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_client_reports_request_metrics() {
+        // Test: Status, retries, and bytes are recorded per host
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hello"))
+            .mount(&server)
+            .await;
+
+        let metrics = Arc::new(InMemoryHttpMetrics::new());
+        let client = APIClient::new(HttpClientConfig {
+            retry: RetryConfig {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap()
+        .with_metrics(metrics.clone());
+
+        client.get(&format!("{}/pkg", server.uri())).await.unwrap();
+
+        let host = metrics.host("127.0.0.1").expect("Host should be recorded");
+        assert_eq!(host.requests, 1);
+        assert_eq!(host.retries, 1, "The 503 should count as a retry");
+        assert_eq!(host.bytes, 5);
+        assert_eq!(host.status_counts.get(&200), Some(&1));
+        assert_eq!(host.failures, 0);
+    }
+
+    #[test]
+    fn test_failed_requests_count_as_failures() {
+        // Test: Requests without a response are counted as failures
+        let metrics = InMemoryHttpMetrics::new();
+        metrics.record(&RequestMetrics {
+            method: "GET".to_string(),
+            url: "https://registry.npmjs.org/left-pad".to_string(),
+            host: "registry.npmjs.org".to_string(),
+            status: None,
+            latency: Duration::from_millis(20),
+            retries: 3,
+            bytes: None,
+            error: Some("connection refused".to_string()),
+        });
+        let host = metrics.host("registry.npmjs.org").unwrap();
+        assert_eq!(host.failures, 1);
+        assert_eq!(host.average_latency(), Duration::from_millis(20));
+    }
+}
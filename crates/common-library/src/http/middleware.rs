@@ -0,0 +1,205 @@
+//! Request/response middleware for the HTTP client
+//!
+//! Middleware is registered on an [`APIClient`](super::APIClient) in order.
+//! Request hooks run in registration order before the request is sent;
+//! response hooks run in reverse order, so the first registered middleware
+//! sees the request first and the response last.
+
+use super::client::ApiResponse;
+use crate::error::{Error, Result};
+use reqwest::Method;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use tracing::debug;
+
+/// The mutable view of an outgoing request passed to middleware
+#[derive(Debug, Clone)]
+pub struct MiddlewareRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+/// Interceptor for requests made by the HTTP client
+pub trait HttpMiddleware: Send + Sync {
+    /// Inspect or modify a request before it is sent
+    ///
+    /// Returning a response short-circuits the request: nothing is sent and
+    /// only the response hooks of earlier middleware run.
+    fn on_request(&self, _request: &mut MiddlewareRequest) -> Result<Option<ApiResponse>> {
+        Ok(None)
+    }
+
+    /// Inspect or modify a buffered response
+    ///
+    /// Not called for streamed responses, whose body is never buffered.
+    fn on_response(&self, _request: &MiddlewareRequest, _response: &mut ApiResponse) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Middleware that adds fixed headers to every request
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMiddleware {
+    headers: HeaderMap,
+}
+
+impl HeaderMiddleware {
+    /// Create an empty header middleware
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header, replacing any existing value on the request
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::http(format!("Invalid header name {}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::http(format!("Invalid header value: {}", e)))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+}
+
+impl HttpMiddleware for HeaderMiddleware {
+    fn on_request(&self, request: &mut MiddlewareRequest) -> Result<Option<ApiResponse>> {
+        for (name, value) in &self.headers {
+            request.headers.insert(name.clone(), value.clone());
+        }
+        Ok(None)
+    }
+}
+
+/// Middleware that logs every request and response at debug level
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+impl HttpMiddleware for LoggingMiddleware {
+    fn on_request(&self, request: &mut MiddlewareRequest) -> Result<Option<ApiResponse>> {
+        debug!("HTTP {} {}", request.method, request.url);
+        Ok(None)
+    }
+
+    fn on_response(&self, request: &MiddlewareRequest, response: &mut ApiResponse) -> Result<()> {
+        debug!(
+            "HTTP {} {} -> {} ({} bytes)",
+            request.method,
+            request.url,
+            response.status,
+            response.body.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::{APIClient, HttpClientConfig};
+    use std::sync::{Arc, Mutex};
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl HttpMiddleware for Recorder {
+        fn on_request(&self, _request: &mut MiddlewareRequest) -> Result<Option<ApiResponse>> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("request:{}", self.name));
+            Ok(None)
+        }
+
+        fn on_response(
+            &self,
+            _request: &MiddlewareRequest,
+            _response: &mut ApiResponse,
+        ) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("response:{}", self.name));
+            Ok(())
+        }
+    }
+
+    struct ShortCircuit;
+
+    impl HttpMiddleware for ShortCircuit {
+        fn on_request(&self, _request: &mut MiddlewareRequest) -> Result<Option<ApiResponse>> {
+            Ok(Some(ApiResponse {
+                status: 200,
+                headers: HeaderMap::new(),
+                body: b"stubbed".to_vec(),
+                from_cache: false,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order_and_header_injection() {
+        // Test: Request hooks run in order, response hooks in reverse, headers are injected
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("X-Collector", "npm"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let client = APIClient::new(HttpClientConfig::default())
+            .unwrap()
+            .with_middleware(Arc::new(Recorder {
+                name: "outer",
+                log: log.clone(),
+            }))
+            .with_middleware(Arc::new(
+                HeaderMiddleware::new()
+                    .header("X-Collector", "npm")
+                    .unwrap(),
+            ))
+            .with_middleware(Arc::new(Recorder {
+                name: "inner",
+                log: log.clone(),
+            }));
+
+        client.get(&format!("{}/pkg", server.uri())).await.unwrap();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "request:outer",
+                "request:inner",
+                "response:inner",
+                "response:outer"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_middleware_short_circuit() {
+        // Test: A middleware response skips the network and later middleware
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let client = APIClient::new(HttpClientConfig::default())
+            .unwrap()
+            .with_middleware(Arc::new(Recorder {
+                name: "outer",
+                log: log.clone(),
+            }))
+            .with_middleware(Arc::new(ShortCircuit))
+            .with_middleware(Arc::new(Recorder {
+                name: "inner",
+                log: log.clone(),
+            }));
+
+        let response = client.get("http://127.0.0.1:9/unreachable").await.unwrap();
+        assert_eq!(response.body, b"stubbed");
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["request:outer", "response:outer"]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! HTTP client functionality for the common library
+//!
+//! Provides an [`APIClient`] with rate limiting, retry logic, authentication,
+//! conditional request caching, and automatic pagination shared by all
+//! collectors.
+
+pub mod auth;
+pub mod batch;
+pub mod cache;
+pub mod cassette;
+pub mod client;
+pub mod compression;
+mod dedupe;
+pub mod download;
+pub mod endpoint;
+pub mod graphql;
+pub mod metrics;
+pub mod middleware;
+pub mod pagination;
+pub mod rate_limiter;
+#[cfg(feature = "redis-rate-limit")]
+pub mod redis_rate_limiter;
+pub mod retry;
+pub mod robots;
+pub mod scheduler;
+pub mod signing;
+pub mod sse;
+pub mod upload;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+pub use auth::{AuthConfig, AuthManager, OAuth2Config, OAuth2Token, TokenSource};
+pub use cache::{CacheEntry, MemoryCache, ResponseCache, StoreCache};
+pub use cassette::{Cassette, CassetteConfig, CassetteMode};
+pub use client::{APIClient, ApiResponse, HttpClientConfig};
+pub use compression::{ContentEncoding, RequestCompression};
+pub use download::{DownloadOptions, DownloadProgress, DownloadSummary, ResponseStream};
+pub use endpoint::{Endpoint, EndpointRequest};
+pub use graphql::{GraphQLBatcher, GraphQLLookup};
+pub use metrics::{HostMetrics, HttpMetricsRecorder, InMemoryHttpMetrics, RequestMetrics};
+pub use middleware::{HeaderMiddleware, HttpMiddleware, LoggingMiddleware, MiddlewareRequest};
+pub use pagination::{Link, PageInfo, PaginationConfig, PaginationStrategy, parse_link_header};
+pub use rate_limiter::{
+    BucketSnapshot, HostRateLimiter, RateLimitBackend, RateLimitBudget, RateLimitStatus,
+    RateLimiter,
+};
+#[cfg(feature = "redis-rate-limit")]
+pub use redis_rate_limiter::RedisRateLimiter;
+pub use retry::{
+    BackoffStrategy, CircuitBreaker, CircuitBreakerConfig, CircuitPermit, CircuitState,
+    ConstantBackoff, EqualJitter, ErrorClass, ExponentialBackoff, FullJitter, Jitter, RetryBudget,
+    RetryConfig, RetryPolicy,
+};
+pub use robots::RobotsTxt;
+pub use scheduler::{RequestPriority, RequestScheduler, SchedulerPermit};
+pub use signing::{HmacSigningConfig, SigV4Config};
+pub use sse::{SseEvent, SseParser};
+pub use upload::{MultipartForm, MultipartPart, ResumableUploadConfig};
+#[cfg(feature = "webhooks")]
+pub use webhook::{SignatureScheme, WebhookConfig, WebhookEvent, WebhookServer, WebhookSource};
+#[cfg(feature = "websocket")]
+pub use websocket::{MessageHandler, WebSocketClient, WebSocketConfig, WsMessage};
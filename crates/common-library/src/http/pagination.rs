@@ -0,0 +1,635 @@
+//! Automatic pagination over paginated API endpoints
+//!
+//! [`PageInfo`] extracts pagination metadata from RFC 8288 `Link` headers
+//! and common JSON envelopes, for the pagination stream as well as callers
+//! that page through results themselves.
+
+use super::client::{APIClient, ApiResponse};
+use crate::error::{Error, Result};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// How the next page of results is located
+#[derive(Debug, Clone)]
+pub enum PaginationStrategy {
+    /// Follow `Link: <...>; rel="next"` headers (GitHub style)
+    LinkHeader,
+    /// Follow next links in the response body envelope (see [`PageInfo::from_envelope`])
+    Envelope,
+    /// Increment a page number query parameter until a short or empty page
+    PageNumber {
+        page_param: String,
+        per_page_param: String,
+        per_page: u32,
+        start_page: u32,
+    },
+    /// Pass an opaque cursor taken from the response body
+    Cursor {
+        cursor_param: String,
+        /// JSON pointer to the next cursor in the response body
+        cursor_pointer: String,
+    },
+}
+
+impl PaginationStrategy {
+    /// Page number pagination using the common `page` / `per_page` parameters
+    pub fn page_number(per_page: u32) -> Self {
+        Self::PageNumber {
+            page_param: "page".to_string(),
+            per_page_param: "per_page".to_string(),
+            per_page,
+            start_page: 1,
+        }
+    }
+
+    /// Cursor pagination with the given query parameter and body pointer
+    pub fn cursor(cursor_param: impl Into<String>, cursor_pointer: impl Into<String>) -> Self {
+        Self::Cursor {
+            cursor_param: cursor_param.into(),
+            cursor_pointer: cursor_pointer.into(),
+        }
+    }
+}
+
+/// Pagination configuration
+#[derive(Debug, Clone)]
+pub struct PaginationConfig {
+    pub strategy: PaginationStrategy,
+    /// JSON pointer to the item array; `None` when the body itself is the array
+    pub items_pointer: Option<String>,
+    /// Stop after this many pages
+    pub max_pages: Option<u32>,
+}
+
+impl Default for PaginationConfig {
+    fn default() -> Self {
+        Self {
+            strategy: PaginationStrategy::LinkHeader,
+            items_pointer: None,
+            max_pages: None,
+        }
+    }
+}
+
+impl PaginationConfig {
+    /// Create a configuration for the given strategy
+    pub fn new(strategy: PaginationStrategy) -> Self {
+        Self {
+            strategy,
+            ..Self::default()
+        }
+    }
+
+    /// Read items from a nested array in the response body
+    pub fn with_items_pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.items_pointer = Some(pointer.into());
+        self
+    }
+
+    /// Limit the number of pages fetched
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = Some(max_pages);
+        self
+    }
+}
+
+struct PageState {
+    next_url: Option<String>,
+    page: u32,
+    pages_fetched: u32,
+}
+
+impl APIClient {
+    /// Stream all items from a paginated endpoint
+    ///
+    /// Pages are fetched lazily through [`APIClient::get`], so every page
+    /// request waits on the client's rate limiter.
+    pub fn get_paginated<'a, T>(
+        &'a self,
+        url: &str,
+        config: PaginationConfig,
+    ) -> impl Stream<Item = Result<T>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        let (first_url, start_page) = match &config.strategy {
+            PaginationStrategy::PageNumber {
+                page_param,
+                per_page_param,
+                per_page,
+                start_page,
+            } => (
+                set_query_params(
+                    url,
+                    &[
+                        (page_param, &start_page.to_string()),
+                        (per_page_param, &per_page.to_string()),
+                    ],
+                ),
+                *start_page,
+            ),
+            _ => (Ok(url.to_string()), 0),
+        };
+
+        let state = first_url.map(|first_url| PageState {
+            next_url: Some(first_url),
+            page: start_page,
+            pages_fetched: 0,
+        });
+
+        stream::once(async move { state })
+            .map_ok(move |state| {
+                let config = config.clone();
+                stream::try_unfold(state, move |state| {
+                    let config = config.clone();
+                    async move { self.fetch_page::<T>(state, &config).await }
+                })
+            })
+            .try_flatten()
+            .map_ok(|items: Vec<T>| stream::iter(items.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    async fn fetch_page<T: DeserializeOwned>(
+        &self,
+        mut state: PageState,
+        config: &PaginationConfig,
+    ) -> Result<Option<(Vec<T>, PageState)>> {
+        let Some(url) = state.next_url.take() else {
+            return Ok(None);
+        };
+        if config
+            .max_pages
+            .is_some_and(|max| state.pages_fetched >= max)
+        {
+            return Ok(None);
+        }
+
+        let response = self.get(&url).await?;
+        let body: Value = response.json()?;
+        let items_value = match &config.items_pointer {
+            Some(pointer) => body.pointer(pointer).cloned().ok_or_else(|| {
+                Error::http(format!("Paginated response has no items at {}", pointer))
+            })?,
+            None => body.clone(),
+        };
+        let items: Vec<T> = serde_json::from_value(items_value)?;
+        state.pages_fetched += 1;
+
+        state.next_url = match &config.strategy {
+            PaginationStrategy::LinkHeader => {
+                PageInfo::from_headers(&response.headers).resolve(&url).next
+            }
+            PaginationStrategy::Envelope => PageInfo::from_envelope(&body).resolve(&url).next,
+            PaginationStrategy::PageNumber {
+                page_param,
+                per_page,
+                ..
+            } => {
+                if items.len() < *per_page as usize {
+                    None
+                } else {
+                    state.page += 1;
+                    Some(set_query_params(
+                        &url,
+                        &[(page_param, &state.page.to_string())],
+                    )?)
+                }
+            }
+            PaginationStrategy::Cursor {
+                cursor_param,
+                cursor_pointer,
+            } => match body.pointer(cursor_pointer) {
+                Some(Value::String(cursor)) if !cursor.is_empty() && !items.is_empty() => {
+                    Some(set_query_params(&url, &[(cursor_param, cursor)])?)
+                }
+                _ => None,
+            },
+        };
+
+        if items.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some((items, state)))
+    }
+}
+
+/// A single link from an RFC 8288 `Link` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub target: String,
+    /// Link relation types, lowercased (`rel="next last"` yields two)
+    pub rels: Vec<String>,
+    /// Target attributes other than `rel`, with lowercased names
+    pub params: Vec<(String, String)>,
+}
+
+impl Link {
+    /// Check whether the link has a relation type
+    pub fn has_rel(&self, rel: &str) -> bool {
+        self.rels.iter().any(|r| r.eq_ignore_ascii_case(rel))
+    }
+
+    /// Get a target attribute by name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse an RFC 8288 `Link` header value
+///
+/// Commas inside targets and quoted parameters are handled; malformed
+/// entries are skipped.
+pub fn parse_link_header(value: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches([',', ' ', '\t']);
+        let Some(target_start) = rest.strip_prefix('<') else {
+            break;
+        };
+        let Some(target_end) = target_start.find('>') else {
+            break;
+        };
+        let target = target_start[..target_end].trim().to_string();
+        rest = &target_start[target_end + 1..];
+
+        let mut rels = Vec::new();
+        let mut params = Vec::new();
+        while let Some(param) = rest.trim_start().strip_prefix(';') {
+            let (name, value, remaining) = parse_link_param(param);
+            rest = remaining;
+            if name == "rel" {
+                rels.extend(value.split_whitespace().map(str::to_ascii_lowercase));
+            } else if !name.is_empty() {
+                params.push((name, value));
+            }
+        }
+        links.push(Link {
+            target,
+            rels,
+            params,
+        });
+
+        match rest.find(',') {
+            Some(comma) => rest = &rest[comma..],
+            None => break,
+        }
+    }
+    links
+}
+
+/// Parse one `name[=value]` link parameter, returning the unparsed rest
+fn parse_link_param(input: &str) -> (String, String, &str) {
+    let input = input.trim_start();
+    let name_end = input.find(['=', ';', ',']).unwrap_or(input.len());
+    let name = input[..name_end].trim().to_ascii_lowercase();
+    let Some(value) = input[name_end..].strip_prefix('=') else {
+        return (name, String::new(), &input[name_end..]);
+    };
+    let value = value.trim_start();
+
+    let Some(quoted) = value.strip_prefix('"') else {
+        let end = value.find([';', ',']).unwrap_or(value.len());
+        return (name, value[..end].trim().to_string(), &value[end..]);
+    };
+    let mut unescaped = String::new();
+    let mut escaped = false;
+    for (i, c) in quoted.char_indices() {
+        match c {
+            _ if escaped => {
+                unescaped.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            '"' => return (name, unescaped, &quoted[i + 1..]),
+            _ => unescaped.push(c),
+        }
+    }
+    (name, unescaped, "")
+}
+
+/// Pagination metadata for one page of results
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageInfo {
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub last: Option<String>,
+    /// Total number of items across all pages, when reported
+    pub total: Option<u64>,
+}
+
+impl PageInfo {
+    /// Read `Link` relations and the `X-Total-Count` header
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let links: Vec<Link> = headers
+            .get_all("link")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(parse_link_header)
+            .collect();
+        let target = |rel: &str| {
+            links
+                .iter()
+                .find(|link| link.has_rel(rel))
+                .map(|link| link.target.clone())
+        };
+        Self {
+            next: target("next"),
+            prev: target("prev").or_else(|| target("previous")),
+            last: target("last"),
+            total: headers
+                .get("x-total-count")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok()),
+        }
+    }
+
+    /// Read common JSON pagination envelopes
+    ///
+    /// Recognizes top-level `next`/`previous`/`last`, `links.*`,
+    /// `_links.*.href` (HAL), and `pagination.*` URLs, and totals in
+    /// `total`, `total_count`, `count`, `meta.total`, or `pagination.total`.
+    pub fn from_envelope(body: &Value) -> Self {
+        let url = |rel: &str| {
+            [
+                format!("/{}", rel),
+                format!("/links/{}", rel),
+                format!("/_links/{}/href", rel),
+                format!("/pagination/{}", rel),
+            ]
+            .iter()
+            .find_map(|pointer| body.pointer(pointer)?.as_str())
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+        };
+        Self {
+            next: url("next"),
+            prev: url("prev").or_else(|| url("previous")),
+            last: url("last"),
+            total: [
+                "/total",
+                "/total_count",
+                "/count",
+                "/meta/total",
+                "/pagination/total",
+            ]
+            .iter()
+            .find_map(|pointer| body.pointer(pointer)?.as_u64()),
+        }
+    }
+
+    /// Combine headers and body, preferring values from headers
+    pub fn from_response(response: &ApiResponse) -> Self {
+        let headers = Self::from_headers(&response.headers);
+        let envelope = response
+            .json::<Value>()
+            .map(|body| Self::from_envelope(&body))
+            .unwrap_or_default();
+        Self {
+            next: headers.next.or(envelope.next),
+            prev: headers.prev.or(envelope.prev),
+            last: headers.last.or(envelope.last),
+            total: headers.total.or(envelope.total),
+        }
+    }
+
+    /// Resolve relative link targets against the URL of the page
+    pub fn resolve(self, base_url: &str) -> Self {
+        let Ok(base) = Url::parse(base_url) else {
+            return self;
+        };
+        let resolve = |target: Option<String>| {
+            target.map(|target| base.join(&target).map(String::from).unwrap_or(target))
+        };
+        Self {
+            next: resolve(self.next),
+            prev: resolve(self.prev),
+            last: resolve(self.last),
+            total: self.total,
+        }
+    }
+
+    /// Check whether another page follows
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+/// Set (or replace) query parameters on a URL
+fn set_query_params(url: &str, params: &[(&str, &str)]) -> Result<String> {
+    let mut parsed =
+        Url::parse(url).map_err(|e| Error::http(format!("Invalid URL {}: {}", url, e)))?;
+    let existing: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !params.iter().any(|(name, _)| name == key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    {
+        let mut query = parsed.query_pairs_mut();
+        query.clear();
+        for (key, value) in &existing {
+            query.append_pair(key, value);
+        }
+        for (key, value) in params {
+            query.append_pair(key, value);
+        }
+    }
+    Ok(parsed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_set_query_params_replaces_existing() {
+        // Test: Existing parameters are replaced and unrelated ones preserved
+        let url =
+            set_query_params("https://example.com/x?page=1&q=rust", &[("page", "2")]).unwrap();
+        assert_eq!(url, "https://example.com/x?q=rust&page=2");
+    }
+
+    #[test]
+    fn test_parse_link_header() {
+        // Test: Commas in targets, quoted params, and multiple rels are handled
+        let links = parse_link_header(
+            "<https://api.example.com/x?ids=1,2&page=2>; rel=\"next\"; title=\"a; \\\"b\\\"\", \
+             <https://api.example.com/x?page=9>; rel=\"last end\", <broken",
+        );
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].target, "https://api.example.com/x?ids=1,2&page=2");
+        assert!(links[0].has_rel("next"));
+        assert_eq!(links[0].param("title"), Some("a; \"b\""));
+        assert!(links[1].has_rel("last") && links[1].has_rel("end"));
+    }
+
+    #[test]
+    fn test_page_info_from_headers_and_envelope() {
+        // Test: Headers and body envelopes produce the same typed metadata
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "link",
+            "</items?page=3>; rel=next, </items?page=1>; rel=prev, </items?page=5>; rel=last"
+                .parse()
+                .unwrap(),
+        );
+        headers.insert("x-total-count", "42".parse().unwrap());
+        let info = PageInfo::from_headers(&headers).resolve("https://api.example.com/items?page=2");
+        assert_eq!(
+            info,
+            PageInfo {
+                next: Some("https://api.example.com/items?page=3".to_string()),
+                prev: Some("https://api.example.com/items?page=1".to_string()),
+                last: Some("https://api.example.com/items?page=5".to_string()),
+                total: Some(42),
+            }
+        );
+
+        let envelope = PageInfo::from_envelope(&serde_json::json!({
+            "count": 7,
+            "next": "https://pypi.example.com/list?page=2",
+            "previous": null,
+            "results": []
+        }));
+        assert_eq!(envelope.total, Some(7));
+        assert!(envelope.has_next());
+        assert_eq!(envelope.prev, None);
+
+        let hal = PageInfo::from_envelope(&serde_json::json!({
+            "_links": {"next": {"href": "/page/2"}}
+        }));
+        assert_eq!(hal.next.as_deref(), Some("/page/2"));
+    }
+
+    #[tokio::test]
+    async fn test_envelope_pagination() {
+        // Test: Relative next links in the body are resolved and followed
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [3],
+                "next": null
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [1, 2],
+                "next": "/packages?page=2"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let config =
+            PaginationConfig::new(PaginationStrategy::Envelope).with_items_pointer("/results");
+        let items: Vec<u32> = client
+            .get_paginated::<u32>(&format!("{}/packages", server.uri()), config)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_link_header_pagination() {
+        // Test: Link headers are followed until there is no next page
+        let server = MockServer::start().await;
+        let next = format!("<{}/items?page=2>; rel=\"next\"", server.uri());
+        Mock::given(method("GET"))
+            .and(path("/items"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![3, 4]))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/items"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", next.as_str())
+                    .set_body_json(vec![1, 2]),
+            )
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let url = format!("{}/items", server.uri());
+        let items: Vec<u32> = client
+            .get_paginated::<u32>(&url, PaginationConfig::default())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_page_number_pagination() {
+        // Test: Page numbers advance until a short page is returned
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec!["a", "b"]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec!["c"]))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let config = PaginationConfig::new(PaginationStrategy::page_number(2));
+        let items: Vec<String> = client
+            .get_paginated::<String>(&format!("{}/list", server.uri()), config)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination_with_items_pointer() {
+        // Test: Cursors from the body are passed to the next request
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("after", "abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [3],
+                "next": null
+            })))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [1, 2],
+                "next": "abc"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let config = PaginationConfig::new(PaginationStrategy::cursor("after", "/next"))
+            .with_items_pointer("/data");
+        let items: Vec<u32> = client
+            .get_paginated::<u32>(&format!("{}/feed", server.uri()), config)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,798 @@
+//! Rate limiting for outgoing HTTP requests
+
+use crate::config::RateLimitConfig;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Token bucket rate limiter
+///
+/// The bucket holds up to `requests_per_minute` tokens and refills
+/// continuously. Each request consumes one token.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    capacity: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+    /// Set when the server reported an exhausted quota or asked us to back off
+    blocked_until: Option<Instant>,
+}
+
+/// Snapshot of a bucket's budget, for planning work within the limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitBudget {
+    /// Whole tokens available now
+    pub remaining: u32,
+    /// Maximum tokens the bucket holds
+    pub capacity: u32,
+    pub refill_per_minute: f64,
+    /// Time left on a server-imposed pause
+    pub paused_for: Option<Duration>,
+}
+
+/// A bucket's state saved between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BucketSnapshot {
+    pub tokens: f64,
+    pub saved_at: DateTime<Utc>,
+    /// End of a server-imposed pause
+    pub blocked_until: Option<DateTime<Utc>>,
+}
+
+/// Rate limit information reported by a server in response headers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitStatus {
+    /// `x-ratelimit-remaining`
+    pub remaining: Option<u32>,
+    /// `x-ratelimit-reset` (Unix epoch seconds)
+    pub reset_at: Option<DateTime<Utc>>,
+    /// `Retry-After`, as seconds or an HTTP date
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// Parse rate limit headers from a response
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+        let remaining = header("x-ratelimit-remaining").and_then(|v| v.trim().parse().ok());
+        let reset_at = header("x-ratelimit-reset")
+            .and_then(|v| v.trim().parse::<i64>().ok())
+            .and_then(|secs| DateTime::from_timestamp(secs, 0));
+        let retry_after = header(RETRY_AFTER.as_str()).and_then(|v| {
+            let v = v.trim();
+            match v.parse::<u64>() {
+                Ok(secs) => Some(Duration::from_secs(secs)),
+                Err(_) => DateTime::parse_from_rfc2822(v).ok().map(|date| {
+                    (date.with_timezone(&Utc) - Utc::now())
+                        .to_std()
+                        .unwrap_or_default()
+                }),
+            }
+        });
+
+        Self {
+            remaining,
+            reset_at,
+            retry_after,
+        }
+    }
+
+    /// How long to wait before the next request, if the server asked for a pause
+    ///
+    /// `Retry-After` wins; otherwise an exhausted quota waits until the reset time.
+    pub fn wait_time(&self) -> Option<Duration> {
+        if let Some(retry_after) = self.retry_after {
+            return Some(retry_after);
+        }
+        match (self.remaining, self.reset_at) {
+            (Some(0), Some(reset_at)) => Some((reset_at - Utc::now()).to_std().unwrap_or_default()),
+            _ => None,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter allowing `requests_per_minute` requests
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self::with_burst(requests_per_minute, requests_per_minute)
+    }
+
+    /// Create a rate limiter refilling at `requests_per_minute` with a bucket of `burst` tokens
+    pub fn with_burst(requests_per_minute: u32, burst: u32) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            state: Mutex::new(BucketState {
+                capacity,
+                refill_per_second: requests_per_minute.max(1) as f64 / 60.0,
+                tokens: capacity,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    /// Create a rate limiter spacing requests at least `interval` apart
+    pub fn with_min_interval(interval: Duration) -> Self {
+        Self {
+            state: Mutex::new(BucketState {
+                capacity: 1.0,
+                refill_per_second: Self::interval_rate(interval),
+                tokens: 1.0,
+                last_refill: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    /// Create a rate limiter from configuration
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::with_burst(
+            config.requests_per_minute,
+            config.burst.unwrap_or(config.requests_per_minute),
+        )
+    }
+
+    /// Wait until `cost` tokens are available and consume them
+    ///
+    /// A cost larger than the bucket waits for a full bucket and leaves it in
+    /// debt, delaying later requests until the excess has refilled.
+    pub async fn acquire(&self, cost: u32) {
+        loop {
+            let wait = {
+                let mut state = self.lock_state();
+                match self.wait_for(&mut state, cost) {
+                    Some(wait) => wait,
+                    None => {
+                        state.tokens -= cost as f64;
+                        return;
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// How long until `cost` tokens could be acquired, or zero if they are available now
+    pub fn time_until_available(&self, cost: u32) -> Duration {
+        self.wait_for(&mut self.lock_state(), cost)
+            .unwrap_or_default()
+    }
+
+    /// Current budget of the bucket
+    pub fn budget(&self) -> RateLimitBudget {
+        let mut state = self.lock_state();
+        let paused_for = Self::blocked_for(&mut state);
+        self.refill(&mut state);
+        RateLimitBudget {
+            remaining: if paused_for.is_some() {
+                0
+            } else {
+                state.tokens.max(0.0).floor() as u32
+            },
+            capacity: state.capacity as u32,
+            refill_per_minute: state.refill_per_second * 60.0,
+            paused_for,
+        }
+    }
+
+    /// Time to wait before `cost` tokens are available, if any
+    fn wait_for(&self, state: &mut BucketState, cost: u32) -> Option<Duration> {
+        if let Some(wait) = Self::blocked_for(state) {
+            return Some(wait);
+        }
+        self.refill(state);
+        let needed = (cost as f64).min(state.capacity);
+        if state.tokens >= needed {
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (needed - state.tokens) / state.refill_per_second,
+            ))
+        }
+    }
+
+    /// Wait until any server-imposed pause has elapsed
+    pub async fn wait_for_reset(&self) {
+        loop {
+            let wait = Self::blocked_for(&mut self.lock_state());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Stop handing out tokens for `duration` (extends an existing pause)
+    pub fn pause_for(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.lock_state();
+        state.blocked_until = Some(state.blocked_until.map_or(until, |b| b.max(until)));
+    }
+
+    /// Update the bucket from rate limit information reported by the server
+    ///
+    /// The local bucket never holds more tokens than the server says remain,
+    /// and an exhausted quota or `Retry-After` pauses the limiter.
+    pub fn update_limits(&self, status: &RateLimitStatus) {
+        if let Some(remaining) = status.remaining {
+            let mut state = self.lock_state();
+            self.refill(&mut state);
+            state.tokens = state.tokens.min(remaining as f64);
+        }
+        if let Some(wait) = status.wait_time() {
+            self.pause_for(wait);
+        }
+    }
+
+    /// Check whether the limiter is paused by the server
+    pub fn is_paused(&self) -> bool {
+        self.paused_for().is_some()
+    }
+
+    /// Time left on a server-imposed pause
+    pub fn paused_for(&self) -> Option<Duration> {
+        Self::blocked_for(&mut self.lock_state())
+    }
+
+    fn blocked_for(state: &mut BucketState) -> Option<Duration> {
+        let until = state.blocked_until?;
+        let now = Instant::now();
+        if until > now {
+            Some(until - now)
+        } else {
+            state.blocked_until = None;
+            None
+        }
+    }
+
+    /// Consume a token if one is available without waiting
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.lock_state();
+        if Self::blocked_for(&mut state).is_some() {
+            return false;
+        }
+        self.refill(&mut state);
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return a previously acquired token to the bucket
+    ///
+    /// Used for requests that did not count against the upstream quota,
+    /// such as conditional requests answered with `304 Not Modified`.
+    pub fn refund(&self) {
+        let mut state = self.lock_state();
+        state.tokens = (state.tokens + 1.0).min(state.capacity);
+    }
+
+    /// Number of whole tokens currently available
+    pub fn remaining(&self) -> u32 {
+        let mut state = self.lock_state();
+        if Self::blocked_for(&mut state).is_some() {
+            return 0;
+        }
+        self.refill(&mut state);
+        state.tokens.floor() as u32
+    }
+
+    /// Capture the bucket so it can be restored by a later run
+    pub fn snapshot(&self) -> BucketSnapshot {
+        let mut state = self.lock_state();
+        let blocked_for = Self::blocked_for(&mut state);
+        self.refill(&mut state);
+        let now = Utc::now();
+        BucketSnapshot {
+            tokens: state.tokens,
+            saved_at: now,
+            blocked_until: blocked_for
+                .and_then(|wait| chrono::Duration::from_std(wait).ok())
+                .map(|wait| now + wait),
+        }
+    }
+
+    /// Restore a saved bucket, crediting tokens refilled since it was saved
+    ///
+    /// Never grants more tokens than the bucket currently holds, and a pause
+    /// still in effect is carried over.
+    pub fn restore(&self, snapshot: &BucketSnapshot) {
+        let now = Utc::now();
+        let elapsed = (now - snapshot.saved_at).to_std().unwrap_or_default();
+        {
+            let mut state = self.lock_state();
+            self.refill(&mut state);
+            let tokens = snapshot.tokens + elapsed.as_secs_f64() * state.refill_per_second;
+            state.tokens = state.tokens.min(tokens.max(0.0));
+        }
+        if let Some(wait) = snapshot
+            .blocked_until
+            .and_then(|until| (until - now).to_std().ok())
+        {
+            self.pause_for(wait);
+        }
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * state.refill_per_second).min(state.capacity);
+        state.last_refill = now;
+    }
+
+    /// Space requests at least `interval` apart from now on
+    ///
+    /// Refilled tokens, debt and any pause are kept. Has no effect if the
+    /// bucket already refills more slowly.
+    pub fn limit_interval(&self, interval: Duration) {
+        let refill_per_second = Self::interval_rate(interval);
+        let mut state = self.lock_state();
+        if refill_per_second >= state.refill_per_second {
+            return;
+        }
+        self.refill(&mut state);
+        state.capacity = 1.0;
+        state.refill_per_second = refill_per_second;
+        state.tokens = state.tokens.min(1.0);
+    }
+
+    fn interval_rate(interval: Duration) -> f64 {
+        1.0 / interval.as_secs_f64().max(0.001)
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Independent token buckets keyed by host
+///
+/// Hosts with an explicit [`RateLimitConfig`] get their own limit; all other
+/// hosts get a separate bucket using the default limit, so a slow registry
+/// never throttles requests to another one.
+pub struct HostRateLimiter {
+    default_limit: RateLimitConfig,
+    overrides: HashMap<String, RateLimitConfig>,
+    limiters: RwLock<HashMap<String, Arc<RateLimiter>>>,
+    backend: Option<Arc<dyn RateLimitBackend>>,
+    /// Serializes state file writes so an older snapshot never replaces a newer one
+    save_lock: Mutex<()>,
+    /// Set while a background save is queued, so bursts write the file once
+    save_pending: AtomicBool,
+}
+
+/// Token store shared by several processes
+///
+/// Local token buckets only limit a single process; a backend lets every
+/// collector instance draw from one budget per host.
+pub trait RateLimitBackend: Send + Sync {
+    /// Try to take one token for `host`
+    ///
+    /// Returns `None` when a token was granted, or how long to wait before
+    /// one becomes available.
+    fn try_acquire<'a>(
+        &'a self,
+        host: &'a str,
+        limit: &'a RateLimitConfig,
+    ) -> BoxFuture<'a, Result<Option<Duration>>>;
+}
+
+impl std::fmt::Debug for HostRateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostRateLimiter")
+            .field("default_limit", &self.default_limit)
+            .field("overrides", &self.overrides)
+            .field("distributed", &self.backend.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl HostRateLimiter {
+    /// Create a per-host limiter with the given default limit
+    pub fn new(default_limit: RateLimitConfig) -> Self {
+        Self {
+            default_limit,
+            overrides: HashMap::new(),
+            limiters: RwLock::new(HashMap::new()),
+            backend: None,
+            save_lock: Mutex::new(()),
+            save_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Share host budgets with other processes through a backend
+    ///
+    /// Local buckets still apply, so server-reported pauses are honored. If
+    /// the backend is unreachable, requests fall back to local limits only.
+    pub fn with_backend(mut self, backend: Arc<dyn RateLimitBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// The distributed backend, if one is configured
+    pub fn backend(&self) -> Option<&Arc<dyn RateLimitBackend>> {
+        self.backend.as_ref()
+    }
+
+    /// The limit applied to a host
+    pub fn limit_for(&self, host: &str) -> &RateLimitConfig {
+        self.overrides.get(host).unwrap_or(&self.default_limit)
+    }
+
+    /// Create a per-host limiter with explicit limits for some hosts
+    pub fn with_overrides(
+        default_limit: RateLimitConfig,
+        overrides: HashMap<String, RateLimitConfig>,
+    ) -> Self {
+        Self {
+            overrides,
+            ..Self::new(default_limit)
+        }
+    }
+
+    /// Get (or create) the limiter for a host
+    pub fn limiter_for(&self, host: &str) -> Arc<RateLimiter> {
+        if let Some(limiter) = self
+            .limiters
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(host)
+        {
+            return limiter.clone();
+        }
+
+        let mut limiters = self.limiters.write().unwrap_or_else(|e| e.into_inner());
+        limiters
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(RateLimiter::from_config(self.limit_for(host))))
+            .clone()
+    }
+
+    /// Space requests to a host at least `interval` apart, e.g. for a crawl-delay
+    ///
+    /// Has no effect if the host's limit is already stricter.
+    pub fn set_min_interval(&self, host: &str, interval: Duration) {
+        self.limiter_for(host).limit_interval(interval);
+    }
+
+    /// Wait for a token from the host's bucket (and the shared backend)
+    pub async fn acquire(&self, host: &str) {
+        self.acquire_cost(host, 1).await;
+    }
+
+    /// Wait for `cost` tokens from the host's bucket
+    ///
+    /// The shared backend counts requests, so it is charged once regardless of cost.
+    pub async fn acquire_cost(&self, host: &str, cost: u32) {
+        self.limiter_for(host).acquire(cost).await;
+
+        let Some(backend) = &self.backend else {
+            return;
+        };
+        loop {
+            match backend.try_acquire(host, self.limit_for(host)).await {
+                Ok(None) => return,
+                Ok(Some(wait)) => tokio::time::sleep(wait).await,
+                Err(e) => {
+                    warn!("Shared rate limiter unavailable for {}: {}", host, e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Snapshot every host bucket created so far
+    pub fn snapshot(&self) -> HashMap<String, BucketSnapshot> {
+        self.limiters
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(host, limiter)| (host.clone(), limiter.snapshot()))
+            .collect()
+    }
+
+    /// Restore host buckets from a snapshot
+    pub fn restore(&self, snapshot: &HashMap<String, BucketSnapshot>) {
+        for (host, bucket) in snapshot {
+            self.limiter_for(host).restore(bucket);
+        }
+    }
+
+    /// Write the state of every host bucket to a JSON file
+    ///
+    /// The file is replaced atomically, so a crash mid-write leaves the
+    /// previous state intact.
+    pub fn save_state(&self, path: &Path) -> Result<()> {
+        let _guard = self.save_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = path.parent() {
+            crate::utils::fs::ensure_dir(parent)?;
+        }
+        crate::utils::fs::write_file_atomic(path, &serde_json::to_vec_pretty(&self.snapshot())?)
+    }
+
+    /// Save state on a blocking thread without delaying the caller
+    ///
+    /// Calls made while a save is still queued are folded into it; the
+    /// snapshot is taken when the write starts, so it includes them.
+    pub fn save_state_in_background(self: &Arc<Self>, path: PathBuf) {
+        if self.save_pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let limiter = self.clone();
+        tokio::task::spawn_blocking(move || {
+            limiter.save_pending.store(false, Ordering::Release);
+            if let Err(e) = limiter.save_state(&path) {
+                warn!("Failed to save rate limiter state: {}", e);
+            }
+        });
+    }
+
+    /// Restore host buckets from a file written by [`save_state`](Self::save_state)
+    ///
+    /// A missing file is not an error.
+    pub fn load_state(&self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let data = std::fs::read(path)?;
+        self.restore(&serde_json::from_slice(&data)?);
+        Ok(())
+    }
+
+    /// Remaining tokens for a host
+    pub fn remaining(&self, host: &str) -> u32 {
+        self.limiter_for(host).remaining()
+    }
+
+    /// Current budget for a host
+    pub fn budget(&self, host: &str) -> RateLimitBudget {
+        self.limiter_for(host).budget()
+    }
+
+    /// Default limit applied to hosts without an override
+    pub fn default_limit(&self) -> &RateLimitConfig {
+        &self.default_limit
+    }
+
+    /// Explicit per-host limits
+    pub fn overrides(&self) -> &HashMap<String, RateLimitConfig> {
+        &self.overrides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_acquire_exhausts_bucket() {
+        // Test: Tokens are consumed until the bucket is empty
+        let limiter = RateLimiter::new(2);
+        assert!(limiter.try_acquire(), "First token should be available");
+        assert!(limiter.try_acquire(), "Second token should be available");
+        assert!(!limiter.try_acquire(), "Bucket should be exhausted");
+    }
+
+    #[test]
+    fn test_refund_restores_token() {
+        // Test: Refunded tokens can be acquired again but never exceed capacity
+        let limiter = RateLimiter::new(1);
+        assert!(limiter.try_acquire());
+        limiter.refund();
+        assert_eq!(limiter.remaining(), 1, "Refund should restore the token");
+        limiter.refund();
+        assert_eq!(limiter.remaining(), 1, "Refund should not exceed capacity");
+    }
+
+    #[test]
+    fn test_host_limiters_are_independent() {
+        // Test: Exhausting one host's bucket does not affect other hosts
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "registry.npmjs.org".to_string(),
+            RateLimitConfig {
+                requests_per_minute: 1,
+                burst: None,
+            },
+        );
+        let limiter = HostRateLimiter::with_overrides(
+            RateLimitConfig {
+                requests_per_minute: 100,
+                burst: Some(5),
+            },
+            overrides,
+        );
+
+        assert!(limiter.limiter_for("registry.npmjs.org").try_acquire());
+        assert!(!limiter.limiter_for("registry.npmjs.org").try_acquire());
+        assert_eq!(limiter.remaining("crates.io"), 5, "Default burst applies");
+        assert!(limiter.limiter_for("crates.io").try_acquire());
+    }
+
+    #[tokio::test]
+    async fn test_min_interval_keeps_bucket_state() {
+        // Test: A crawl delay slows the existing bucket without dropping its debt or pause
+        let hosts = HostRateLimiter::new(RateLimitConfig {
+            requests_per_minute: 6000,
+            burst: Some(2),
+        });
+        let limiter = hosts.limiter_for("example.com");
+        limiter.acquire(5).await;
+        hosts.set_min_interval("example.com", Duration::from_secs(10));
+
+        assert!(
+            Arc::ptr_eq(&limiter, &hosts.limiter_for("example.com")),
+            "The bucket should be updated in place"
+        );
+        let budget = limiter.budget();
+        assert_eq!(budget.capacity, 1);
+        assert!((budget.refill_per_minute - 6.0).abs() < 1e-9);
+        assert!(
+            limiter.time_until_available(1) > Duration::from_secs(30),
+            "Debt from the oversized request should be kept"
+        );
+
+        hosts
+            .limiter_for("api.github.com")
+            .pause_for(Duration::from_secs(60));
+        hosts.set_min_interval("api.github.com", Duration::from_secs(10));
+        assert!(hosts.limiter_for("api.github.com").is_paused());
+        hosts.set_min_interval("example.com", Duration::from_millis(1));
+        assert!(
+            (limiter.budget().refill_per_minute - 6.0).abs() < 1e-9,
+            "A looser interval has no effect"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_status_from_headers() {
+        // Test: GitHub-style rate limit headers are parsed
+        let reset = Utc::now().timestamp() + 120;
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", reset.to_string().parse().unwrap());
+        let status = RateLimitStatus::from_headers(&headers);
+        assert_eq!(status.remaining, Some(0));
+        assert_eq!(status.reset_at.unwrap().timestamp(), reset);
+        let wait = status.wait_time().unwrap();
+        assert!(wait > Duration::from_secs(100), "Should wait until reset");
+
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        let status = RateLimitStatus::from_headers(&headers);
+        assert_eq!(status.wait_time(), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_update_limits_pauses_limiter() {
+        // Test: Server-reported limits clamp tokens and pause on exhaustion
+        let limiter = RateLimiter::new(100);
+        limiter.update_limits(&RateLimitStatus {
+            remaining: Some(3),
+            ..RateLimitStatus::default()
+        });
+        assert_eq!(limiter.remaining(), 3, "Tokens clamp to server remaining");
+
+        limiter.update_limits(&RateLimitStatus {
+            retry_after: Some(Duration::from_secs(60)),
+            ..RateLimitStatus::default()
+        });
+        assert!(limiter.is_paused());
+        assert!(!limiter.try_acquire(), "Paused limiter hands out no tokens");
+    }
+
+    struct CountingBackend {
+        calls: Mutex<u32>,
+    }
+
+    impl RateLimitBackend for CountingBackend {
+        fn try_acquire<'a>(
+            &'a self,
+            _host: &'a str,
+            _limit: &'a RateLimitConfig,
+        ) -> BoxFuture<'a, Result<Option<Duration>>> {
+            Box::pin(async move {
+                let mut calls = self.calls.lock().unwrap();
+                *calls += 1;
+                // Deny the first request so the limiter has to wait and retry
+                Ok((*calls == 1).then(|| Duration::from_millis(5)))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_acquire_cost_and_budget() {
+        // Test: Costly acquisitions drain the bucket and the budget reports what is left
+        let limiter = RateLimiter::with_burst(6000, 10);
+        limiter.acquire(7).await;
+        let budget = limiter.budget();
+        assert_eq!(budget.remaining, 3);
+        assert_eq!(budget.capacity, 10);
+        assert_eq!(budget.refill_per_minute, 6000.0);
+        assert_eq!(budget.paused_for, None);
+        assert_eq!(limiter.time_until_available(3), Duration::ZERO);
+        assert!(
+            limiter.time_until_available(10) > Duration::ZERO,
+            "A full bucket is needed for cost 10"
+        );
+
+        let started = Instant::now();
+        limiter.acquire(15).await;
+        assert!(
+            started.elapsed() >= Duration::from_millis(50),
+            "Oversized costs should wait for a full bucket"
+        );
+        assert_eq!(limiter.budget().remaining, 0, "Excess cost leaves a debt");
+
+        limiter.pause_for(Duration::from_secs(60));
+        assert!(limiter.budget().paused_for.is_some());
+    }
+
+    #[test]
+    fn test_state_survives_restart() {
+        // Test: Spent tokens and server pauses are restored from the state file
+        let path = std::env::temp_dir().join(format!(
+            "rate-limit-state-{}.json",
+            crate::utils::crypto::generate_uuid()
+        ));
+        let limit = RateLimitConfig {
+            requests_per_minute: 1,
+            burst: Some(3),
+        };
+        let first = HostRateLimiter::new(limit.clone());
+        let drained = first.limiter_for("registry.npmjs.org");
+        while drained.try_acquire() {}
+        first
+            .limiter_for("api.github.com")
+            .pause_for(Duration::from_secs(3600));
+        first.save_state(&path).unwrap();
+
+        let second = HostRateLimiter::new(limit);
+        second.load_state(&path).unwrap();
+        assert_eq!(
+            second.remaining("registry.npmjs.org"),
+            0,
+            "Spent tokens should not be refilled by a restart"
+        );
+        assert!(second.limiter_for("api.github.com").is_paused());
+        assert_eq!(second.remaining("pypi.org"), 3);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            second.load_state(&path).is_ok(),
+            "A missing state file should be ignored"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_shared_backend() {
+        // Test: Acquire retries the backend until it grants a token
+        let backend = Arc::new(CountingBackend {
+            calls: Mutex::new(0),
+        });
+        let limiter = HostRateLimiter::new(RateLimitConfig {
+            requests_per_minute: 60,
+            burst: None,
+        })
+        .with_backend(backend.clone());
+
+        limiter.acquire("registry.npmjs.org").await;
+        assert_eq!(*backend.calls.lock().unwrap(), 2);
+    }
+}
@@ -0,0 +1,131 @@
+//! Redis-backed rate limiting shared across collector instances
+//!
+//! Each host has a token bucket stored in a Redis hash. Tokens are taken by
+//! a Lua script so concurrent instances never overspend the budget, and the
+//! Redis server clock is used so instances with skewed clocks agree.
+
+use super::rate_limiter::RateLimitBackend;
+use crate::config::RateLimitConfig;
+use crate::error::{Error, Result};
+use futures::future::BoxFuture;
+use redis::Script;
+use redis::aio::ConnectionManager;
+use std::time::Duration;
+
+/// Token bucket refill and spend; returns milliseconds to wait, 0 if granted
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local per_ms = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1]) or capacity
+local ts = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * per_ms)
+local wait = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+else
+  wait = math.ceil((1 - tokens) / per_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / per_ms) + 1000)
+return wait
+"#;
+
+/// Rate limit backend storing token buckets in Redis
+#[derive(Clone)]
+pub struct RedisRateLimiter {
+    connection: ConnectionManager,
+    script: Script,
+    key_prefix: String,
+}
+
+impl RedisRateLimiter {
+    /// Connect to Redis, e.g. `redis://cache.internal:6379/0`
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| Error::http(format!("Invalid Redis url {}: {}", url, e)))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| Error::http(format!("Failed to connect to Redis: {}", e)))?;
+        Ok(Self {
+            connection,
+            script: Script::new(TOKEN_BUCKET_SCRIPT),
+            key_prefix: "repo-intel:rate-limit".to_string(),
+        })
+    }
+
+    /// Namespace keys, so unrelated deployments can share a Redis instance
+    pub fn with_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefix = prefix.into();
+        self
+    }
+
+    /// Redis key holding the bucket for a host
+    pub fn key_for(&self, host: &str) -> String {
+        format!("{}:{}", self.key_prefix, host)
+    }
+}
+
+impl RateLimitBackend for RedisRateLimiter {
+    fn try_acquire<'a>(
+        &'a self,
+        host: &'a str,
+        limit: &'a RateLimitConfig,
+    ) -> BoxFuture<'a, Result<Option<Duration>>> {
+        Box::pin(async move {
+            let capacity = limit.burst.unwrap_or(limit.requests_per_minute).max(1);
+            let per_ms = f64::from(limit.requests_per_minute.max(1)) / 60_000.0;
+            let mut connection = self.connection.clone();
+            let wait_ms: u64 = self
+                .script
+                .key(self.key_for(host))
+                .arg(capacity)
+                .arg(per_ms)
+                .invoke_async(&mut connection)
+                .await
+                .map_err(|e| Error::http(format!("Redis rate limit script failed: {}", e)))?;
+            Ok((wait_ms > 0).then(|| Duration::from_millis(wait_ms)))
+        })
+    }
+}
+
+impl std::fmt::Debug for RedisRateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisRateLimiter")
+            .field("key_prefix", &self.key_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shared_bucket_limits_all_instances() {
+        // Test: Two instances draw from one Redis bucket (requires REDIS_URL)
+        let Ok(url) = std::env::var("REDIS_URL") else {
+            return;
+        };
+        let prefix = format!("test:{}", crate::utils::crypto::generate_uuid());
+        let first = RedisRateLimiter::connect(&url)
+            .await
+            .unwrap()
+            .with_key_prefix(&prefix);
+        let second = RedisRateLimiter::connect(&url)
+            .await
+            .unwrap()
+            .with_key_prefix(&prefix);
+        let limit = RateLimitConfig {
+            requests_per_minute: 2,
+            burst: None,
+        };
+
+        assert!(first.try_acquire("npm", &limit).await.unwrap().is_none());
+        assert!(second.try_acquire("npm", &limit).await.unwrap().is_none());
+        let wait = first.try_acquire("npm", &limit).await.unwrap();
+        assert!(wait.is_some(), "Shared budget should be exhausted");
+    }
+}
@@ -0,0 +1,758 @@
+//! Retry logic for HTTP requests
+
+use crate::config::RegistryRetryConfig;
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Computes the delay before a retry attempt (1-based)
+pub trait BackoffStrategy: fmt::Debug + Send + Sync {
+    fn delay(&self, attempt: u32) -> Duration;
+}
+
+/// The same delay before every retry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantBackoff(pub Duration);
+
+impl BackoffStrategy for ConstantBackoff {
+    fn delay(&self, _attempt: u32) -> Duration {
+        self.0
+    }
+}
+
+/// Exponentially growing delay capped at a maximum
+///
+/// A delay that is not a valid duration, e.g. from a negative or NaN
+/// multiplier, falls back to `max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+}
+
+impl BackoffStrategy for ExponentialBackoff {
+    fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let delay = self.initial.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(delay.min(self.max.as_secs_f64())).unwrap_or(self.max)
+    }
+}
+
+/// "Full jitter": a uniformly random delay between zero and the inner delay
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FullJitter<B>(pub B);
+
+impl<B: BackoffStrategy> BackoffStrategy for FullJitter<B> {
+    fn delay(&self, attempt: u32) -> Duration {
+        self.0.delay(attempt).mul_f64(rand::random_range(0.0..=1.0))
+    }
+}
+
+/// "Equal jitter": half the inner delay plus a random share of the other half
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqualJitter<B>(pub B);
+
+impl<B: BackoffStrategy> BackoffStrategy for EqualJitter<B> {
+    fn delay(&self, attempt: u32) -> Duration {
+        let half = self.0.delay(attempt) / 2;
+        half + half.mul_f64(rand::random_range(0.0..=1.0))
+    }
+}
+
+/// Jitter applied to the exponential backoff of a [`RetryConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Jitter {
+    #[default]
+    None,
+    Full,
+    Equal,
+}
+
+/// Kind of failure, used to decide whether a request is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// `429 Too Many Requests`
+    RateLimited,
+    /// `5xx` responses
+    ServerError,
+    /// `4xx` responses other than 429
+    ClientError,
+    /// Connection failures, timeouts, and other transport errors
+    Network,
+}
+
+impl ErrorClass {
+    /// Classify a response status; successful statuses have no class
+    pub fn from_status(status: u16) -> Option<Self> {
+        match status {
+            429 => Some(Self::RateLimited),
+            400..=499 => Some(Self::ClientError),
+            500..=599 => Some(Self::ServerError),
+            _ => None,
+        }
+    }
+}
+
+/// Which classes of failure are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub rate_limited: bool,
+    pub server_errors: bool,
+    pub client_errors: bool,
+    pub network_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            rate_limited: true,
+            server_errors: true,
+            client_errors: false,
+            network_errors: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Check whether a class of failure should be retried
+    pub fn should_retry(&self, class: ErrorClass) -> bool {
+        match class {
+            ErrorClass::RateLimited => self.rate_limited,
+            ErrorClass::ServerError => self.server_errors,
+            ErrorClass::ClientError => self.client_errors,
+            ErrorClass::Network => self.network_errors,
+        }
+    }
+}
+
+/// Total retries allowed across all requests sharing the budget
+///
+/// Caps retry amplification during an outage: once a run has spent its
+/// budget, failures are returned immediately instead of retried.
+#[derive(Debug)]
+pub struct RetryBudget {
+    remaining: AtomicU32,
+}
+
+impl RetryBudget {
+    /// Create a budget allowing `max_retries` retries in total
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            remaining: AtomicU32::new(max_retries),
+        }
+    }
+
+    /// Spend one retry, returning `false` if the budget is exhausted
+    pub fn try_consume(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Retries left in the budget
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::Acquire)
+    }
+}
+
+/// Retry configuration with exponential backoff
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Jitter applied to the exponential backoff
+    pub jitter: Jitter,
+    /// Custom backoff replacing the exponential settings above
+    pub strategy: Option<Arc<dyn BackoffStrategy>>,
+    /// Retry budget shared across requests
+    pub budget: Option<Arc<RetryBudget>>,
+    pub policy: RetryPolicy,
+    /// Response statuses to retry, replacing `policy` for non-rate-limit responses
+    pub retry_statuses: Option<Vec<u16>>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: Jitter::None,
+            strategy: None,
+            budget: None,
+            policy: RetryPolicy::default(),
+            retry_statuses: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Calculate the backoff delay before the given retry attempt (1-based)
+    pub fn calculate_backoff(&self, attempt: u32) -> Duration {
+        if let Some(strategy) = &self.strategy {
+            return strategy.delay(attempt);
+        }
+        let exponential = ExponentialBackoff {
+            initial: self.initial_backoff,
+            max: self.max_backoff,
+            multiplier: self.multiplier,
+        };
+        match self.jitter {
+            Jitter::None => exponential.delay(attempt),
+            Jitter::Full => FullJitter(exponential).delay(attempt),
+            Jitter::Equal => EqualJitter(exponential).delay(attempt),
+        }
+    }
+
+    /// Check whether a response status should be retried
+    pub fn is_retryable_status(status: u16) -> bool {
+        status == 429 || (500..600).contains(&status)
+    }
+
+    /// Decide whether to retry a failure of the given class
+    ///
+    /// `attempt` is the number of retries already made. A retry is only
+    /// granted if the policy allows the class, `max_retries` is not reached,
+    /// and the shared budget (if any) still has retries to spend.
+    pub fn should_retry(&self, class: ErrorClass, attempt: u32) -> bool {
+        self.policy.should_retry(class) && self.can_retry(attempt)
+    }
+
+    /// Decide whether to retry a response with the given status
+    ///
+    /// Uses `retry_statuses` when set, otherwise the status's error class.
+    pub fn should_retry_status(&self, status: u16, attempt: u32) -> bool {
+        match &self.retry_statuses {
+            Some(statuses) => statuses.contains(&status) && self.can_retry(attempt),
+            None => ErrorClass::from_status(status).is_some_and(|c| self.should_retry(c, attempt)),
+        }
+    }
+
+    /// Apply a registry's overrides on top of this configuration
+    pub fn with_overrides(&self, overrides: &RegistryRetryConfig) -> Self {
+        let mut config = self.clone();
+        if let Some(max_retries) = overrides.max_retries {
+            config.max_retries = max_retries;
+        }
+        if let Some(statuses) = &overrides.retry_statuses {
+            config.retry_statuses = Some(statuses.clone());
+        }
+        if let Some(ms) = overrides.initial_backoff_ms {
+            config.initial_backoff = Duration::from_millis(ms);
+        }
+        if let Some(ms) = overrides.max_backoff_ms {
+            config.max_backoff = Duration::from_millis(ms);
+        }
+        if let Some(multiplier) = overrides.multiplier {
+            config.multiplier = multiplier;
+        }
+        config
+    }
+
+    fn can_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_retries
+            && self
+                .budget
+                .as_ref()
+                .is_none_or(|budget| budget.try_consume())
+    }
+}
+
+/// Circuit breaker state for a single host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests fail fast until the cooldown elapses
+    Open,
+    /// A limited number of trial requests are allowed through
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Circuit breaker configuration
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before allowing trial requests
+    pub cooldown: Duration,
+    /// Trial requests allowed while half-open
+    pub half_open_max_requests: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(60),
+            half_open_max_requests: 1,
+        }
+    }
+}
+
+/// A state change of a host's circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitTransition {
+    pub host: String,
+    pub from: CircuitState,
+    pub to: CircuitState,
+}
+
+/// Callback invoked on every circuit state transition
+pub type CircuitListener = Arc<dyn Fn(&CircuitTransition) + Send + Sync>;
+
+#[derive(Debug)]
+struct HostCircuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// Times the circuit has opened, to tell trials of different half-open periods apart
+    openings: u64,
+    half_open_requests: u32,
+}
+
+impl Default for HostCircuit {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            openings: 0,
+            half_open_requests: 0,
+        }
+    }
+}
+
+/// Per-host circuit breaker
+///
+/// Repeated server errors or timeouts against a host open its circuit so
+/// further requests fail fast for the cooldown window instead of hammering
+/// a struggling registry.
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+    listener: Option<CircuitListener>,
+}
+
+impl CircuitBreaker {
+    /// Create a new circuit breaker
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+            listener: None,
+        }
+    }
+
+    /// Register a callback for state transitions (e.g. to record metrics)
+    pub fn with_listener(mut self, listener: CircuitListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Check whether a request to `host` may proceed
+    ///
+    /// Record the outcome on the returned permit. While half-open, a permit
+    /// dropped without an outcome gives its trial slot back.
+    pub fn check(&self, host: &str) -> Result<CircuitPermit<'_>> {
+        let mut transition = None;
+        let result = {
+            let mut hosts = self.lock_hosts();
+            let circuit = hosts.entry(host.to_string()).or_default();
+            let trial = Some(circuit.openings);
+            match circuit.state {
+                CircuitState::Closed => Ok(None),
+                CircuitState::Open => {
+                    let cooled_down = circuit
+                        .opened_at
+                        .is_some_and(|opened| opened.elapsed() >= self.config.cooldown);
+                    if cooled_down {
+                        circuit.state = CircuitState::HalfOpen;
+                        circuit.half_open_requests = 1;
+                        transition = Some((CircuitState::Open, CircuitState::HalfOpen));
+                        Ok(trial)
+                    } else {
+                        Err(Error::http(format!("circuit breaker open for {}", host)))
+                    }
+                }
+                CircuitState::HalfOpen => {
+                    if circuit.half_open_requests < self.config.half_open_max_requests {
+                        circuit.half_open_requests += 1;
+                        Ok(trial)
+                    } else {
+                        Err(Error::http(format!(
+                            "circuit breaker half-open for {}",
+                            host
+                        )))
+                    }
+                }
+            }
+        };
+
+        if let Some((from, to)) = transition {
+            self.notify(host, from, to);
+        }
+        result.map(|trial| CircuitPermit {
+            breaker: self,
+            host: host.to_string(),
+            trial,
+        })
+    }
+
+    /// Record a successful request to `host`
+    pub fn record_success(&self, host: &str) {
+        let previous = {
+            let mut hosts = self.lock_hosts();
+            let circuit = hosts.entry(host.to_string()).or_default();
+            let previous = circuit.state;
+            *circuit = HostCircuit {
+                openings: circuit.openings,
+                ..HostCircuit::default()
+            };
+            previous
+        };
+
+        if previous != CircuitState::Closed {
+            self.notify(host, previous, CircuitState::Closed);
+        }
+    }
+
+    /// Record a failed request (server error or timeout) to `host`
+    pub fn record_failure(&self, host: &str) {
+        let transition = {
+            let mut hosts = self.lock_hosts();
+            let circuit = hosts.entry(host.to_string()).or_default();
+            circuit.consecutive_failures += 1;
+
+            let should_open = match circuit.state {
+                CircuitState::Closed => {
+                    circuit.consecutive_failures >= self.config.failure_threshold
+                }
+                CircuitState::HalfOpen => true,
+                CircuitState::Open => false,
+            };
+
+            if should_open {
+                let previous = circuit.state;
+                circuit.state = CircuitState::Open;
+                circuit.opened_at = Some(Instant::now());
+                circuit.openings += 1;
+                circuit.half_open_requests = 0;
+                Some(previous)
+            } else {
+                None
+            }
+        };
+
+        if let Some(previous) = transition {
+            self.notify(host, previous, CircuitState::Open);
+        }
+    }
+
+    /// Return a trial slot taken after the circuit's `openings`-th opening
+    fn release_trial(&self, host: &str, openings: u64) {
+        let mut hosts = self.lock_hosts();
+        if let Some(circuit) = hosts.get_mut(host)
+            && circuit.state == CircuitState::HalfOpen
+            && circuit.openings == openings
+        {
+            circuit.half_open_requests = circuit.half_open_requests.saturating_sub(1);
+        }
+    }
+
+    /// Current state of the circuit for `host`
+    pub fn state(&self, host: &str) -> CircuitState {
+        self.lock_hosts()
+            .get(host)
+            .map(|circuit| circuit.state)
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    fn notify(&self, host: &str, from: CircuitState, to: CircuitState) {
+        match to {
+            CircuitState::Open => warn!("Circuit breaker for {} {} -> {}", host, from, to),
+            _ => info!("Circuit breaker for {} {} -> {}", host, from, to),
+        }
+        if let Some(listener) = &self.listener {
+            listener(&CircuitTransition {
+                host: host.to_string(),
+                from,
+                to,
+            });
+        }
+    }
+
+    fn lock_hosts(&self) -> std::sync::MutexGuard<'_, HashMap<String, HostCircuit>> {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Permission from [`CircuitBreaker::check`] for one request
+///
+/// Dropping it without recording an outcome, e.g. when the request future
+/// is cancelled, frees a half-open trial slot so the circuit cannot be left
+/// half-open with no request able to close it.
+#[must_use = "record the request outcome on the permit"]
+pub struct CircuitPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    host: String,
+    /// Set for half-open trials, holding the circuit's opening count
+    trial: Option<u64>,
+}
+
+impl CircuitPermit<'_> {
+    pub fn record_success(mut self) {
+        self.trial = None;
+        self.breaker.record_success(&self.host);
+    }
+
+    pub fn record_failure(mut self) {
+        self.trial = None;
+        self.breaker.record_failure(&self.host);
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(openings) = self.trial {
+            self.breaker.release_trial(&self.host, openings);
+        }
+    }
+}
+
+impl fmt::Debug for CircuitPermit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitPermit")
+            .field("host", &self.host)
+            .field("trial", &self.trial.is_some())
+            .finish()
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_backoff() {
+        // Test: Backoff grows exponentially and is capped at max_backoff
+        let config = RetryConfig {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            multiplier: 2.0,
+            ..RetryConfig::default()
+        };
+        assert_eq!(config.calculate_backoff(1), Duration::from_millis(100));
+        assert_eq!(config.calculate_backoff(2), Duration::from_millis(200));
+        assert_eq!(config.calculate_backoff(3), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_invalid_multiplier_falls_back_to_max() {
+        // Test: Negative or NaN multipliers yield the maximum delay instead of panicking
+        for multiplier in [-2.0, f64::NAN, f64::INFINITY] {
+            let backoff = ExponentialBackoff {
+                initial: Duration::from_millis(100),
+                max: Duration::from_secs(1),
+                multiplier,
+            };
+            assert_eq!(
+                backoff.delay(2),
+                Duration::from_secs(1),
+                "Multiplier {} should fall back to max",
+                multiplier
+            );
+        }
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        // Test: Only rate limit and server errors are retryable
+        assert!(RetryConfig::is_retryable_status(429));
+        assert!(RetryConfig::is_retryable_status(503));
+        assert!(!RetryConfig::is_retryable_status(404));
+        assert!(!RetryConfig::is_retryable_status(200));
+    }
+
+    #[test]
+    fn test_jittered_backoff_stays_in_range() {
+        // Test: Full jitter stays below the base delay, equal jitter above half of it
+        let base = ExponentialBackoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            multiplier: 2.0,
+        };
+        for attempt in 1..=5 {
+            let full = FullJitter(base).delay(attempt);
+            let equal = EqualJitter(base).delay(attempt);
+            assert!(full <= base.delay(attempt));
+            assert!(equal >= base.delay(attempt) / 2 && equal <= base.delay(attempt));
+        }
+
+        let config = RetryConfig {
+            strategy: Some(Arc::new(ConstantBackoff(Duration::from_millis(7)))),
+            ..RetryConfig::default()
+        };
+        assert_eq!(config.calculate_backoff(4), Duration::from_millis(7));
+    }
+
+    #[test]
+    fn test_retry_policy_and_budget() {
+        // Test: Client errors are never retried and the budget caps total retries
+        let config = RetryConfig {
+            max_retries: 5,
+            budget: Some(Arc::new(RetryBudget::new(2))),
+            ..RetryConfig::default()
+        };
+        assert!(!config.should_retry(ErrorClass::ClientError, 0));
+        assert!(config.should_retry(ErrorClass::ServerError, 0));
+        assert!(config.should_retry(ErrorClass::RateLimited, 1));
+        assert!(
+            !config.should_retry(ErrorClass::Network, 0),
+            "Exhausted budget should stop retries"
+        );
+        assert_eq!(ErrorClass::from_status(404), Some(ErrorClass::ClientError));
+        assert_eq!(ErrorClass::from_status(200), None);
+    }
+
+    #[test]
+    fn test_registry_overrides_and_status_matrix() {
+        // Test: Registry overrides replace only the fields they set and the status list
+        let base = RetryConfig::default();
+        let config = base.with_overrides(&RegistryRetryConfig {
+            max_retries: Some(1),
+            retry_statuses: Some(vec![404, 503]),
+            initial_backoff_ms: Some(100),
+            ..RegistryRetryConfig::default()
+        });
+        assert_eq!(config.max_retries, 1);
+        assert_eq!(config.initial_backoff, Duration::from_millis(100));
+        assert_eq!(config.max_backoff, base.max_backoff);
+        assert!(config.should_retry_status(404, 0));
+        assert!(
+            !config.should_retry_status(500, 0),
+            "Statuses outside the list should not be retried"
+        );
+        assert!(!config.should_retry_status(503, 1));
+        assert!(base.should_retry_status(500, 0));
+        assert!(!base.should_retry_status(404, 0));
+    }
+
+    #[test]
+    fn test_circuit_opens_after_threshold() {
+        // Test: Consecutive failures open the circuit and requests fail fast
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+            half_open_max_requests: 1,
+        });
+        breaker.record_failure("registry.npmjs.org");
+        assert_eq!(breaker.state("registry.npmjs.org"), CircuitState::Closed);
+        breaker.record_failure("registry.npmjs.org");
+        assert_eq!(breaker.state("registry.npmjs.org"), CircuitState::Open);
+        assert!(breaker.check("registry.npmjs.org").is_err());
+        assert!(
+            breaker.check("crates.io").is_ok(),
+            "Other hosts are unaffected"
+        );
+    }
+
+    #[test]
+    fn test_circuit_half_open_recovery() {
+        // Test: After the cooldown a trial request is allowed and success closes the circuit
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let recorded = transitions.clone();
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown: Duration::ZERO,
+            half_open_max_requests: 1,
+        })
+        .with_listener(Arc::new(move |t: &CircuitTransition| {
+            recorded.lock().unwrap().push(t.to);
+        }));
+
+        breaker.record_failure("pypi.org");
+        let trial = breaker
+            .check("pypi.org")
+            .expect("Trial request should pass");
+        assert_eq!(breaker.state("pypi.org"), CircuitState::HalfOpen);
+        assert!(breaker.check("pypi.org").is_err(), "Only one trial request");
+
+        trial.record_success();
+        assert_eq!(breaker.state("pypi.org"), CircuitState::Closed);
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            vec![
+                CircuitState::Open,
+                CircuitState::HalfOpen,
+                CircuitState::Closed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_abandoned_half_open_trial_frees_its_slot() {
+        // Test: A trial dropped without an outcome lets the next request probe the host
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown: Duration::ZERO,
+            half_open_max_requests: 1,
+        });
+        breaker.record_failure("pypi.org");
+
+        let abandoned = breaker.check("pypi.org").unwrap();
+        assert!(breaker.check("pypi.org").is_err(), "Slot is taken");
+        drop(abandoned);
+        assert_eq!(breaker.state("pypi.org"), CircuitState::HalfOpen);
+
+        let trial = breaker
+            .check("pypi.org")
+            .expect("Abandoned slot should be reusable");
+        trial.record_failure();
+        assert_eq!(breaker.state("pypi.org"), CircuitState::Open);
+
+        let stale = breaker.check("pypi.org").unwrap();
+        breaker.record_failure("pypi.org");
+        let fresh = breaker.check("pypi.org").unwrap();
+        drop(stale);
+        assert!(
+            breaker.check("pypi.org").is_err(),
+            "A trial from before the circuit reopened must not free the new slot"
+        );
+        fresh.record_success();
+        assert!(breaker.check("pypi.org").is_ok());
+    }
+}
@@ -0,0 +1,437 @@
+//! Crawl politeness for sources without formal APIs
+//!
+//! When enabled in [`PolitenessConfig`], the client fetches `robots.txt`
+//! once per host, refuses disallowed paths, and spaces requests by the
+//! host's `Crawl-delay` through the rate limiter. As RFC 9309 requires, a
+//! missing `robots.txt` allows everything while a server error or an
+//! unreachable host disallows everything; the fetch is retried on the
+//! next request to that host.
+
+use super::client::APIClient;
+use crate::config::PolitenessConfig;
+use crate::error::{Error, Result};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell};
+use tracing::{debug, warn};
+
+/// Parsed `robots.txt` rules
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsTxt {
+    groups: Vec<RobotsGroup>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsGroup {
+    agents: Vec<String>,
+    /// `(allow, pattern)` pairs
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsTxt {
+    /// Parse a `robots.txt` body; unknown lines are ignored
+    pub fn parse(body: &str) -> Self {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        let mut in_agents = false;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push(RobotsGroup::default());
+                        in_agents = true;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                field => {
+                    in_agents = false;
+                    let Some(group) = groups.last_mut() else {
+                        continue;
+                    };
+                    match field {
+                        "allow" if !value.is_empty() => group.rules.push((true, value.to_string())),
+                        "disallow" if !value.is_empty() => {
+                            group.rules.push((false, value.to_string()))
+                        }
+                        "crawl-delay" => {
+                            group.crawl_delay = value
+                                .parse::<f64>()
+                                .ok()
+                                .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Self { groups }
+    }
+
+    /// Check whether `user_agent` may fetch `path`
+    ///
+    /// The longest matching rule wins, with `Allow` winning ties.
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let Some(group) = self.group_for(user_agent) else {
+            return true;
+        };
+        group
+            .rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+
+    /// Delay requested between requests from `user_agent`
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.group_for(user_agent)?.crawl_delay
+    }
+
+    /// The group naming this agent's product token, else the `*` group
+    fn group_for(&self, user_agent: &str) -> Option<&RobotsGroup> {
+        let token = product_token(user_agent);
+        self.groups
+            .iter()
+            .find(|group| group.agents.contains(&token))
+            .or_else(|| {
+                self.groups
+                    .iter()
+                    .find(|group| group.agents.iter().any(|agent| agent == "*"))
+            })
+    }
+}
+
+/// `common-library/0.1.0` matches groups for `common-library`
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .split(['/', ' '])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Match a path against a rule supporting `*` wildcards and a `$` anchor
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Per-host `robots.txt` rules fetched on first contact
+///
+/// Each host has its own cell, so a slow `robots.txt` only delays requests
+/// to that host.
+#[derive(Debug, Default)]
+pub(crate) struct RobotsCache {
+    hosts: Mutex<HashMap<String, Arc<OnceCell<Arc<RobotsTxt>>>>>,
+}
+
+impl APIClient {
+    /// Fail if politeness rules forbid fetching `url`
+    ///
+    /// On the first request to a host this fetches its `robots.txt` (when
+    /// enabled) and applies the crawl delay to the host's rate limiter. If
+    /// the file could not be fetched the request fails and the next one
+    /// tries again.
+    pub(crate) async fn check_politeness(&self, host: &str, url: &str) -> Result<()> {
+        let politeness = &self.config().politeness;
+        if !politeness.respect_robots_txt && politeness.default_crawl_delay_seconds.is_none() {
+            return Ok(());
+        }
+        let user_agent = self.user_agent_for(host);
+        let cell = self
+            .robots()
+            .hosts
+            .lock()
+            .await
+            .entry(host.to_string())
+            .or_default()
+            .clone();
+        let robots = cell
+            .get_or_try_init(|| async {
+                let robots = Arc::new(self.fetch_robots(politeness, host, url).await?);
+                let delay = robots.crawl_delay(user_agent).or_else(|| {
+                    politeness
+                        .default_crawl_delay_seconds
+                        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                });
+                if let Some(delay) = delay {
+                    debug!("Spacing requests to {} by {:?}", host, delay);
+                    self.rate_limiter().set_min_interval(host, delay);
+                }
+                Ok::<_, Error>(robots)
+            })
+            .await?
+            .clone();
+
+        let parsed =
+            Url::parse(url).map_err(|e| Error::http(format!("Invalid URL {}: {}", url, e)))?;
+        let path = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+        if !robots.is_allowed(user_agent, &path) {
+            return Err(Error::http(format!("{} is disallowed by robots.txt", url)));
+        }
+        Ok(())
+    }
+
+    /// User-Agent sent to a host, honoring per-host overrides
+    pub(crate) fn user_agent_for(&self, host: &str) -> &str {
+        self.config()
+            .politeness
+            .user_agents
+            .get(host)
+            .unwrap_or(&self.config().user_agent)
+    }
+
+    /// Fetch a host's `robots.txt`
+    ///
+    /// A missing file (a 4xx answer) allows everything. A server error or
+    /// failed request is an error, since the rules are unknown.
+    async fn fetch_robots(
+        &self,
+        politeness: &PolitenessConfig,
+        host: &str,
+        url: &str,
+    ) -> Result<RobotsTxt> {
+        if !politeness.respect_robots_txt {
+            return Ok(RobotsTxt::default());
+        }
+        let Some(robots_url) = Url::parse(url)
+            .ok()
+            .and_then(|u| u.join("/robots.txt").ok())
+        else {
+            return Ok(RobotsTxt::default());
+        };
+        let unavailable = |reason: String| {
+            warn!("Failed to fetch {}: {}", robots_url, reason);
+            Error::http(format!(
+                "robots.txt for {} is unavailable ({}); retry later",
+                host, reason
+            ))
+        };
+        let response = self
+            .http_client()
+            .get(robots_url.clone())
+            .header(reqwest::header::USER_AGENT, self.user_agent_for(host))
+            .timeout(self.config().timeout)
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => match response.text().await {
+                Ok(body) => Ok(RobotsTxt::parse(&body)),
+                Err(e) => Err(unavailable(e.to_string())),
+            },
+            Ok(response) if response.status().is_server_error() => {
+                Err(unavailable(response.status().to_string()))
+            }
+            Ok(_) => Ok(RobotsTxt::default()),
+            Err(e) => Err(unavailable(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ROBOTS: &str = "\
+User-agent: *
+Disallow: /private
+Allow: /private/public
+Disallow: /*.json$
+
+User-agent: common-library
+User-agent: other-bot
+Disallow: /admin
+Crawl-delay: 2.5
+";
+
+    #[test]
+    fn test_robots_rules_and_crawl_delay() {
+        // Test: Groups are chosen by product token and the longest rule wins
+        let robots = RobotsTxt::parse(ROBOTS);
+        assert!(!robots.is_allowed("scraper/1.0", "/private/data"));
+        assert!(robots.is_allowed("scraper/1.0", "/private/public/page"));
+        assert!(!robots.is_allowed("scraper/1.0", "/dump/all.json"));
+        assert!(robots.is_allowed("scraper/1.0", "/dump/all.json.gz"));
+        assert_eq!(robots.crawl_delay("scraper/1.0"), None);
+
+        assert!(!robots.is_allowed("common-library/0.1.0", "/admin"));
+        assert!(
+            robots.is_allowed("common-library/0.1.0", "/private/data"),
+            "A specific group replaces the * group"
+        );
+        assert_eq!(
+            robots.crawl_delay("Common-Library/0.1.0"),
+            Some(Duration::from_millis(2500))
+        );
+
+        for delay in ["-1", "NaN", "inf", "1e30"] {
+            let robots = RobotsTxt::parse(&format!("User-agent: *\nCrawl-delay: {}\n", delay));
+            assert_eq!(
+                robots.crawl_delay("bot"),
+                None,
+                "{} should be ignored",
+                delay
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_robots_txt_does_not_block_other_hosts() {
+        // Test: Fetching one host's robots.txt does not hold up requests to another host
+        let slow = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(404).set_delay(Duration::from_secs(2)))
+            .mount(&slow)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&slow)
+            .await;
+        let fast = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&fast)
+            .await;
+
+        let mut config = HttpClientConfig::default();
+        config.politeness.respect_robots_txt = true;
+        config.politeness.default_crawl_delay_seconds = Some(f64::NAN);
+        let client = Arc::new(APIClient::new(config).unwrap());
+        let slow_url = format!("http://localhost:{}/packages", slow.address().port());
+        let pending = tokio::spawn({
+            let client = client.clone();
+            async move { client.get(&slow_url).await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let fast_response = tokio::time::timeout(
+            Duration::from_secs(1),
+            client.get(&format!("{}/packages", fast.uri())),
+        )
+        .await;
+        assert!(
+            matches!(fast_response, Ok(Ok(_))),
+            "Other hosts should not wait for the slow robots.txt"
+        );
+        assert!(
+            pending.await.unwrap().is_ok(),
+            "Invalid default delay is ignored"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_robots_server_error_disallows_until_retried() {
+        // Test: A 5xx robots.txt refuses the request and is fetched again on the next one
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/packages"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = HttpClientConfig::default();
+        config.politeness.respect_robots_txt = true;
+        let client = APIClient::new(config).unwrap();
+        let url = format!("{}/packages", server.uri());
+
+        assert!(
+            client.get(&url).await.is_err(),
+            "A server error on robots.txt should disallow the request"
+        );
+        assert!(
+            client.get(&url).await.is_ok(),
+            "A missing robots.txt on retry should allow it"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_honors_robots_and_user_agent_override() {
+        // Test: Disallowed paths fail, crawl-delay limits the host, and the UA override is sent
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(
+                    "User-agent: polite-bot\nDisallow: /private\nCrawl-delay: 60\n",
+                ),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/packages"))
+            .and(header("User-Agent", "polite-bot/2.0"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = HttpClientConfig::default();
+        config.politeness.respect_robots_txt = true;
+        config
+            .politeness
+            .user_agents
+            .insert("127.0.0.1".to_string(), "polite-bot/2.0".to_string());
+        let client = APIClient::new(config).unwrap();
+
+        let disallowed = client.get(&format!("{}/private/x", server.uri())).await;
+        assert!(disallowed.is_err(), "Disallowed path should be refused");
+
+        client
+            .get(&format!("{}/packages", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(
+            client.rate_limiter().remaining("127.0.0.1"),
+            0,
+            "Crawl-delay should leave no burst capacity"
+        );
+    }
+}
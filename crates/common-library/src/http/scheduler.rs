@@ -0,0 +1,205 @@
+//! Priority-based request scheduling
+//!
+//! A [`RequestScheduler`] caps the number of requests in flight and hands
+//! freed slots to the highest-priority waiter first, so interactive queries
+//! are not starved behind bulk backfill traffic. Requests of equal priority
+//! are served in arrival order.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Priority of a scheduled request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum RequestPriority {
+    /// Bulk collection and backfill traffic
+    Background,
+    #[default]
+    Normal,
+    /// Queries a user is actively waiting on
+    Interactive,
+}
+
+#[derive(Debug, Default)]
+struct SchedulerState {
+    in_flight: usize,
+    waiters: BTreeMap<RequestPriority, VecDeque<oneshot::Sender<()>>>,
+}
+
+/// Limits concurrent requests and admits waiters by priority
+#[derive(Debug)]
+pub struct RequestScheduler {
+    max_concurrent: usize,
+    state: Mutex<SchedulerState>,
+}
+
+impl RequestScheduler {
+    /// Create a scheduler allowing `max_concurrent` requests in flight
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent: max_concurrent.max(1),
+            state: Mutex::new(SchedulerState::default()),
+        }
+    }
+
+    /// Wait for a slot
+    ///
+    /// The slot is held until the returned permit is dropped.
+    pub async fn acquire(self: &Arc<Self>, priority: RequestPriority) -> SchedulerPermit {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            let queued = state.waiters.values().any(|queue| !queue.is_empty());
+            if state.in_flight < self.max_concurrent && !queued {
+                state.in_flight += 1;
+                return SchedulerPermit {
+                    scheduler: self.clone(),
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.waiters.entry(priority).or_default().push_back(sender);
+            receiver
+        };
+
+        let mut pending = PendingSlot {
+            scheduler: self.clone(),
+            receiver: Some(receiver),
+        };
+        if let Some(receiver) = pending.receiver.as_mut() {
+            // The sender is only dropped unsent if the scheduler itself is
+            // dropped, which cannot happen while we hold an `Arc` to it
+            let _ = receiver.await;
+        }
+        pending.receiver = None;
+        SchedulerPermit {
+            scheduler: self.clone(),
+        }
+    }
+
+    /// Maximum number of requests in flight
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Number of requests currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Number of requests waiting at a priority
+    pub fn queued(&self, priority: RequestPriority) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .waiters
+            .get(&priority)
+            .map_or(0, VecDeque::len)
+    }
+
+    /// Hand a slot to the highest-priority waiter, or free it
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(mut entry) = state.waiters.last_entry() {
+            let sender = entry.get_mut().pop_front();
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+            if let Some(sender) = sender
+                && sender.send(()).is_ok()
+            {
+                return;
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+}
+
+/// A held scheduler slot, released on drop
+#[derive(Debug)]
+pub struct SchedulerPermit {
+    scheduler: Arc<RequestScheduler>,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// Returns a slot that was handed over after its waiter was cancelled
+struct PendingSlot {
+    scheduler: Arc<RequestScheduler>,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for PendingSlot {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if receiver.try_recv().is_ok() {
+                self.scheduler.release();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_higher_priority_waiters_are_admitted_first() {
+        // Test: A freed slot goes to the interactive waiter before earlier background waiters
+        let scheduler = Arc::new(RequestScheduler::new(1));
+        let held = scheduler.acquire(RequestPriority::Normal).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut tasks = Vec::new();
+        for (name, priority) in [
+            ("background-1", RequestPriority::Background),
+            ("background-2", RequestPriority::Background),
+            ("interactive", RequestPriority::Interactive),
+        ] {
+            let scheduler = scheduler.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = scheduler.acquire(priority).await;
+                order.lock().unwrap().push(name);
+            }));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(scheduler.queued(RequestPriority::Background), 2);
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["interactive", "background-1", "background-2"]
+        );
+        assert_eq!(scheduler.in_flight(), 0, "All slots should be released");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_does_not_leak_slot() {
+        // Test: Dropping a waiting acquire leaves the slot count consistent
+        let scheduler = Arc::new(RequestScheduler::new(1));
+        let held = scheduler.acquire(RequestPriority::Normal).await;
+
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(10),
+            scheduler.acquire(RequestPriority::Interactive),
+        )
+        .await;
+        assert!(
+            waiting.is_err(),
+            "Acquire should wait while the slot is held"
+        );
+
+        drop(held);
+        assert_eq!(scheduler.in_flight(), 0);
+        let _permit = scheduler.acquire(RequestPriority::Background).await;
+        assert_eq!(scheduler.in_flight(), 1);
+    }
+}
@@ -0,0 +1,394 @@
+//! Server-Sent Events client with automatic reconnection
+//!
+//! Streams are reconnected with backoff when the connection drops, resuming
+//! from the last received event via the `Last-Event-ID` header. A `retry:`
+//! field sent by the server overrides the backoff delay.
+
+use super::client::APIClient;
+use super::retry::RetryConfig;
+use crate::error::{Error, Result};
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use reqwest::Method;
+use reqwest::header::{ACCEPT, HeaderMap, HeaderValue};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tracing::warn;
+
+/// A single server-sent event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    pub id: Option<String>,
+    /// Event type; `message` when the server does not name one
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental parser for the `text/event-stream` format
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: String,
+    /// Trailing bytes of a UTF-8 character split across chunks
+    partial: Vec<u8>,
+    id: Option<String>,
+    event: Option<String>,
+    data: Vec<String>,
+    retry: Option<Duration>,
+    last_event_id: Option<String>,
+}
+
+impl SseParser {
+    /// Create an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the stream, returning every event it completes
+    pub fn feed(&mut self, chunk: &str) -> Vec<SseEvent> {
+        self.buffer.push_str(chunk);
+        let mut events = Vec::new();
+        while let Some(newline) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=newline).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Feed raw bytes, holding back a UTF-8 character split across chunks
+    pub fn feed_bytes(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.partial.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(_) => self.partial.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => {
+                let text = String::from_utf8_lossy(&self.partial).into_owned();
+                self.partial.clear();
+                return self.feed(&text);
+            }
+        };
+        let rest = self.partial.split_off(valid);
+        let text =
+            String::from_utf8(std::mem::replace(&mut self.partial, rest)).unwrap_or_default();
+        self.feed(&text)
+    }
+
+    /// ID of the last dispatched event, sent as `Last-Event-ID` on reconnect
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// Reconnection delay requested by the server
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Drop any partially received event, e.g. after a disconnect
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.partial.clear();
+        self.id = None;
+        self.event = None;
+        self.data.clear();
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "data" => self.data.push(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(millis) = value.parse() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        if let Some(id) = self.id.take() {
+            self.last_event_id = Some(id);
+        }
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            id: self.last_event_id.clone(),
+            event: event.unwrap_or_else(|| "message".to_string()),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+struct SseState<'a> {
+    client: &'a APIClient,
+    url: String,
+    reconnect: RetryConfig,
+    parser: SseParser,
+    body: Option<BoxStream<'a, Result<Bytes>>>,
+    pending: VecDeque<SseEvent>,
+    failures: u32,
+    done: bool,
+}
+
+impl APIClient {
+    /// Subscribe to a Server-Sent Events endpoint
+    ///
+    /// Dropped connections are re-established with the backoff in
+    /// `reconnect`; the stream ends with an error once `max_retries`
+    /// consecutive reconnects have failed. Only the client's `read_timeout`
+    /// applies, so a stream can stay open indefinitely while events arrive.
+    pub fn subscribe_sse<'a>(
+        &'a self,
+        url: &str,
+        reconnect: RetryConfig,
+    ) -> impl Stream<Item = Result<SseEvent>> + 'a {
+        let state = SseState {
+            client: self,
+            url: url.to_string(),
+            reconnect,
+            parser: SseParser::new(),
+            body: None,
+            pending: VecDeque::new(),
+            failures: 0,
+            done: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.done {
+                    return None;
+                }
+
+                let Some(body) = state.body.as_mut() else {
+                    match state.connect().await {
+                        Ok(body) => state.body = Some(body),
+                        Err(e) => {
+                            if let Some(error) = state.disconnected(e).await {
+                                return Some((Err(error), state));
+                            }
+                        }
+                    }
+                    continue;
+                };
+
+                match body.next().await {
+                    Some(Ok(chunk)) => {
+                        state.failures = 0;
+                        let events = state.parser.feed_bytes(&chunk);
+                        state.pending.extend(events);
+                    }
+                    Some(Err(e)) => {
+                        if let Some(error) = state.disconnected(e).await {
+                            return Some((Err(error), state));
+                        }
+                    }
+                    None => {
+                        let closed = Error::http(format!("Event stream {} closed", state.url));
+                        if let Some(error) = state.disconnected(closed).await {
+                            return Some((Err(error), state));
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl<'a> SseState<'a> {
+    async fn connect(&self) -> Result<BoxStream<'a, Result<Bytes>>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+        if let Some(id) = self.parser.last_event_id()
+            && let Ok(value) = HeaderValue::from_str(id)
+        {
+            headers.insert("Last-Event-ID", value);
+        }
+
+        let mut retries = 0;
+        let response = self
+            .client
+            .send(Method::GET, &self.url, headers, None, None, &mut retries)
+            .await?;
+        if !response.status().is_success() {
+            return Err(Error::http(format!(
+                "Event stream {} failed with status {}",
+                self.url,
+                response.status().as_u16()
+            )));
+        }
+        Ok(response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(|e| Error::http(format!("Event stream error: {}", e))))
+            .boxed())
+    }
+
+    /// Wait before reconnecting, or give up and return the final error
+    async fn disconnected(&mut self, error: Error) -> Option<Error> {
+        self.body = None;
+        self.parser.reset();
+        self.failures += 1;
+        if self.failures > self.reconnect.max_retries {
+            self.done = true;
+            return Some(error);
+        }
+        let delay = self
+            .parser
+            .retry()
+            .unwrap_or_else(|| self.reconnect.calculate_backoff(self.failures));
+        warn!(
+            "Event stream {} disconnected ({}), reconnecting in {:?}",
+            self.url, error, delay
+        );
+        tokio::time::sleep(delay).await;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parser_handles_split_chunks_and_fields() {
+        // Test: Events split across chunks are assembled with id, type, and multi-line data
+        let mut parser = SseParser::new();
+        assert!(parser.feed(": keep-alive\nid: 7\nevent: cha").is_empty());
+        let events = parser.feed("nge\ndata: a\ndata: b\nretry: 250\n\ndata: c\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "change");
+        assert_eq!(events[0].data, "a\nb");
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[1].event, "message");
+        assert_eq!(parser.retry(), Some(Duration::from_millis(250)));
+
+        let bytes = "data: caf\u{e9}\n\n".as_bytes();
+        let split = bytes.len() - 3;
+        assert!(parser.feed_bytes(&bytes[..split]).is_empty());
+        let events = parser.feed_bytes(&bytes[split..]);
+        assert_eq!(
+            events[0].data, "caf\u{e9}",
+            "Split characters should survive"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscription_reconnects_with_last_event_id() {
+        // Test: After the stream closes, the client reconnects and resumes from the last id
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("Last-Event-ID", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "text/event-stream")
+                    .set_body_string("id: 2\ndata: second\n\n"),
+            )
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "text/event-stream")
+                    .set_body_string("id: 1\ndata: first\n\n"),
+            )
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let reconnect = RetryConfig {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        let events: Vec<SseEvent> = client
+            .subscribe_sse(&format!("{}/changes", server.uri()), reconnect)
+            .take(2)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(events[0].data, "first");
+        assert_eq!(events[1].data, "second");
+    }
+
+    #[tokio::test]
+    async fn test_subscription_ignores_total_timeout() {
+        // Test: A stream outliving the client's total timeout is not cut off
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "text/event-stream")
+                    .set_body_string("id: 1\ndata: late\n\n")
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig {
+            timeout: Duration::from_millis(100),
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let reconnect = RetryConfig {
+            max_retries: 0,
+            ..RetryConfig::default()
+        };
+        let events: Vec<Result<SseEvent>> = client
+            .subscribe_sse(&format!("{}/changes", server.uri()), reconnect)
+            .take(1)
+            .collect()
+            .await;
+
+        assert_eq!(
+            events[0].as_ref().unwrap().data,
+            "late",
+            "Slow stream should still deliver"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscription_gives_up_after_max_retries() {
+        // Test: Consecutive connection failures end the stream with an error
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let client = APIClient::new(HttpClientConfig::default()).unwrap();
+        let reconnect = RetryConfig {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        let results: Vec<Result<SseEvent>> = client
+            .subscribe_sse(&format!("{}/changes", server.uri()), reconnect)
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+}
@@ -0,0 +1,462 @@
+//! Multipart and resumable uploads
+//!
+//! Resumable uploads use the `Content-Range` protocol spoken by most object
+//! stores: each chunk is sent with `Content-Range: bytes start-end/total`,
+//! intermediate chunks are acknowledged with `308` and a `Range` header, and
+//! the committed offset can be queried with `Content-Range: bytes */total`.
+
+use super::client::{APIClient, ApiResponse, host_of};
+use crate::error::{Error, Result};
+use reqwest::Method;
+use reqwest::header::{CONTENT_RANGE, CONTENT_TYPE, HeaderMap, HeaderValue, RANGE};
+use std::path::Path;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::warn;
+
+/// A single part of a multipart form
+#[derive(Debug, Clone)]
+pub struct MultipartPart {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// A `multipart/form-data` request body
+#[derive(Debug, Clone)]
+pub struct MultipartForm {
+    boundary: String,
+    parts: Vec<MultipartPart>,
+}
+
+impl Default for MultipartForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultipartForm {
+    /// Create an empty form with a random boundary
+    pub fn new() -> Self {
+        Self {
+            boundary: format!(
+                "----common-library-{}",
+                crate::utils::crypto::generate_uuid().simple()
+            ),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Add a text field
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.parts.push(MultipartPart {
+            name: name.into(),
+            filename: None,
+            content_type: None,
+            data: value.into().into_bytes(),
+        });
+        self
+    }
+
+    /// Add a file field
+    pub fn file(
+        mut self,
+        name: impl Into<String>,
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> Self {
+        self.parts.push(MultipartPart {
+            name: name.into(),
+            filename: Some(filename.into()),
+            content_type: Some(content_type.into()),
+            data,
+        });
+        self
+    }
+
+    /// Value for the `Content-Type` header
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Encode the form body
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for part in &self.parts {
+            body.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            let mut disposition = format!(
+                "Content-Disposition: form-data; name=\"{}\"",
+                escape_quoted(&part.name)
+            );
+            if let Some(filename) = &part.filename {
+                disposition.push_str(&format!("; filename=\"{}\"", escape_quoted(filename)));
+            }
+            body.extend_from_slice(disposition.as_bytes());
+            body.extend_from_slice(b"\r\n");
+            if let Some(content_type) = &part.content_type {
+                body.extend_from_slice(format!("Content-Type: {}\r\n", content_type).as_bytes());
+            }
+            body.extend_from_slice(b"\r\n");
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body
+    }
+}
+
+/// Resumable upload configuration
+#[derive(Debug, Clone)]
+pub struct ResumableUploadConfig {
+    /// Bytes sent per request
+    pub chunk_size: usize,
+    /// How many times a single chunk is retried after the client's own retries fail
+    pub max_chunk_retries: u32,
+}
+
+impl Default for ResumableUploadConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 8 * 1024 * 1024,
+            max_chunk_retries: 3,
+        }
+    }
+}
+
+impl APIClient {
+    /// POST a multipart form
+    pub async fn upload_multipart(&self, url: &str, form: &MultipartForm) -> Result<ApiResponse> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_str(&form.content_type())
+                .map_err(|e| Error::http(format!("Invalid content type: {}", e)))?,
+        );
+        let response = self
+            .execute(Method::POST, url, headers, Some(form.to_bytes()))
+            .await?;
+        if !response.is_success() {
+            return Err(Error::http(format!(
+                "Multipart upload to {} failed with status {}",
+                url, response.status
+            )));
+        }
+        Ok(response)
+    }
+
+    /// Query how many bytes of a resumable upload the server has committed
+    ///
+    /// Returns `None` when the server reports the upload as already complete.
+    pub async fn upload_offset(&self, url: &str, total: u64) -> Result<Option<u64>> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_RANGE, range_header(&format!("bytes */{}", total))?);
+        let response = self
+            .execute(Method::PUT, url, headers, Some(Vec::new()))
+            .await?;
+
+        match response.status {
+            200 | 201 => Ok(None),
+            308 => Ok(Some(committed_offset(&response))),
+            status => Err(Error::http(format!(
+                "Upload status query for {} failed with status {}",
+                url, status
+            ))),
+        }
+    }
+
+    /// Upload a file in chunks, resuming from the server's committed offset
+    ///
+    /// Failed chunks are retried individually after re-querying the
+    /// committed offset, so an interrupted upload never restarts from zero.
+    pub async fn upload_resumable(
+        &self,
+        url: &str,
+        path: impl AsRef<Path>,
+        config: &ResumableUploadConfig,
+    ) -> Result<ApiResponse> {
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::open(path).await?;
+        let total = file.metadata().await?.len();
+        let chunk_size = config.chunk_size.max(1) as u64;
+        let retry = self.config().retry_for(&host_of(url)?);
+
+        let mut offset = match self.upload_offset(url, total).await? {
+            Some(offset) => offset,
+            None => return self.completed_upload(url, total).await,
+        };
+        let mut chunk_failures = 0;
+
+        loop {
+            let end = (offset + chunk_size).min(total);
+            let mut chunk = vec![0u8; (end - offset) as usize];
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            file.read_exact(&mut chunk).await?;
+
+            let mut headers = HeaderMap::new();
+            let content_range = if total == 0 {
+                "bytes */0".to_string()
+            } else {
+                format!("bytes {}-{}/{}", offset, end - 1, total)
+            };
+            headers.insert(CONTENT_RANGE, range_header(&content_range)?);
+
+            // A 308 only counts as success if the server committed more bytes
+            let failure = match self.execute(Method::PUT, url, headers, Some(chunk)).await {
+                Ok(response) if response.is_success() => return Ok(response),
+                Ok(response) if response.status == 308 && committed_offset(&response) > offset => {
+                    offset = committed_offset(&response);
+                    chunk_failures = 0;
+                    continue;
+                }
+                failure => failure,
+            };
+
+            chunk_failures += 1;
+            if chunk_failures > config.max_chunk_retries {
+                return Err(match failure {
+                    Err(e) => e,
+                    Ok(response) if response.status == 308 => Error::http(format!(
+                        "Chunk upload to {} made no progress past offset {}",
+                        url, offset
+                    )),
+                    Ok(response) => Error::http(format!(
+                        "Chunk upload to {} failed with status {}",
+                        url, response.status
+                    )),
+                });
+            }
+            warn!(
+                "Chunk at offset {} of {} failed, resuming (attempt {})",
+                offset, url, chunk_failures
+            );
+            tokio::time::sleep(retry.calculate_backoff(chunk_failures)).await;
+            offset = match self.upload_offset(url, total).await? {
+                Some(offset) => offset,
+                None => return self.completed_upload(url, total).await,
+            };
+        }
+    }
+
+    async fn completed_upload(&self, url: &str, total: u64) -> Result<ApiResponse> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_RANGE, range_header(&format!("bytes */{}", total))?);
+        self.execute(Method::PUT, url, headers, Some(Vec::new()))
+            .await
+    }
+}
+
+/// Parse the committed offset from a `Range: bytes=0-N` header
+fn committed_offset(response: &ApiResponse) -> u64 {
+    response
+        .header(RANGE.as_str())
+        .and_then(|range| range.trim().strip_prefix("bytes="))
+        .and_then(|range| range.split('-').nth(1))
+        .and_then(|end| end.trim().parse::<u64>().ok())
+        .map_or(0, |end| end + 1)
+}
+
+/// Percent-encode `"`, CR, and LF in a quoted header parameter, as browsers do
+fn escape_quoted(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn range_header(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| Error::http(format!("Invalid header value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::HttpClientConfig;
+    use crate::http::retry::RetryConfig;
+    use std::time::Duration;
+    use wiremock::matchers::{body_string, body_string_contains, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn test_client() -> APIClient {
+        APIClient::new(HttpClientConfig {
+            retry: RetryConfig {
+                max_retries: 0,
+                initial_backoff: Duration::from_millis(1),
+                ..RetryConfig::default()
+            },
+            ..HttpClientConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_multipart_encoding() {
+        // Test: Parts are encoded with boundaries and dispositions
+        let form = MultipartForm::new().text("kind", "report").file(
+            "file",
+            "report.json",
+            "application/json",
+            b"{}".to_vec(),
+        );
+        let body = String::from_utf8(form.to_bytes()).unwrap();
+        assert!(body.contains("name=\"kind\"\r\n\r\nreport\r\n"));
+        assert!(body.contains("filename=\"report.json\""));
+        assert!(body.contains("Content-Type: application/json\r\n\r\n{}"));
+        assert!(body.trim_end().ends_with("--"), "Body should be terminated");
+
+        let form = MultipartForm::new().file(
+            "a\"b",
+            "evil\"\r\nX-Injected: 1.txt",
+            "text/plain",
+            Vec::new(),
+        );
+        let body = String::from_utf8(form.to_bytes()).unwrap();
+        assert!(body.contains("name=\"a%22b\"; filename=\"evil%22%0D%0AX-Injected: 1.txt\""));
+        assert!(
+            !body.contains("\r\nX-Injected"),
+            "Names must not inject header lines"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_multipart() {
+        // Test: Multipart uploads are sent with the form content type
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("filename=\"data.csv\""))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let form = MultipartForm::new().file("file", "data.csv", "text/csv", b"a,b".to_vec());
+        let response = test_client()
+            .upload_multipart(&format!("{}/upload", server.uri()), &form)
+            .await
+            .unwrap();
+        assert_eq!(response.status, 201);
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload_resumes_and_retries_chunks() {
+        // Test: Upload resumes at the committed offset and retries a failed chunk
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(header("Content-Range", "bytes */11"))
+            .respond_with(ResponseTemplate::new(308).insert_header("Range", "bytes=0-4"))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(header("Content-Range", "bytes 5-8/11"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(header("Content-Range", "bytes 5-8/11"))
+            .and(body_string(" wor"))
+            .respond_with(ResponseTemplate::new(308).insert_header("Range", "bytes=0-8"))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(header("Content-Range", "bytes 9-10/11"))
+            .and(body_string("ld"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("done"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let path = std::env::temp_dir().join(format!(
+            "upload-{}.txt",
+            crate::utils::crypto::generate_uuid()
+        ));
+        std::fs::write(&path, "hello world").unwrap();
+
+        let config = ResumableUploadConfig {
+            chunk_size: 4,
+            max_chunk_retries: 2,
+        };
+        let response = test_client()
+            .upload_resumable(&format!("{}/session", server.uri()), &path, &config)
+            .await
+            .unwrap();
+        assert_eq!(response.text().unwrap(), "done");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload_fails_without_progress() {
+        // Test: 308 responses that never advance the offset exhaust the chunk retries
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(308).insert_header("Range", "bytes=0-4"))
+            .mount(&server)
+            .await;
+
+        let path = std::env::temp_dir().join(format!(
+            "upload-{}.txt",
+            crate::utils::crypto::generate_uuid()
+        ));
+        std::fs::write(&path, "hello world").unwrap();
+
+        let config = ResumableUploadConfig {
+            chunk_size: 4,
+            max_chunk_retries: 2,
+        };
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            test_client().upload_resumable(&format!("{}/session", server.uri()), &path, &config),
+        )
+        .await
+        .expect("Upload should not loop forever");
+        assert!(result.is_err(), "Stalled upload should fail");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload_backs_off_with_host_retry() {
+        // Test: Chunk retries wait for the host's backoff, not the default one
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(308).insert_header("Range", "bytes=0-4"))
+            .mount(&server)
+            .await;
+
+        let path = std::env::temp_dir().join(format!(
+            "upload-{}.txt",
+            crate::utils::crypto::generate_uuid()
+        ));
+        std::fs::write(&path, "hello world").unwrap();
+
+        let fast = RetryConfig {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryConfig::default()
+        };
+        let client = APIClient::new(
+            HttpClientConfig {
+                retry: RetryConfig {
+                    max_retries: 0,
+                    initial_backoff: Duration::from_secs(60),
+                    ..RetryConfig::default()
+                },
+                ..HttpClientConfig::default()
+            }
+            .with_host_retry("127.0.0.1", fast),
+        )
+        .unwrap();
+        let config = ResumableUploadConfig {
+            chunk_size: 4,
+            max_chunk_retries: 2,
+        };
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            client.upload_resumable(&format!("{}/session", server.uri()), &path, &config),
+        )
+        .await
+        .expect("The default 60s backoff should not be used for this host");
+        assert!(result.is_err(), "Stalled upload should fail");
+        std::fs::remove_file(path).unwrap();
+    }
+}
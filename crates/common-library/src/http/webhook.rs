@@ -0,0 +1,352 @@
+//! Embeddable webhook receiver
+//!
+//! A [`WebhookServer`] accepts `POST /webhooks/{source}` deliveries from
+//! registries and GitHub, verifies their HMAC-SHA256 signatures, and pushes
+//! the decoded events into a channel that collectors consume for
+//! near-real-time updates.
+
+use super::auth::REDACTED;
+use crate::error::{Error, Result};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// How a webhook source signs its deliveries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureScheme {
+    /// Header carrying the signature
+    pub signature_header: String,
+    /// Prefix before the hex digest, e.g. `sha256=`
+    pub prefix: String,
+    /// Header naming the event type
+    pub event_header: Option<String>,
+    /// Header carrying a unique delivery ID
+    pub delivery_header: Option<String>,
+}
+
+impl SignatureScheme {
+    /// GitHub's `X-Hub-Signature-256` scheme
+    pub fn github() -> Self {
+        Self {
+            signature_header: "x-hub-signature-256".to_string(),
+            prefix: "sha256=".to_string(),
+            event_header: Some("x-github-event".to_string()),
+            delivery_header: Some("x-github-delivery".to_string()),
+        }
+    }
+
+    /// npm hooks' `x-npm-signature` scheme
+    pub fn npm() -> Self {
+        Self {
+            signature_header: "x-npm-signature".to_string(),
+            prefix: "sha256=".to_string(),
+            event_header: Some("x-npm-event".to_string()),
+            delivery_header: None,
+        }
+    }
+}
+
+/// A registered webhook source
+#[derive(Clone)]
+pub struct WebhookSource {
+    pub secret: String,
+    pub scheme: SignatureScheme,
+}
+
+impl fmt::Debug for WebhookSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookSource")
+            .field("secret", &REDACTED)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// Webhook server configuration; `Debug` redacts source secrets
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub bind: SocketAddr,
+    /// Sources keyed by the `{source}` path segment
+    pub sources: HashMap<String, WebhookSource>,
+    /// Events buffered before deliveries are rejected with 503
+    pub channel_capacity: usize,
+}
+
+impl WebhookConfig {
+    /// Listen on `bind` with no sources registered
+    pub fn new(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            sources: HashMap::new(),
+            channel_capacity: 1024,
+        }
+    }
+
+    /// Register a source served at `/webhooks/{name}`
+    pub fn with_source(
+        mut self,
+        name: impl Into<String>,
+        secret: impl Into<String>,
+        scheme: SignatureScheme,
+    ) -> Self {
+        self.sources.insert(
+            name.into(),
+            WebhookSource {
+                secret: secret.into(),
+                scheme,
+            },
+        );
+        self
+    }
+}
+
+/// A verified webhook delivery
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookEvent {
+    pub source: String,
+    pub event_type: Option<String>,
+    pub delivery_id: Option<String>,
+    pub payload: serde_json::Value,
+    pub received_at: DateTime<Utc>,
+}
+
+struct ServerState {
+    sources: HashMap<String, WebhookSource>,
+    events: mpsc::Sender<WebhookEvent>,
+}
+
+/// A running webhook server
+#[derive(Debug)]
+pub struct WebhookServer {
+    local_addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl WebhookServer {
+    /// Bind and start serving, returning the server and its event channel
+    pub async fn start(config: WebhookConfig) -> Result<(Self, mpsc::Receiver<WebhookEvent>)> {
+        let (events, receiver) = mpsc::channel(config.channel_capacity.max(1));
+        let state = Arc::new(ServerState {
+            sources: config.sources,
+            events,
+        });
+        let app = Router::new()
+            .route("/webhooks/{source}", post(receive))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind(config.bind).await?;
+        let local_addr = listener.local_addr()?;
+        let (shutdown, stop) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let server = axum::serve(listener, app).with_graceful_shutdown(async {
+                let _ = stop.await;
+            });
+            if let Err(e) = server.await {
+                warn!("Webhook server failed: {}", e);
+            }
+        });
+
+        Ok((
+            Self {
+                local_addr,
+                shutdown: Some(shutdown),
+                task,
+            },
+            receiver,
+        ))
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop accepting deliveries and wait for in-flight requests
+    pub async fn shutdown(mut self) -> Result<()> {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        (&mut self.task)
+            .await
+            .map_err(|e| Error::http(format!("Webhook server task failed: {}", e)))
+    }
+}
+
+impl Drop for WebhookServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn receive(
+    State(state): State<Arc<ServerState>>,
+    Path(source): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let Some(config) = state.sources.get(&source) else {
+        return StatusCode::NOT_FOUND;
+    };
+    let signature = header(&headers, &config.scheme.signature_header);
+    if !verify_signature(&config.secret, &config.scheme.prefix, signature, &body) {
+        warn!("Rejected webhook from {} with invalid signature", source);
+        return StatusCode::UNAUTHORIZED;
+    }
+    let Ok(payload) = serde_json::from_slice(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    let scheme = &config.scheme;
+    let event = WebhookEvent {
+        source: source.clone(),
+        event_type: scheme
+            .event_header
+            .as_deref()
+            .and_then(|name| header(&headers, name))
+            .map(str::to_string),
+        delivery_id: scheme
+            .delivery_header
+            .as_deref()
+            .and_then(|name| header(&headers, name))
+            .map(str::to_string),
+        payload,
+        received_at: Utc::now(),
+    };
+    match state.events.try_send(event) {
+        Ok(()) => {
+            debug!("Accepted webhook from {}", source);
+            StatusCode::ACCEPTED
+        }
+        Err(mpsc::error::TrySendError::Full(_)) => StatusCode::SERVICE_UNAVAILABLE,
+        Err(mpsc::error::TrySendError::Closed(_)) => StatusCode::GONE,
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Check an HMAC-SHA256 signature of `body` in constant time
+pub fn verify_signature(secret: &str, prefix: &str, signature: Option<&str>, body: &[u8]) -> bool {
+    let Some(digest) = signature.and_then(|s| s.strip_prefix(prefix)) else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(digest) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // Test: Only signatures made with the shared secret are accepted
+        let body = br#"{"action":"published"}"#;
+        let signature = sign("topsecret", body);
+        assert!(verify_signature(
+            "topsecret",
+            "sha256=",
+            Some(&signature),
+            body
+        ));
+        assert!(!verify_signature(
+            "other",
+            "sha256=",
+            Some(&signature),
+            body
+        ));
+        assert!(!verify_signature("topsecret", "sha256=", None, body));
+    }
+
+    #[test]
+    fn test_debug_output_redacts_secrets() {
+        // Test: Source secrets never appear in the Debug output of a config
+        let config = WebhookConfig::new("127.0.0.1:0".parse().unwrap()).with_source(
+            "github",
+            "topsecret",
+            SignatureScheme::github(),
+        );
+        let output = format!("{:?}", config);
+        assert!(!output.contains("topsecret"), "Secret leaked: {}", output);
+        assert!(output.contains(REDACTED));
+        assert!(output.contains("x-hub-signature-256"));
+    }
+
+    #[tokio::test]
+    async fn test_server_delivers_verified_events() {
+        // Test: Signed deliveries reach the channel; unsigned and unknown ones are rejected
+        let config = WebhookConfig::new("127.0.0.1:0".parse().unwrap()).with_source(
+            "github",
+            "topsecret",
+            SignatureScheme::github(),
+        );
+        let (server, mut events) = WebhookServer::start(config).await.unwrap();
+        let url = format!("http://{}/webhooks/github", server.local_addr());
+        let client = reqwest::Client::new();
+        let body = br#"{"action":"published"}"#.to_vec();
+
+        let accepted = client
+            .post(&url)
+            .header("X-Hub-Signature-256", sign("topsecret", &body))
+            .header("X-GitHub-Event", "release")
+            .header("X-GitHub-Delivery", "abc-123")
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(accepted.status().as_u16(), 202);
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.source, "github");
+        assert_eq!(event.event_type.as_deref(), Some("release"));
+        assert_eq!(event.delivery_id.as_deref(), Some("abc-123"));
+        assert_eq!(event.payload["action"], "published");
+
+        let forged = client
+            .post(&url)
+            .header("X-Hub-Signature-256", sign("guess", &body))
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(forged.status().as_u16(), 401);
+
+        let unknown = client
+            .post(format!("http://{}/webhooks/pypi", server.local_addr()))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unknown.status().as_u16(), 404);
+
+        server.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+//! WebSocket client with automatic reconnection
+//!
+//! A [`WebSocketClient`] keeps a subscription alive: when the connection
+//! drops it reconnects with backoff and replays the handler's subscription
+//! messages, so collectors can consume streaming feeds instead of polling.
+
+use super::retry::RetryConfig;
+use crate::error::{Error, Result};
+use futures::{SinkExt, StreamExt};
+use std::ops::ControlFlow;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+/// A message received from or sent to a WebSocket server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl From<WsMessage> for Message {
+    fn from(message: WsMessage) -> Self {
+        match message {
+            WsMessage::Text(text) => Message::text(text),
+            WsMessage::Binary(data) => Message::binary(data),
+        }
+    }
+}
+
+/// Handles messages of a WebSocket subscription
+pub trait MessageHandler: Send {
+    /// Messages sent after every (re)connect, e.g. subscription requests
+    fn on_connect(&mut self) -> Vec<WsMessage> {
+        Vec::new()
+    }
+
+    /// Handle a message; returning `Break` closes the connection
+    fn on_message(&mut self, message: WsMessage) -> ControlFlow<()>;
+}
+
+/// WebSocket client configuration
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    /// Backoff between reconnects; `max_retries` bounds consecutive failures
+    pub reconnect: RetryConfig,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            reconnect: RetryConfig {
+                max_retries: 10,
+                ..RetryConfig::default()
+            },
+        }
+    }
+}
+
+/// Reconnecting WebSocket client
+#[derive(Debug, Clone)]
+pub struct WebSocketClient {
+    url: String,
+    config: WebSocketConfig,
+}
+
+impl WebSocketClient {
+    /// Create a client for a `ws://` or `wss://` URL
+    pub fn new(url: impl Into<String>, config: WebSocketConfig) -> Self {
+        Self {
+            url: url.into(),
+            config,
+        }
+    }
+
+    /// Run the subscription until the handler stops it
+    ///
+    /// Returns an error once `max_retries` consecutive connection attempts
+    /// have failed.
+    pub async fn run<H: MessageHandler>(&self, handler: &mut H) -> Result<()> {
+        let mut failures = 0;
+        loop {
+            match self.session(handler, &mut failures).await {
+                Ok(ControlFlow::Break(())) => return Ok(()),
+                Ok(ControlFlow::Continue(())) => {
+                    warn!("WebSocket {} closed by server", self.url);
+                }
+                Err(e) => warn!("WebSocket {} failed: {}", self.url, e),
+            }
+
+            failures += 1;
+            if failures > self.config.reconnect.max_retries {
+                return Err(Error::http(format!(
+                    "WebSocket {} failed after {} reconnect attempts",
+                    self.url, self.config.reconnect.max_retries
+                )));
+            }
+            let delay = self.config.reconnect.calculate_backoff(failures);
+            info!("Reconnecting to {} in {:?}", self.url, delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Run one connection until it closes or the handler stops
+    async fn session<H: MessageHandler>(
+        &self,
+        handler: &mut H,
+        failures: &mut u32,
+    ) -> Result<ControlFlow<()>> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.url.as_str())
+            .await
+            .map_err(|e| Error::http(format!("WebSocket connect failed: {}", e)))?;
+        let send_error = |e| Error::http(format!("WebSocket send failed: {}", e));
+        for message in handler.on_connect() {
+            socket.send(message.into()).await.map_err(send_error)?;
+        }
+
+        while let Some(message) = socket.next().await {
+            let message = match message
+                .map_err(|e| Error::http(format!("WebSocket receive failed: {}", e)))?
+            {
+                Message::Text(text) => WsMessage::Text(text.to_string()),
+                Message::Binary(data) => WsMessage::Binary(data.to_vec()),
+                Message::Ping(data) => {
+                    socket.send(Message::Pong(data)).await.map_err(send_error)?;
+                    continue;
+                }
+                Message::Close(_) => break,
+                Message::Pong(_) | Message::Frame(_) => continue,
+            };
+            *failures = 0;
+            if handler.on_message(message).is_break() {
+                let _ = socket.close(None).await;
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct Collect {
+        messages: Vec<String>,
+        limit: usize,
+    }
+
+    impl MessageHandler for Collect {
+        fn on_connect(&mut self) -> Vec<WsMessage> {
+            vec![WsMessage::Text("subscribe".to_string())]
+        }
+
+        fn on_message(&mut self, message: WsMessage) -> ControlFlow<()> {
+            if let WsMessage::Text(text) = message {
+                self.messages.push(text);
+            }
+            if self.messages.len() >= self.limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    /// Each connection echoes the subscription message once, then closes
+    async fn echo_once_server() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connection = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                connection += 1;
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                if let Some(Ok(Message::Text(text))) = socket.next().await {
+                    let reply = format!("{}-{}", text, connection);
+                    socket.send(Message::text(reply)).await.unwrap();
+                }
+                let _ = socket.close(None).await;
+            }
+        });
+        format!("ws://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_and_resubscribes() {
+        // Test: After the server closes, the client reconnects and resends subscriptions
+        let url = echo_once_server().await;
+        let client = WebSocketClient::new(
+            url,
+            WebSocketConfig {
+                reconnect: RetryConfig {
+                    initial_backoff: Duration::from_millis(1),
+                    ..RetryConfig::default()
+                },
+            },
+        );
+        let mut handler = Collect {
+            messages: Vec::new(),
+            limit: 2,
+        };
+
+        client.run(&mut handler).await.unwrap();
+        assert_eq!(handler.messages, vec!["subscribe-1", "subscribe-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_when_server_is_unreachable() {
+        // Test: Connection failures beyond max_retries return an error
+        let client = WebSocketClient::new(
+            "ws://127.0.0.1:9",
+            WebSocketConfig {
+                reconnect: RetryConfig {
+                    max_retries: 1,
+                    initial_backoff: Duration::from_millis(1),
+                    ..RetryConfig::default()
+                },
+            },
+        );
+        let mut handler = Collect {
+            messages: Vec::new(),
+            limit: 1,
+        };
+        assert!(client.run(&mut handler).await.is_err());
+    }
+}
@@ -37,7 +37,11 @@
 
 pub mod config;
 pub mod error;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod logging;
+pub mod metrics;
+pub mod storage;
 pub mod utils;
 
 // Future modules (to be implemented in subsequent phases)
@@ -47,7 +51,9 @@ pub mod utils;
 pub mod prelude {
     pub use crate::config::ConfigManager;
     pub use crate::error::{Error, Result};
-    pub use crate::logging::Logger;
+    #[cfg(feature = "http")]
+    pub use crate::http::{APIClient, HttpClientConfig};
+    pub use crate::logging::{CorrelationId, Logger};
     pub use crate::utils::*;
 
     // Future re-exports will be added in subsequent phases
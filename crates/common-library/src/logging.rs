@@ -1,8 +1,65 @@
 //! Logging functionality for the common library
+//!
+//! A [`CorrelationId`] scopes one logical operation, such as a collection
+//! job: it is included in every [`Logger`] message and sent as a header on
+//! every HTTP request made within the scope.
 
-use tracing::{info, warn, error, debug, Level};
+use tracing::{info, warn, error, debug, Level, Instrument};
 use tracing_subscriber::{fmt, EnvFilter, Registry, prelude::*};
 use crate::error::{Error, Result};
+use std::fmt as std_fmt;
+use std::future::Future;
+
+tokio::task_local! {
+    static CORRELATION_ID: CorrelationId;
+}
+
+/// Identifier shared by all logs and requests of one logical operation
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    /// Generate a new random correlation ID
+    pub fn new() -> Self {
+        Self(crate::utils::crypto::generate_uuid_string())
+    }
+
+    /// Use an existing ID, e.g. one received from an upstream caller
+    pub fn from_string(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// The ID as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The ID of the enclosing [`CorrelationId::scope`], if any
+    pub fn current() -> Option<Self> {
+        CORRELATION_ID.try_with(|id| id.clone()).ok()
+    }
+
+    /// Run `future` with this ID as the current correlation ID
+    ///
+    /// The future also runs inside a tracing span carrying the ID, so
+    /// structured log output includes it.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        let span = tracing::info_span!("operation", correlation_id = %self.0);
+        CORRELATION_ID.scope(self, future.instrument(span)).await
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std_fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut std_fmt::Formatter<'_>) -> std_fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 /// Logger configuration
 #[derive(Debug, Clone)]
@@ -70,7 +127,7 @@ pub fn init_with_config(config: LoggerConfig) -> Result<()> {
                 .create(true)
                 .append(true)
                 .open(&path)
-                .map_err(|e| Error::Io(e))?;
+                .map_err(Error::Io)?;
 
             let fmt_layer = match config.format {
                 LogFormat::Json => fmt::layer().json().with_writer(file).boxed(),
@@ -85,8 +142,12 @@ pub fn init_with_config(config: LoggerConfig) -> Result<()> {
 }
 
 /// Logger struct for structured logging
+///
+/// Messages are prefixed with the target and, when set or in scope, the
+/// correlation ID.
 pub struct Logger {
     target: String,
+    correlation_id: Option<CorrelationId>,
 }
 
 impl Logger {
@@ -94,59 +155,78 @@ impl Logger {
     pub fn new(target: impl Into<String>) -> Self {
         Self {
             target: target.into(),
+            correlation_id: None,
+        }
+    }
+
+    /// Tag messages with a fixed correlation ID instead of the one in scope
+    pub fn with_correlation_id(mut self, id: CorrelationId) -> Self {
+        self.correlation_id = Some(id);
+        self
+    }
+
+    /// The correlation ID included in messages, if any
+    pub fn correlation_id(&self) -> Option<CorrelationId> {
+        self.correlation_id.clone().or_else(CorrelationId::current)
+    }
+
+    fn prefix(&self) -> String {
+        match self.correlation_id() {
+            Some(id) => format!("[{}] [{}]", self.target, id),
+            None => format!("[{}]", self.target),
         }
     }
 
     /// Log an info message
     pub fn info(&self, message: &str) {
-        info!("[{}] {}", self.target, message);
+        info!("{} {}", self.prefix(), message);
     }
 
     /// Log an info message with fields
     pub fn info_with_fields(&self, message: &str, _fields: &[(&str, &str)]) {
         // Simplified implementation - just log the message
-        info!("[{}] {}", self.target, message);
+        info!("{} {}", self.prefix(), message);
     }
 
     /// Log a warning message
     pub fn warn(&self, message: &str) {
-        warn!("[{}] {}", self.target, message);
+        warn!("{} {}", self.prefix(), message);
     }
 
     /// Log a warning message with fields
     pub fn warn_with_fields(&self, message: &str, _fields: &[(&str, &str)]) {
         // Simplified implementation - just log the message
-        warn!("[{}] {}", self.target, message);
+        warn!("{} {}", self.prefix(), message);
     }
 
     /// Log an error message
     pub fn error(&self, message: &str) {
-        error!("[{}] {}", self.target, message);
+        error!("{} {}", self.prefix(), message);
     }
 
     /// Log an error message with fields
     pub fn error_with_fields(&self, message: &str, _fields: &[(&str, &str)]) {
         // Simplified implementation - just log the message
-        error!("[{}] {}", self.target, message);
+        error!("{} {}", self.prefix(), message);
     }
 
     /// Log a debug message
     pub fn debug(&self, message: &str) {
-        debug!("[{}] {}", self.target, message);
+        debug!("{} {}", self.prefix(), message);
     }
 
     /// Log a debug message with fields
     pub fn debug_with_fields(&self, message: &str, _fields: &[(&str, &str)]) {
         // Simplified implementation - just log the message
-        debug!("[{}] {}", self.target, message);
+        debug!("{} {}", self.prefix(), message);
     }
 
     /// Log performance metrics
     pub fn log_performance(&self, operation: &str, duration: std::time::Duration) {
         let duration_ms = duration.as_millis();
         info!(
-            "[{}] Performance: {} completed in {}ms",
-            self.target, operation, duration_ms
+            "{} Performance: {} completed in {}ms",
+            self.prefix(), operation, duration_ms
         );
     }
 }
@@ -0,0 +1,260 @@
+//! Autoregressive forecasts
+//!
+//! An [`ArForecaster`] fits an AR(p) model by Yule-Walker estimation to a
+//! series differenced `d` times (an ARIMA(p, d, 0) model), then forecasts
+//! recursively and integrates back to the original scale.
+
+use super::forecasting::{
+    FittedModel, Forecast, ForecastPoint, ForecastResult, ResidualDiagnostics, aic,
+};
+use super::statistical::normal_quantile;
+use crate::error::{Error, Result};
+
+/// Fits ARIMA(p, d, 0) models
+#[derive(Debug, Clone)]
+pub struct ArForecaster {
+    order: usize,
+    differences: usize,
+    confidence: f64,
+}
+
+impl ArForecaster {
+    /// AR(`order`) on the undifferenced series with 95% prediction intervals
+    pub fn new(order: usize) -> Self {
+        Self {
+            order,
+            differences: 0,
+            confidence: 0.95,
+        }
+    }
+
+    /// Difference the series `differences` times before fitting
+    pub fn with_differences(mut self, differences: usize) -> Self {
+        self.differences = differences;
+        self
+    }
+
+    /// Set the prediction interval confidence level, in `(0, 1)`
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Fewest observations the model can be fitted to
+    pub fn min_observations(&self) -> usize {
+        self.order + self.differences + 2
+    }
+
+    /// Fit to `values` and project `horizon` steps past the last one
+    pub fn forecast(&self, values: &[f64], horizon: usize) -> Result<ForecastResult> {
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(Error::metrics("Confidence must be in (0, 1)"));
+        }
+        if values.len() < self.min_observations() {
+            return Err(Error::metrics(format!(
+                "Need at least {} observations, got {}",
+                self.min_observations(),
+                values.len()
+            )));
+        }
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(Error::metrics("Cannot forecast non-finite values"));
+        }
+
+        // levels[k] is the series differenced k times
+        let mut levels = vec![values.to_vec()];
+        for _ in 0..self.differences {
+            let last = levels.last().expect("levels start non-empty");
+            levels.push(last.windows(2).map(|w| w[1] - w[0]).collect());
+        }
+        let series = levels.last().expect("levels start non-empty");
+        let mean = series.iter().sum::<f64>() / series.len() as f64;
+        let coefficients = yule_walker(series, mean, self.order);
+
+        let predict = |history: &[f64]| {
+            mean + coefficients
+                .iter()
+                .enumerate()
+                .map(|(i, phi)| phi * (history[history.len() - 1 - i] - mean))
+                .sum::<f64>()
+        };
+        let residuals: Vec<f64> = (self.order..series.len())
+            .map(|t| series[t] - predict(&series[..t]))
+            .collect();
+        let noise_variance = residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64;
+
+        let mut extended = series.clone();
+        for _ in 0..horizon {
+            let next = predict(&extended);
+            extended.push(next);
+        }
+        let mut projected = extended[series.len()..].to_vec();
+        for level in levels[..self.differences].iter().rev() {
+            let mut running = *level.last().expect("levels are non-empty");
+            for value in projected.iter_mut() {
+                running += *value;
+                *value = running;
+            }
+        }
+
+        let psi = psi_weights(&coefficients, self.differences, horizon);
+        let z = normal_quantile(0.5 + self.confidence / 2.0);
+        let mut cumulative = 0.0;
+        let points = projected
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                cumulative += psi[i] * psi[i];
+                let margin = z * (noise_variance * cumulative).sqrt();
+                ForecastPoint {
+                    step: i + 1,
+                    value,
+                    lower: value - margin,
+                    upper: value + margin,
+                }
+            })
+            .collect();
+
+        Ok(ForecastResult {
+            aic: aic(&residuals, self.order + 1),
+            diagnostics: ResidualDiagnostics::from_residuals(&residuals),
+            model: FittedModel::Autoregressive {
+                differences: self.differences,
+                mean,
+                coefficients,
+            },
+            forecast: Forecast {
+                points,
+                residual_std_dev: noise_variance.sqrt(),
+                residuals,
+                confidence: self.confidence,
+            },
+        })
+    }
+}
+
+/// AR coefficients from sample autocovariances via Levinson-Durbin recursion
+fn yule_walker(series: &[f64], mean: f64, order: usize) -> Vec<f64> {
+    let n = series.len() as f64;
+    let autocovariance: Vec<f64> = (0..=order)
+        .map(|lag| {
+            series[lag..]
+                .iter()
+                .zip(series)
+                .map(|(a, b)| (a - mean) * (b - mean))
+                .sum::<f64>()
+                / n
+        })
+        .collect();
+    let mut phi = vec![0.0; order];
+    if order == 0 || autocovariance[0] <= 0.0 {
+        return phi;
+    }
+
+    let mut error = autocovariance[0];
+    for k in 0..order {
+        let accumulated: f64 = (0..k).map(|j| phi[j] * autocovariance[k - j]).sum();
+        let reflection = (autocovariance[k + 1] - accumulated) / error;
+        let previous = phi.clone();
+        phi[k] = reflection;
+        for j in 0..k {
+            phi[j] = previous[j] - reflection * previous[k - 1 - j];
+        }
+        error *= 1.0 - reflection * reflection;
+        if error <= 0.0 {
+            break;
+        }
+    }
+    phi
+}
+
+/// Moving-average weights of the integrated model, used for interval widths
+fn psi_weights(coefficients: &[f64], differences: usize, horizon: usize) -> Vec<f64> {
+    // Expand (1 - φ1 B - ... - φp B^p)(1 - B)^d into 1 - a1 B - a2 B^2 - ...
+    let mut polynomial = vec![1.0];
+    polynomial.extend(coefficients.iter().map(|phi| -phi));
+    for _ in 0..differences {
+        let mut next = vec![0.0; polynomial.len() + 1];
+        for (i, c) in polynomial.iter().enumerate() {
+            next[i] += c;
+            next[i + 1] -= c;
+        }
+        polynomial = next;
+    }
+
+    let mut psi = vec![1.0];
+    for j in 1..horizon {
+        let weight = (1..polynomial.len().min(j + 1))
+            .map(|k| -polynomial[k] * psi[j - k])
+            .sum();
+        psi.push(weight);
+    }
+    psi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic, roughly normal noise
+    fn noise(count: usize) -> Vec<f64> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                (0..12)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        (state >> 11) as f64 / (1u64 << 53) as f64
+                    })
+                    .sum::<f64>()
+                    - 6.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ar1_recovers_coefficient() {
+        // Test: Yule-Walker recovers the AR(1) coefficient and forecasts decay to the mean
+        let mut values = vec![0.0];
+        for e in noise(2000) {
+            values.push(0.7 * values.last().unwrap() + e);
+        }
+        let result = ArForecaster::new(1).forecast(&values, 20).unwrap();
+        let FittedModel::Autoregressive { coefficients, .. } = &result.model else {
+            panic!("Expected an autoregressive model");
+        };
+        assert!(
+            (coefficients[0] - 0.7).abs() < 0.05,
+            "Estimated phi was {}",
+            coefficients[0]
+        );
+        assert!(result.forecast.points[19].value.abs() < 0.5);
+        assert!(
+            result.diagnostics.ljung_box < 30.0,
+            "Residuals of the true model should look like white noise"
+        );
+    }
+
+    #[test]
+    fn test_differenced_model_follows_trend() {
+        // Test: ARIMA(0, 1, 0) with drift extends a linear trend with growing intervals
+        let values: Vec<f64> = (0..30).map(|i| 3.0 * i as f64).collect();
+        let result = ArForecaster::new(0)
+            .with_differences(1)
+            .forecast(&values, 5)
+            .unwrap();
+        let points = &result.forecast.points;
+        assert!((points[0].value - 90.0).abs() < 1e-9);
+        assert!((points[4].value - 102.0).abs() < 1e-9);
+        assert!(ArForecaster::new(5).forecast(&values[..4], 1).is_err());
+    }
+
+    #[test]
+    fn test_psi_weights_of_random_walk() {
+        // Test: A random walk's forecast variance grows linearly with horizon
+        assert_eq!(psi_weights(&[], 1, 4), vec![1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(psi_weights(&[0.5], 0, 3), vec![1.0, 0.5, 0.25]);
+    }
+}
@@ -0,0 +1,306 @@
+//! Benchmark harness for timing code paths
+//!
+//! A [`Benchmark`] runs a closure, sync or async, through warm-up and
+//! measured iterations, drops timings far from the median and summarizes
+//! the rest. Results saved in a [`BenchmarkBaseline`] let later runs be
+//! checked for regressions from code. Allocations are not counted: that
+//! needs a counting global allocator, which only the final binary can
+//! install.
+
+use super::statistical::StreamingStatistics;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::Instant;
+
+/// Scales a median absolute deviation to a normal standard deviation
+const MAD_TO_STD_DEV: f64 = 1.4826;
+
+/// Iteration counts and outlier rejection for a [`Benchmark`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BenchmarkConfig {
+    /// Untimed iterations run first to warm caches and lazy initialization
+    pub warmup_iterations: usize,
+    pub iterations: usize,
+    /// Timings more than this many robust standard deviations from the median are dropped
+    pub outlier_threshold: f64,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            warmup_iterations: 10,
+            iterations: 100,
+            outlier_threshold: 5.0,
+        }
+    }
+}
+
+/// Timing summary of one benchmark, in nanoseconds per iteration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub name: String,
+    /// Iterations kept after outlier rejection
+    pub iterations: usize,
+    pub outliers: usize,
+    pub mean_ns: f64,
+    pub median_ns: f64,
+    pub std_dev_ns: f64,
+    pub min_ns: f64,
+    pub max_ns: f64,
+    /// Iterations per second at the mean time
+    pub throughput: f64,
+}
+
+/// Runs closures and summarizes their timings
+#[derive(Debug, Clone, Default)]
+pub struct Benchmark {
+    config: BenchmarkConfig,
+}
+
+impl Benchmark {
+    pub fn new(config: BenchmarkConfig) -> Result<Self> {
+        if config.iterations == 0 {
+            return Err(Error::metrics("Benchmark needs at least 1 iteration"));
+        }
+        if !(config.outlier_threshold.is_finite() && config.outlier_threshold > 0.0) {
+            return Err(Error::metrics(
+                "Outlier threshold must be a positive number",
+            ));
+        }
+        Ok(Self { config })
+    }
+
+    pub fn config(&self) -> &BenchmarkConfig {
+        &self.config
+    }
+
+    /// Time `f`, keeping its result alive so the work is not optimized away
+    pub fn run<T>(&self, name: &str, mut f: impl FnMut() -> T) -> Result<BenchmarkResult> {
+        for _ in 0..self.config.warmup_iterations {
+            black_box(f());
+        }
+        let timings = (0..self.config.iterations)
+            .map(|_| {
+                let start = Instant::now();
+                black_box(f());
+                start.elapsed().as_nanos() as f64
+            })
+            .collect();
+        self.summarize(name, timings)
+    }
+
+    /// Time the futures returned by `f`, awaiting each to completion
+    pub async fn run_async<T, F, Fut>(&self, name: &str, mut f: F) -> Result<BenchmarkResult>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        for _ in 0..self.config.warmup_iterations {
+            black_box(f().await);
+        }
+        let mut timings = Vec::with_capacity(self.config.iterations);
+        for _ in 0..self.config.iterations {
+            let start = Instant::now();
+            black_box(f().await);
+            timings.push(start.elapsed().as_nanos() as f64);
+        }
+        self.summarize(name, timings)
+    }
+
+    fn summarize(&self, name: &str, mut timings: Vec<f64>) -> Result<BenchmarkResult> {
+        timings.sort_by(f64::total_cmp);
+        let median = sorted_median(&timings)
+            .ok_or_else(|| Error::metrics(format!("Benchmark {} recorded no timings", name)))?;
+        let mut deviations: Vec<f64> = timings.iter().map(|t| (t - median).abs()).collect();
+        deviations.sort_by(f64::total_cmp);
+        let limit = sorted_median(&deviations).unwrap_or(0.0)
+            * MAD_TO_STD_DEV
+            * self.config.outlier_threshold;
+        let total = timings.len();
+        if limit > 0.0 {
+            timings.retain(|t| (t - median).abs() <= limit);
+        }
+
+        let stats: StreamingStatistics = timings.iter().copied().collect();
+        let mean = stats.mean().unwrap_or(median);
+        Ok(BenchmarkResult {
+            name: name.to_string(),
+            iterations: timings.len(),
+            outliers: total - timings.len(),
+            mean_ns: mean,
+            median_ns: median,
+            std_dev_ns: stats.std_dev().unwrap_or(0.0),
+            min_ns: stats.min().unwrap_or(median),
+            max_ns: stats.max().unwrap_or(median),
+            throughput: if mean > 0.0 {
+                1e9 / mean
+            } else {
+                f64::INFINITY
+            },
+        })
+    }
+}
+
+/// Change of a benchmark's mean time against its baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    pub name: String,
+    pub baseline_mean_ns: f64,
+    pub current_mean_ns: f64,
+    /// Fractional change in mean time, e.g. `0.2` for 20% slower
+    pub change: f64,
+    pub regressed: bool,
+}
+
+/// Stored benchmark results to compare later runs against
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkBaseline {
+    pub results: BTreeMap<String, BenchmarkResult>,
+}
+
+impl BenchmarkBaseline {
+    /// Load a baseline saved with [`save`](Self::save); a missing file is an empty baseline
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read(path).map_err(|e| {
+            Error::storage(format!("Failed to read baseline {}: {}", path.display(), e))
+        })?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        crate::utils::fs::write_file_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Store `result` as the baseline for its name, replacing any earlier one
+    pub fn record(&mut self, result: BenchmarkResult) {
+        self.results.insert(result.name.clone(), result);
+    }
+
+    /// Compare `result` with the baseline of the same name
+    ///
+    /// It counts as regressed when its mean time grew by more than
+    /// `threshold`, e.g. `0.1` for 10%. `None` when there is no baseline.
+    pub fn compare(&self, result: &BenchmarkResult, threshold: f64) -> Option<BenchmarkComparison> {
+        let baseline = self.results.get(&result.name)?;
+        let change = if baseline.mean_ns > 0.0 {
+            result.mean_ns / baseline.mean_ns - 1.0
+        } else {
+            0.0
+        };
+        Some(BenchmarkComparison {
+            name: result.name.clone(),
+            baseline_mean_ns: baseline.mean_ns,
+            current_mean_ns: result.mean_ns,
+            change,
+            regressed: change > threshold,
+        })
+    }
+}
+
+/// Median of sorted values
+fn sorted_median(sorted: &[f64]) -> Option<f64> {
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len.is_multiple_of(2) => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+        _ => Some(sorted[middle]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_rejects_outliers() {
+        // Test: A single slow iteration is dropped and does not skew the mean
+        let benchmark = Benchmark::default();
+        let mut timings: Vec<f64> = (0..99).map(|i| 1000.0 + (i % 10) as f64).collect();
+        timings.push(50_000.0);
+        let result = benchmark.summarize("parse", timings).unwrap();
+
+        assert_eq!(result.outliers, 1, "The 50µs iteration is an outlier");
+        assert_eq!(result.iterations, 99);
+        assert!(
+            (result.mean_ns - 1004.5).abs() < 0.1,
+            "mean was {}",
+            result.mean_ns
+        );
+        assert_eq!(result.max_ns, 1009.0);
+        assert!((result.throughput - 1e9 / result.mean_ns).abs() < 1e-6);
+
+        let constant = benchmark.summarize("noop", vec![5.0; 10]).unwrap();
+        assert_eq!(constant.outliers, 0, "Identical timings have no outliers");
+        assert!(benchmark.summarize("empty", Vec::new()).is_err());
+        assert!(
+            Benchmark::new(BenchmarkConfig {
+                iterations: 0,
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runs_sync_and_async_closures() {
+        // Test: Warm-up iterations run but only measured ones are summarized
+        let benchmark = Benchmark::new(BenchmarkConfig {
+            warmup_iterations: 3,
+            iterations: 20,
+            outlier_threshold: 5.0,
+        })
+        .unwrap();
+        let mut calls = 0;
+        let result = benchmark
+            .run("sum", || {
+                calls += 1;
+                (0..100u64).sum::<u64>()
+            })
+            .unwrap();
+        assert_eq!(calls, 23);
+        assert_eq!(result.iterations + result.outliers, 20);
+
+        let result = benchmark
+            .run_async("yield", || async { tokio::task::yield_now().await })
+            .await
+            .unwrap();
+        assert_eq!(result.iterations + result.outliers, 20);
+        assert!(result.min_ns <= result.median_ns && result.median_ns <= result.max_ns);
+    }
+
+    #[test]
+    fn test_baseline_round_trip_and_regressions() {
+        // Test: Saved baselines reload and flag runs slower than the threshold
+        let dir = std::env::temp_dir().join(format!(
+            "benchmark-baseline-{}",
+            crate::utils::crypto::generate_uuid()
+        ));
+        crate::utils::fs::ensure_dir(&dir).unwrap();
+        let path = dir.join("baseline.json");
+        let benchmark = Benchmark::default();
+
+        assert!(BenchmarkBaseline::load(&path).unwrap().results.is_empty());
+        let mut baseline = BenchmarkBaseline::default();
+        baseline.record(benchmark.summarize("parse", vec![100.0; 10]).unwrap());
+        baseline.save(&path).unwrap();
+        let baseline = BenchmarkBaseline::load(&path).unwrap();
+
+        let slower = benchmark.summarize("parse", vec![125.0; 10]).unwrap();
+        let comparison = baseline.compare(&slower, 0.1).unwrap();
+        assert!((comparison.change - 0.25).abs() < 1e-9);
+        assert!(comparison.regressed, "25% slower exceeds a 10% threshold");
+        let similar = benchmark.summarize("parse", vec![105.0; 10]).unwrap();
+        assert!(!baseline.compare(&similar, 0.1).unwrap().regressed);
+        let unknown = benchmark.summarize("render", vec![1.0; 10]).unwrap();
+        assert!(baseline.compare(&unknown, 0.1).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
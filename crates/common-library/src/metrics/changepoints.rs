@@ -0,0 +1,187 @@
+//! Changepoint detection on metric series
+//!
+//! A maintainer change, a major release or abandonment shifts a metric's
+//! level abruptly; a trend fitted across the shift averages it away.
+//! [`detect_changepoints`] finds such shifts by binary segmentation: the
+//! split that most reduces the squared error around segment means is kept
+//! while the reduction exceeds a penalty, and each side is searched again.
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// An abrupt shift in the mean level of a series
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Changepoint {
+    /// First observation at the new level
+    pub timestamp: DateTime<Utc>,
+    /// Mean of the segment ending before the change
+    pub mean_before: f64,
+    /// Mean of the segment starting at the change
+    pub mean_after: f64,
+}
+
+/// Shifts in mean level of `points`, ordered by timestamp, oldest first
+///
+/// Segments are at least `min_segment` observations long. Without an
+/// explicit `penalty`, a split must reduce the squared error by more than
+/// `2 σ² ln n`, with the noise σ estimated robustly from the differences
+/// between neighbouring values. Non-finite values are rejected.
+pub fn detect_changepoints(
+    points: &[(DateTime<Utc>, f64)],
+    min_segment: usize,
+    penalty: Option<f64>,
+) -> Result<Vec<Changepoint>> {
+    if min_segment == 0 {
+        return Err(Error::metrics("Minimum segment length must be at least 1"));
+    }
+    if let Some(penalty) = penalty
+        && !(penalty.is_finite() && penalty >= 0.0)
+    {
+        return Err(Error::metrics(
+            "Changepoint penalty must be finite and non-negative",
+        ));
+    }
+    if let Some((timestamp, _)) = points.iter().find(|(_, value)| !value.is_finite()) {
+        return Err(Error::metrics(format!("Non-finite value at {}", timestamp)));
+    }
+    let n = points.len();
+    let Some(min_split) = min_segment.checked_mul(2).filter(|length| *length <= n) else {
+        return Ok(Vec::new());
+    };
+
+    let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+    let mut sums = vec![0.0; n + 1];
+    let mut squares = vec![0.0; n + 1];
+    for (i, value) in values.iter().enumerate() {
+        sums[i + 1] = sums[i] + value;
+        squares[i + 1] = squares[i] + value * value;
+    }
+    if !squares[n].is_finite() {
+        return Err(Error::metrics(
+            "Series values are too large for changepoint detection",
+        ));
+    }
+    let cost = |from: usize, to: usize| {
+        let sum = sums[to] - sums[from];
+        (squares[to] - squares[from] - sum * sum / (to - from) as f64).max(0.0)
+    };
+    let penalty = penalty.unwrap_or_else(|| {
+        let sigma = noise_scale(&values);
+        2.0 * sigma * sigma * (n as f64).ln()
+    });
+
+    let mut splits = Vec::new();
+    let mut pending = vec![(0, n)];
+    while let Some((from, to)) = pending.pop() {
+        if to - from < min_split {
+            continue;
+        }
+        let whole = cost(from, to);
+        let best = (from + min_segment..=to - min_segment)
+            .map(|split| (split, whole - cost(from, split) - cost(split, to)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((split, gain)) = best
+            && gain > penalty
+        {
+            splits.push(split);
+            pending.push((from, split));
+            pending.push((split, to));
+        }
+    }
+    splits.sort_unstable();
+
+    let mut bounds = vec![0];
+    bounds.extend(&splits);
+    bounds.push(n);
+    let mean = |from: usize, to: usize| (sums[to] - sums[from]) / (to - from) as f64;
+    Ok(bounds
+        .windows(3)
+        .map(|b| Changepoint {
+            timestamp: points[b[1]].0,
+            mean_before: mean(b[0], b[1]),
+            mean_after: mean(b[1], b[2]),
+        })
+        .collect())
+}
+
+/// Noise standard deviation from the median absolute difference of neighbours
+///
+/// Level shifts affect only one difference each, so they barely move the estimate.
+fn noise_scale(values: &[f64]) -> f64 {
+    let mut differences: Vec<f64> = values.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    if differences.is_empty() {
+        return 0.0;
+    }
+    differences.sort_by(f64::total_cmp);
+    let middle = differences.len() / 2;
+    let median = if differences.len().is_multiple_of(2) {
+        (differences[middle - 1] + differences[middle]) / 2.0
+    } else {
+        differences[middle]
+    };
+    // MAD-to-σ factor, divided by √2 because differences double the variance
+    median / (0.6745 * std::f64::consts::SQRT_2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, TimeZone};
+
+    fn daily(values: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (start + TimeDelta::days(i as i64), *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_level_shifts_with_timestamps() {
+        // Test: Two abrupt shifts in a noisy series are reported at the right days
+        let noise = [0.4, -0.3, 0.1, -0.5, 0.2, 0.3, -0.1, -0.2, 0.5, -0.4];
+        let values: Vec<f64> = (0..90)
+            .map(|i| {
+                let level = match i {
+                    0..30 => 100.0,
+                    30..60 => 20.0,
+                    _ => 60.0,
+                };
+                level + noise[i % noise.len()]
+            })
+            .collect();
+        let points = daily(&values);
+        let changes = detect_changepoints(&points, 5, None).unwrap();
+
+        assert_eq!(changes.len(), 2, "Expected two shifts, got {:?}", changes);
+        assert_eq!(changes[0].timestamp, points[30].0);
+        assert_eq!(changes[1].timestamp, points[60].0);
+        assert!((changes[0].mean_before - 100.0).abs() < 0.5);
+        assert!((changes[0].mean_after - 20.0).abs() < 0.5);
+        assert!((changes[1].mean_after - 60.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_stable_series_and_invalid_settings() {
+        // Test: Noise alone yields no changepoints and bad settings are rejected
+        let values: Vec<f64> = (0..60).map(|i| 50.0 + ((i * 7) % 5) as f64 * 0.2).collect();
+        let points = daily(&values);
+        assert!(detect_changepoints(&points, 5, None).unwrap().is_empty());
+        assert!(
+            detect_changepoints(&points[..6], 5, None)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            detect_changepoints(&points, usize::MAX, None)
+                .unwrap()
+                .is_empty(),
+            "Huge segment lengths must not overflow"
+        );
+        assert!(detect_changepoints(&points, 0, None).is_err());
+        assert!(detect_changepoints(&points, 5, Some(f64::NAN)).is_err());
+        assert!(detect_changepoints(&daily(&[1.0, f64::INFINITY]), 1, None).is_err());
+    }
+}
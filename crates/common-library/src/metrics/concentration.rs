@@ -0,0 +1,135 @@
+//! Contributor concentration metrics
+//!
+//! Given how much each contributor has done (commits, lines, reviews), these
+//! measure how dependent a project is on a few people: the bus factor, the
+//! Gini coefficient and the Herfindahl-Hirschman index.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Share of all contributions the bus factor's contributors must cover by default
+pub const DEFAULT_BUS_FACTOR_THRESHOLD: f64 = 0.5;
+
+/// Concentration summary of one contribution distribution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ContributorConcentration {
+    /// Contributors with a non-zero share
+    pub contributors: usize,
+    /// Fewest contributors covering half of all contributions
+    pub bus_factor: usize,
+    /// 0 when everyone contributes equally, approaching 1 when one person does everything
+    pub gini: f64,
+    /// Sum of squared shares, from `1 / contributors` (even) to 1 (single contributor)
+    pub herfindahl: f64,
+}
+
+impl ContributorConcentration {
+    /// Summarize per-contributor amounts, which must be finite and non-negative
+    pub fn from_contributions(contributions: &[f64]) -> Result<Self> {
+        let shares = shares(contributions)?;
+        Ok(Self {
+            contributors: shares.iter().filter(|&&share| share > 0.0).count(),
+            bus_factor: bus_factor_of_shares(&shares, DEFAULT_BUS_FACTOR_THRESHOLD),
+            gini: gini_of_shares(&shares),
+            herfindahl: shares.iter().map(|share| share * share).sum(),
+        })
+    }
+}
+
+/// Fewest contributors whose combined share reaches `threshold`, in `(0, 1]`
+pub fn bus_factor(contributions: &[f64], threshold: f64) -> Result<usize> {
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(Error::metrics("Bus factor threshold must be in (0, 1]"));
+    }
+    Ok(bus_factor_of_shares(&shares(contributions)?, threshold))
+}
+
+/// Gini coefficient of the contribution distribution
+pub fn gini(contributions: &[f64]) -> Result<f64> {
+    Ok(gini_of_shares(&shares(contributions)?))
+}
+
+/// Herfindahl-Hirschman index (sum of squared shares)
+pub fn herfindahl(contributions: &[f64]) -> Result<f64> {
+    Ok(shares(contributions)?
+        .iter()
+        .map(|share| share * share)
+        .sum())
+}
+
+/// Contributions as fractions of the total, largest first
+fn shares(contributions: &[f64]) -> Result<Vec<f64>> {
+    if contributions.iter().any(|c| !c.is_finite() || *c < 0.0) {
+        return Err(Error::metrics(
+            "Contributions must be finite and non-negative",
+        ));
+    }
+    let total: f64 = contributions.iter().sum();
+    if total == 0.0 {
+        return Err(Error::metrics("No contributions to measure"));
+    }
+    let mut shares: Vec<f64> = contributions.iter().map(|c| c / total).collect();
+    shares.sort_by(|a, b| b.total_cmp(a));
+    Ok(shares)
+}
+
+fn bus_factor_of_shares(shares: &[f64], threshold: f64) -> usize {
+    let mut covered = 0.0;
+    for (index, share) in shares.iter().enumerate() {
+        covered += share;
+        // Tolerate rounding when the threshold is exactly reachable
+        if covered >= threshold - 1e-12 {
+            return index + 1;
+        }
+    }
+    shares.len()
+}
+
+/// Gini over all entries, including zero contributors
+fn gini_of_shares(shares: &[f64]) -> f64 {
+    let n = shares.len() as f64;
+    if shares.len() < 2 {
+        return 0.0;
+    }
+    // With shares sorted descending, rank i (1-based) from the largest
+    let weighted: f64 = shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| (i + 1) as f64 * share)
+        .sum();
+    (n + 1.0 - 2.0 * weighted) / n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_even_and_dominated_distributions() {
+        // Test: Equal contributors have no concentration, a single dominant one has a lot
+        let even = ContributorConcentration::from_contributions(&[10.0; 4]).unwrap();
+        assert_eq!(even.contributors, 4);
+        assert_eq!(even.bus_factor, 2);
+        assert!(even.gini.abs() < 1e-12, "Equal shares have zero Gini");
+        assert!((even.herfindahl - 0.25).abs() < 1e-12);
+
+        let dominated =
+            ContributorConcentration::from_contributions(&[1.0, 97.0, 1.0, 1.0]).unwrap();
+        assert_eq!(dominated.bus_factor, 1);
+        assert!((dominated.gini - 0.72).abs() < 1e-12);
+        assert!(dominated.herfindahl > 0.9);
+    }
+
+    #[test]
+    fn test_thresholds_and_invalid_input() {
+        // Test: Custom thresholds change the bus factor and bad input is rejected
+        let commits = [50.0, 30.0, 15.0, 5.0];
+        assert_eq!(bus_factor(&commits, 0.8).unwrap(), 2);
+        assert_eq!(bus_factor(&commits, 0.9).unwrap(), 3);
+        assert_eq!(bus_factor(&commits, 1.0).unwrap(), 4);
+        assert!(bus_factor(&commits, 0.0).is_err());
+        assert!(gini(&[1.0, -1.0]).is_err());
+        assert!(herfindahl(&[0.0, 0.0]).is_err(), "Zero total is an error");
+        assert_eq!(gini(&[5.0]).unwrap(), 0.0);
+    }
+}
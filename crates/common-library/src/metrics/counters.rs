@@ -0,0 +1,128 @@
+//! Conversions between cumulative counters and per-period values
+//!
+//! Registries report totals (all-time downloads) that occasionally reset to
+//! zero. These helpers turn such counters into increases or rates, treating
+//! any drop as a reset rather than negative growth, and turn per-period
+//! values back into running totals. Results that overflow `f64` are
+//! reported as errors rather than stored as infinities.
+
+use super::timeseries::TimeSeries;
+use crate::error::Result;
+use chrono::TimeDelta;
+
+impl TimeSeries {
+    /// Increase of a cumulative counter since the previous observation
+    ///
+    /// Each output point carries the later timestamp. A drop is treated as a
+    /// reset to zero, so the increase is the new value itself.
+    pub fn counter_increases(&self) -> Result<TimeSeries> {
+        let mut output = TimeSeries::default();
+        for pair in self.points().windows(2) {
+            let ((_, previous), (timestamp, current)) = (pair[0], pair[1]);
+            let increase = if current >= previous {
+                current - previous
+            } else {
+                current
+            };
+            output.push(timestamp, increase)?;
+        }
+        Ok(output)
+    }
+
+    /// Counter increase per `period` between consecutive observations
+    ///
+    /// Observations sharing a timestamp are skipped.
+    pub fn counter_rate(&self, period: TimeDelta) -> Result<TimeSeries> {
+        let period_ms = period.num_milliseconds() as f64;
+        let mut output = TimeSeries::default();
+        for (pair, (timestamp, increase)) in self
+            .points()
+            .windows(2)
+            .zip(self.counter_increases()?.points().iter().copied())
+        {
+            let elapsed_ms = (pair[1].0 - pair[0].0).num_milliseconds() as f64;
+            if elapsed_ms > 0.0 {
+                output.push(timestamp, increase / elapsed_ms * period_ms)?;
+            }
+        }
+        Ok(output)
+    }
+
+    /// Running total of per-period values, starting from `initial`
+    pub fn cumulative_sum(&self, initial: f64) -> Result<TimeSeries> {
+        let mut total = initial;
+        let mut output = TimeSeries::default();
+        for &(timestamp, value) in self.points() {
+            total += value;
+            output.push(timestamp, total)?;
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 7, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_counter_resets_and_rates() {
+        // Test: Drops are treated as resets and rates account for uneven spacing
+        let totals = TimeSeries::new(vec![
+            (day(1), 100.0),
+            (day(2), 150.0),
+            (day(3), 20.0),
+            (day(5), 80.0),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            totals.counter_increases().unwrap().values(),
+            vec![50.0, 20.0, 60.0],
+            "The reset on day 3 counts from zero"
+        );
+        let daily = totals.counter_rate(TimeDelta::days(1)).unwrap();
+        assert_eq!(daily.values(), vec![50.0, 20.0, 30.0]);
+        assert_eq!(daily.first().unwrap().0, day(2));
+    }
+
+    #[test]
+    fn test_cumulative_sum_round_trips_increases() {
+        // Test: Summing increases rebuilds a reset-free running total
+        let totals = TimeSeries::new(vec![(day(1), 10.0), (day(2), 15.0), (day(3), 4.0)]).unwrap();
+        let rebuilt = totals
+            .counter_increases()
+            .unwrap()
+            .cumulative_sum(10.0)
+            .unwrap();
+        assert_eq!(rebuilt.values(), vec![15.0, 19.0]);
+        assert!(
+            TimeSeries::default()
+                .counter_rate(TimeDelta::days(1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_overflowing_counters_are_errors() {
+        // Test: Results beyond the range of f64 fail instead of panicking
+        let huge = TimeSeries::new(vec![(day(1), 1e308), (day(2), 1e308)]).unwrap();
+        assert!(huge.cumulative_sum(0.0).is_err(), "Sum overflows");
+        assert!(
+            TimeSeries::new(vec![(day(1), 1.0)])
+                .unwrap()
+                .cumulative_sum(f64::NAN)
+                .is_err(),
+            "Non-finite initial total"
+        );
+
+        let swing = TimeSeries::new(vec![(day(1), -1e308), (day(2), 1e308)]).unwrap();
+        assert!(swing.counter_increases().is_err(), "Increase overflows");
+        assert!(swing.counter_rate(TimeDelta::days(1)).is_err());
+    }
+}
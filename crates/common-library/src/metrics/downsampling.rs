@@ -0,0 +1,92 @@
+//! Downsampling for charts
+//!
+//! Largest-Triangle-Three-Buckets keeps the first and last points and, from
+//! each of the evenly sized buckets between them, the point forming the
+//! largest triangle with its neighbours' selections. Peaks and dips survive,
+//! so a multi-year daily series reduced to a few hundred points keeps its
+//! visual shape.
+
+use super::timeseries::TimeSeries;
+
+impl TimeSeries {
+    /// Reduce to at most `threshold` points with LTTB
+    ///
+    /// Series already within the threshold, and thresholds below 3, return
+    /// the series unchanged.
+    pub fn downsample_lttb(&self, threshold: usize) -> TimeSeries {
+        let points = self.points();
+        if threshold < 3 || points.len() <= threshold {
+            return self.clone();
+        }
+        let x = |i: usize| points[i].0.timestamp_millis() as f64;
+        let y = |i: usize| points[i].1;
+
+        let bucket_size = (points.len() - 2) as f64 / (threshold - 2) as f64;
+        let bucket = |b: usize| {
+            let start = (b as f64 * bucket_size) as usize + 1;
+            let end = (((b + 1) as f64 * bucket_size) as usize + 1).min(points.len() - 1);
+            start..end
+        };
+
+        let mut selected = vec![0];
+        let mut previous = 0;
+        for b in 0..threshold - 2 {
+            // Average of the next bucket, or the last point for the final bucket
+            let next = if b + 1 < threshold - 2 {
+                bucket(b + 1)
+            } else {
+                points.len() - 1..points.len()
+            };
+            let count = next.len() as f64;
+            let avg_x = next.clone().map(x).sum::<f64>() / count;
+            let avg_y = next.map(y).sum::<f64>() / count;
+
+            let (ax, ay) = (x(previous), y(previous));
+            let best = bucket(b)
+                .max_by(|&i, &j| {
+                    let area =
+                        |k: usize| ((ax - avg_x) * (y(k) - ay) - (ax - x(k)) * (avg_y - ay)).abs();
+                    area(i).total_cmp(&area(j))
+                })
+                .expect("buckets are non-empty");
+            selected.push(best);
+            previous = best;
+        }
+        selected.push(points.len() - 1);
+
+        TimeSeries::new(selected.into_iter().map(|i| points[i]).collect())
+            .expect("selected values are finite")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    #[test]
+    fn test_lttb_keeps_endpoints_and_spikes() {
+        // Test: Downsampling keeps the first, last and extreme points
+        let start = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        let series = TimeSeries::new(
+            (0..1000)
+                .map(|i| {
+                    let value = if i == 500 { 1000.0 } else { (i % 10) as f64 };
+                    (start + TimeDelta::days(i), value)
+                })
+                .collect(),
+        )
+        .unwrap();
+
+        let reduced = series.downsample_lttb(50);
+        assert_eq!(reduced.len(), 50);
+        assert_eq!(reduced.first(), series.first());
+        assert_eq!(reduced.last(), series.last());
+        assert!(
+            reduced.values().contains(&1000.0),
+            "The spike should survive downsampling"
+        );
+        assert_eq!(series.downsample_lttb(2000).len(), 1000);
+        assert_eq!(series.downsample_lttb(2).len(), 1000);
+    }
+}
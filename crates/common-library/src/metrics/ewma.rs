@@ -0,0 +1,204 @@
+//! Exponentially weighted moving statistics
+//!
+//! [`Ewma`] and [`EwmVar`] weight recent observations more heavily, with
+//! the weight halving every half-life. The half-life is either a number of
+//! observations or a span of time, the latter decaying correctly when
+//! updates arrive irregularly. Both serialize so monitoring state can be
+//! persisted between full recomputations.
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How quickly old observations lose weight
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HalfLife {
+    /// Weight halves every this many observations
+    Observations(f64),
+    /// Weight halves every this much time
+    Duration(TimeDelta),
+}
+
+impl HalfLife {
+    fn validate(self) -> Result<Self> {
+        let valid = match self {
+            HalfLife::Observations(n) => n.is_finite() && n > 0.0,
+            HalfLife::Duration(span) => span > TimeDelta::zero(),
+        };
+        if valid {
+            Ok(self)
+        } else {
+            Err(Error::metrics("Half-life must be positive"))
+        }
+    }
+
+    /// Weight given to a new observation arriving `elapsed` after the previous one
+    fn alpha(self, elapsed: Option<TimeDelta>) -> f64 {
+        let steps = match self {
+            HalfLife::Observations(n) => 1.0 / n,
+            HalfLife::Duration(span) => match elapsed {
+                Some(elapsed) => {
+                    elapsed.num_milliseconds().max(0) as f64 / span.num_milliseconds() as f64
+                }
+                None => 1.0,
+            },
+        };
+        1.0 - 0.5f64.powf(steps)
+    }
+}
+
+/// Exponentially weighted moving average
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ewma {
+    half_life: HalfLife,
+    mean: Option<f64>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl Ewma {
+    /// An empty average with the given half-life
+    pub fn new(half_life: HalfLife) -> Result<Self> {
+        Ok(Self {
+            half_life: half_life.validate()?,
+            mean: None,
+            updated_at: None,
+        })
+    }
+
+    /// Add a value observed now; non-finite values are ignored
+    pub fn update(&mut self, value: f64) {
+        self.update_at(Utc::now(), value);
+    }
+
+    /// Add a value observed at `timestamp`
+    pub fn update_at(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let elapsed = self.updated_at.map(|previous| timestamp - previous);
+        self.mean = Some(match self.mean {
+            None => value,
+            Some(mean) => mean + self.half_life.alpha(elapsed) * (value - mean),
+        });
+        self.updated_at = Some(timestamp);
+    }
+
+    /// Current average, if any values were added
+    pub fn value(&self) -> Option<f64> {
+        self.mean
+    }
+}
+
+/// Exponentially weighted moving mean and variance
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EwmVar {
+    half_life: HalfLife,
+    mean: Option<f64>,
+    variance: f64,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl EwmVar {
+    /// An empty estimator with the given half-life
+    pub fn new(half_life: HalfLife) -> Result<Self> {
+        Ok(Self {
+            half_life: half_life.validate()?,
+            mean: None,
+            variance: 0.0,
+            updated_at: None,
+        })
+    }
+
+    /// Add a value observed now; non-finite values are ignored
+    pub fn update(&mut self, value: f64) {
+        self.update_at(Utc::now(), value);
+    }
+
+    /// Add a value observed at `timestamp`
+    pub fn update_at(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        let elapsed = self.updated_at.map(|previous| timestamp - previous);
+        match self.mean {
+            None => self.mean = Some(value),
+            Some(mean) => {
+                let alpha = self.half_life.alpha(elapsed);
+                let difference = value - mean;
+                let increment = alpha * difference;
+                self.mean = Some(mean + increment);
+                self.variance = (1.0 - alpha) * (self.variance + difference * increment);
+            }
+        }
+        self.updated_at = Some(timestamp);
+    }
+
+    /// Current weighted mean
+    pub fn mean(&self) -> Option<f64> {
+        self.mean
+    }
+
+    /// Current weighted variance
+    pub fn variance(&self) -> Option<f64> {
+        self.mean.map(|_| self.variance)
+    }
+
+    /// Current weighted standard deviation
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Standard deviations `value` lies from the weighted mean
+    pub fn z_score(&self, value: f64) -> Option<f64> {
+        let std_dev = self.std_dev().filter(|sd| *sd > 0.0)?;
+        Some((value - self.mean?) / std_dev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 9, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_half_life_weights() {
+        // Test: After one half-life a step change is half absorbed, for both half-life kinds
+        let mut by_count = Ewma::new(HalfLife::Observations(1.0)).unwrap();
+        by_count.update_at(day(1), 0.0);
+        by_count.update_at(day(2), 100.0);
+        assert_eq!(by_count.value(), Some(50.0));
+
+        let mut by_time = Ewma::new(HalfLife::Duration(TimeDelta::days(2))).unwrap();
+        by_time.update_at(day(1), 0.0);
+        by_time.update_at(day(5), 100.0);
+        assert_eq!(
+            by_time.value(),
+            Some(75.0),
+            "Four days is two half-lives regardless of update count"
+        );
+        assert!(Ewma::new(HalfLife::Observations(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_ewmvar_tracks_spread_and_serializes() {
+        // Test: Variance grows with noise, z-scores flag outliers, and state round-trips
+        let mut stats = EwmVar::new(HalfLife::Observations(10.0)).unwrap();
+        assert_eq!(stats.variance(), None);
+        for i in 0..200 {
+            stats.update_at(day(1), if i % 2 == 0 { 9.0 } else { 11.0 });
+        }
+        assert!((stats.mean().unwrap() - 10.0).abs() < 0.1);
+        assert!((stats.std_dev().unwrap() - 1.0).abs() < 0.1);
+        assert!(stats.z_score(20.0).unwrap() > 5.0);
+
+        let json = serde_json::to_string(&stats).unwrap();
+        let restored: EwmVar = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.mean(), stats.mean());
+        assert!((restored.variance().unwrap() - stats.variance().unwrap()).abs() < 1e-12);
+    }
+}
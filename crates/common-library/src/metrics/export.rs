@@ -0,0 +1,399 @@
+//! Pushing internal metrics to a collector
+//!
+//! Components record counters and gauges in a shared [`MetricsRegistry`].
+//! [`start_exporter`] periodically snapshots the registry and pushes it as
+//! StatsD lines over UDP or as OTLP/HTTP JSON (with the `http` feature), for
+//! environments that cannot scrape an endpoint.
+
+use crate::config::{MetricsExportConfig, MetricsProtocol};
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// Largest StatsD datagram, sized to avoid IP fragmentation
+const MAX_STATSD_PACKET: usize = 1432;
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    counters: BTreeMap<String, u64>,
+    gauges: BTreeMap<String, f64>,
+}
+
+/// Thread-safe store of named counters and gauges; clones share state
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    state: Arc<Mutex<RegistryState>>,
+}
+
+/// Point-in-time copy of a registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Cumulative counter totals
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, f64>,
+    pub taken_at: DateTime<Utc>,
+}
+
+impl MetricsRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `by` to a counter, creating it at zero
+    pub fn increment(&self, name: &str, by: u64) {
+        let mut state = self.state.lock().expect("metrics registry poisoned");
+        let counter = state.counters.entry(name.to_string()).or_default();
+        *counter = counter.saturating_add(by);
+    }
+
+    /// Set a gauge to its current value
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        if value.is_finite() {
+            let mut state = self.state.lock().expect("metrics registry poisoned");
+            state.gauges.insert(name.to_string(), value);
+        }
+    }
+
+    /// Copy the current values
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let state = self.state.lock().expect("metrics registry poisoned");
+        MetricsSnapshot {
+            counters: state.counters.clone(),
+            gauges: state.gauges.clone(),
+            taken_at: Utc::now(),
+        }
+    }
+}
+
+fn prefixed(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) if !prefix.is_empty() => format!("{}.{}", prefix, name),
+        _ => name.to_string(),
+    }
+}
+
+/// Sends snapshots as StatsD lines over UDP
+#[derive(Debug)]
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    prefix: Option<String>,
+    /// Counter totals already reported, so each push sends only the increase
+    reported: HashMap<String, u64>,
+}
+
+impl StatsdExporter {
+    /// Connect to a StatsD daemon at `host:port`
+    pub async fn connect(endpoint: &str, prefix: Option<String>) -> Result<Self> {
+        let target = tokio::net::lookup_host(endpoint)
+            .await?
+            .next()
+            .ok_or_else(|| {
+                Error::metrics(format!("Cannot resolve StatsD endpoint {}", endpoint))
+            })?;
+        let bind = if target.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target).await?;
+        Ok(Self {
+            socket,
+            prefix,
+            reported: HashMap::new(),
+        })
+    }
+
+    /// StatsD lines for a snapshot: counter increases since the last call, then gauges
+    ///
+    /// A counter lower than last reported is assumed to have been reset.
+    pub fn lines(&mut self, snapshot: &MetricsSnapshot) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (name, &total) in &snapshot.counters {
+            let previous = self.reported.insert(name.clone(), total).unwrap_or(0);
+            let delta = if total >= previous {
+                total - previous
+            } else {
+                total
+            };
+            if delta > 0 {
+                lines.push(format!(
+                    "{}:{}|c",
+                    prefixed(self.prefix.as_deref(), name),
+                    delta
+                ));
+            }
+        }
+        for (name, value) in &snapshot.gauges {
+            lines.push(format!(
+                "{}:{}|g",
+                prefixed(self.prefix.as_deref(), name),
+                value
+            ));
+        }
+        lines
+    }
+
+    /// Send a snapshot, packing lines into as few datagrams as fit
+    pub async fn export(&mut self, snapshot: &MetricsSnapshot) -> Result<()> {
+        let mut packet = String::new();
+        for line in self.lines(snapshot) {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_STATSD_PACKET {
+                self.socket.send(packet.as_bytes()).await?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.socket.send(packet.as_bytes()).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Posts snapshots to an OTLP/HTTP collector as JSON
+#[cfg(feature = "http")]
+pub struct OtlpExporter {
+    client: crate::http::APIClient,
+    url: String,
+    prefix: Option<String>,
+    service_name: String,
+    started_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "http")]
+impl OtlpExporter {
+    /// Export to `{endpoint}/v1/metrics`
+    pub fn new(endpoint: &str, prefix: Option<String>, service_name: String) -> Result<Self> {
+        Ok(Self {
+            client: crate::http::APIClient::new(crate::http::HttpClientConfig::default())?,
+            url: format!("{}/v1/metrics", endpoint.trim_end_matches('/')),
+            prefix,
+            service_name,
+            started_at: Utc::now(),
+        })
+    }
+
+    /// OTLP JSON body for a snapshot; counters are cumulative monotonic sums
+    pub fn payload(&self, snapshot: &MetricsSnapshot) -> serde_json::Value {
+        let nanos =
+            |time: DateTime<Utc>| time.timestamp_nanos_opt().unwrap_or_default().to_string();
+        let now = nanos(snapshot.taken_at);
+        let start = nanos(self.started_at);
+        let mut metrics: Vec<serde_json::Value> = snapshot
+            .counters
+            .iter()
+            .map(|(name, total)| {
+                serde_json::json!({
+                    "name": prefixed(self.prefix.as_deref(), name),
+                    "sum": {
+                        "dataPoints": [{
+                            "asInt": total.to_string(),
+                            "startTimeUnixNano": start,
+                            "timeUnixNano": now
+                        }],
+                        "aggregationTemporality": 2,
+                        "isMonotonic": true
+                    }
+                })
+            })
+            .collect();
+        metrics.extend(snapshot.gauges.iter().map(|(name, value)| {
+            serde_json::json!({
+                "name": prefixed(self.prefix.as_deref(), name),
+                "gauge": {
+                    "dataPoints": [{"asDouble": value, "timeUnixNano": now}]
+                }
+            })
+        }));
+        serde_json::json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": {"stringValue": self.service_name}
+                    }]
+                },
+                "scopeMetrics": [{
+                    "scope": {"name": "common-library"},
+                    "metrics": metrics
+                }]
+            }]
+        })
+    }
+
+    /// Post a snapshot to the collector
+    pub async fn export(&self, snapshot: &MetricsSnapshot) -> Result<()> {
+        self.client
+            .post_json(&self.url, &self.payload(snapshot))
+            .await?;
+        Ok(())
+    }
+}
+
+/// An exporter for any configured protocol
+pub enum MetricsExporter {
+    Statsd(StatsdExporter),
+    #[cfg(feature = "http")]
+    Otlp(Box<OtlpExporter>),
+}
+
+impl MetricsExporter {
+    /// Build the exporter described by `config`
+    pub async fn from_config(config: &MetricsExportConfig) -> Result<Self> {
+        match config.protocol {
+            MetricsProtocol::Statsd => Ok(MetricsExporter::Statsd(
+                StatsdExporter::connect(&config.endpoint, config.prefix.clone()).await?,
+            )),
+            #[cfg(feature = "http")]
+            MetricsProtocol::Otlp => Ok(MetricsExporter::Otlp(Box::new(OtlpExporter::new(
+                &config.endpoint,
+                config.prefix.clone(),
+                config.service_name.clone(),
+            )?))),
+            #[cfg(not(feature = "http"))]
+            MetricsProtocol::Otlp => Err(Error::config(
+                "OTLP metrics export requires the http feature",
+            )),
+        }
+    }
+
+    /// Push one snapshot
+    pub async fn export(&mut self, snapshot: &MetricsSnapshot) -> Result<()> {
+        match self {
+            MetricsExporter::Statsd(exporter) => exporter.export(snapshot).await,
+            #[cfg(feature = "http")]
+            MetricsExporter::Otlp(exporter) => exporter.export(snapshot).await,
+        }
+    }
+}
+
+/// Push `registry` every `interval_seconds` until the returned task is aborted
+///
+/// Failed pushes are logged and retried on the next tick.
+pub async fn start_exporter(
+    registry: MetricsRegistry,
+    config: &MetricsExportConfig,
+) -> Result<JoinHandle<()>> {
+    let mut exporter = MetricsExporter::from_config(config).await?;
+    let period = Duration::from_secs(config.interval_seconds.max(1));
+    Ok(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            match exporter.export(&registry.snapshot()).await {
+                Ok(()) => debug!("Exported metrics snapshot"),
+                Err(e) => warn!("Metrics export failed: {}", e),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statsd_config(endpoint: String) -> MetricsExportConfig {
+        MetricsExportConfig {
+            protocol: MetricsProtocol::Statsd,
+            endpoint,
+            interval_seconds: 1,
+            prefix: Some("repo_intel".to_string()),
+            service_name: "test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_statsd_sends_counter_deltas_and_gauges() {
+        // Test: Counters are sent as increases since the last push, gauges as values
+        let daemon = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let registry = MetricsRegistry::new();
+        registry.increment("requests", 3);
+        registry.set_gauge("queue_depth", 1.5);
+
+        let mut exporter = StatsdExporter::connect(
+            &daemon.local_addr().unwrap().to_string(),
+            Some("repo_intel".to_string()),
+        )
+        .await
+        .unwrap();
+        exporter.export(&registry.snapshot()).await.unwrap();
+
+        let mut buf = [0u8; MAX_STATSD_PACKET];
+        let len = daemon.recv(&mut buf).await.unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            "repo_intel.requests:3|c\nrepo_intel.queue_depth:1.5|g"
+        );
+
+        registry.increment("requests", 2);
+        let lines = exporter.lines(&registry.snapshot());
+        assert_eq!(
+            lines[0], "repo_intel.requests:2|c",
+            "Only the increase is sent"
+        );
+        assert_eq!(
+            exporter.lines(&registry.snapshot()).len(),
+            1,
+            "Unchanged counters are skipped"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_exporter_pushes_periodically() {
+        // Test: The background task pushes snapshots on its interval
+        let daemon = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let registry = MetricsRegistry::new();
+        registry.set_gauge("up", 1.0);
+        let handle = start_exporter(
+            registry,
+            &statsd_config(daemon.local_addr().unwrap().to_string()),
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; MAX_STATSD_PACKET];
+        let len = tokio::time::timeout(Duration::from_secs(5), daemon.recv(&mut buf))
+            .await
+            .expect("exporter should push")
+            .unwrap();
+        assert_eq!(&buf[..len], b"repo_intel.up:1|g");
+        handle.abort();
+    }
+
+    #[cfg(feature = "http")]
+    #[tokio::test]
+    async fn test_otlp_posts_json_metrics() {
+        // Test: OTLP export posts counters as sums and gauges to /v1/metrics
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/metrics"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let registry = MetricsRegistry::new();
+        registry.increment("requests", 7);
+        registry.set_gauge("up", 1.0);
+        let exporter = OtlpExporter::new(&server.uri(), None, "collector".to_string()).unwrap();
+        let payload = exporter.payload(&registry.snapshot());
+        let metrics = &payload["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+        assert_eq!(metrics[0]["sum"]["dataPoints"][0]["asInt"], "7");
+        assert_eq!(metrics[1]["gauge"]["dataPoints"][0]["asDouble"], 1.0);
+        exporter.export(&registry.snapshot()).await.unwrap();
+    }
+}
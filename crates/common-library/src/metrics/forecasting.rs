@@ -0,0 +1,492 @@
+//! Exponential smoothing forecasts
+//!
+//! A [`Forecaster`] fits simple, double (Holt) or triple (additive
+//! Holt-Winters) exponential smoothing to an evenly spaced series and
+//! projects it forward with prediction intervals, e.g. monthly downloads
+//! 6–12 months out. [`select_model`] fits several candidate models,
+//! including autoregressive ones, and keeps the one with the lowest AIC.
+
+use super::autoregressive::ArForecaster;
+use super::statistical::normal_quantile;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Exponential smoothing variant and its smoothing factors, each in `(0, 1]`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SmoothingModel {
+    /// Level only
+    Simple { alpha: f64 },
+    /// Level and trend (Holt)
+    Double { alpha: f64, beta: f64 },
+    /// Level, trend and additive seasonality (Holt-Winters)
+    Triple {
+        alpha: f64,
+        beta: f64,
+        gamma: f64,
+        season_length: usize,
+    },
+}
+
+impl SmoothingModel {
+    /// Fewest observations the model can be fitted to
+    pub fn min_observations(&self) -> usize {
+        match self {
+            SmoothingModel::Simple { .. } => 1,
+            SmoothingModel::Double { .. } => 2,
+            SmoothingModel::Triple { season_length, .. } => season_length * 2,
+        }
+    }
+
+    /// Smoothing factors plus initial states, as counted by AIC
+    fn parameter_count(&self) -> usize {
+        match self {
+            SmoothingModel::Simple { .. } => 2,
+            SmoothingModel::Double { .. } => 4,
+            SmoothingModel::Triple { season_length, .. } => 5 + season_length,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let factors: &[f64] = match self {
+            SmoothingModel::Simple { alpha } => &[*alpha],
+            SmoothingModel::Double { alpha, beta } => &[*alpha, *beta],
+            SmoothingModel::Triple {
+                alpha,
+                beta,
+                gamma,
+                season_length,
+            } => {
+                if *season_length < 2 {
+                    return Err(Error::metrics("Season length must be at least 2"));
+                }
+                &[*alpha, *beta, *gamma]
+            }
+        };
+        if factors.iter().any(|f| !(*f > 0.0 && *f <= 1.0)) {
+            return Err(Error::metrics("Smoothing factors must be in (0, 1]"));
+        }
+        Ok(())
+    }
+}
+
+/// One projected value with its prediction interval
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ForecastPoint {
+    /// Steps ahead of the last observation, starting at 1
+    pub step: usize,
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Projected values and the in-sample fit they came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forecast {
+    pub points: Vec<ForecastPoint>,
+    /// One-step-ahead errors over the fitted part of the series
+    pub residuals: Vec<f64>,
+    /// Root mean square of the residuals
+    pub residual_std_dev: f64,
+    /// Confidence level of the intervals, e.g. 0.95
+    pub confidence: f64,
+}
+
+/// Fits exponential smoothing models and projects them forward
+#[derive(Debug, Clone)]
+pub struct Forecaster {
+    model: SmoothingModel,
+    confidence: f64,
+}
+
+impl Forecaster {
+    /// Forecast with `model` and 95% prediction intervals
+    pub fn new(model: SmoothingModel) -> Self {
+        Self {
+            model,
+            confidence: 0.95,
+        }
+    }
+
+    /// Set the prediction interval confidence level, in `(0, 1)`
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// The configured model
+    pub fn model(&self) -> SmoothingModel {
+        self.model
+    }
+
+    /// Fit to `values` and project `horizon` steps past the last one
+    pub fn forecast(&self, values: &[f64], horizon: usize) -> Result<Forecast> {
+        self.model.validate()?;
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(Error::metrics("Confidence must be in (0, 1)"));
+        }
+        if values.len() < self.model.min_observations() {
+            return Err(Error::metrics(format!(
+                "Need at least {} observations, got {}",
+                self.model.min_observations(),
+                values.len()
+            )));
+        }
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(Error::metrics("Cannot forecast non-finite values"));
+        }
+
+        let (residuals, project): (Vec<f64>, Box<dyn Fn(usize) -> f64>) = match self.model {
+            SmoothingModel::Simple { alpha } => {
+                let mut level = values[0];
+                let mut residuals = Vec::with_capacity(values.len());
+                for &value in &values[1..] {
+                    residuals.push(value - level);
+                    level = alpha * value + (1.0 - alpha) * level;
+                }
+                (residuals, Box::new(move |_| level))
+            }
+            SmoothingModel::Double { alpha, beta } => {
+                let mut level = values[0];
+                let mut trend = values[1] - values[0];
+                let mut residuals = Vec::with_capacity(values.len());
+                for &value in &values[1..] {
+                    residuals.push(value - (level + trend));
+                    let previous = level;
+                    level = alpha * value + (1.0 - alpha) * (level + trend);
+                    trend = beta * (level - previous) + (1.0 - beta) * trend;
+                }
+                (residuals, Box::new(move |h| level + h as f64 * trend))
+            }
+            SmoothingModel::Triple {
+                alpha,
+                beta,
+                gamma,
+                season_length: m,
+            } => {
+                let first = mean(&values[..m]);
+                let second = mean(&values[m..2 * m]);
+                let mut level = first;
+                let mut trend = (second - first) / m as f64;
+                let mut seasonal: Vec<f64> = values[..m].iter().map(|v| v - first).collect();
+                let mut residuals = Vec::with_capacity(values.len());
+                for (t, &value) in values.iter().enumerate().skip(m) {
+                    let season = seasonal[t % m];
+                    residuals.push(value - (level + trend + season));
+                    let previous = level;
+                    level = alpha * (value - season) + (1.0 - alpha) * (level + trend);
+                    trend = beta * (level - previous) + (1.0 - beta) * trend;
+                    seasonal[t % m] = gamma * (value - level) + (1.0 - gamma) * season;
+                }
+                let n = values.len();
+                (
+                    residuals,
+                    Box::new(move |h| level + h as f64 * trend + seasonal[(n + h - 1) % m]),
+                )
+            }
+        };
+
+        let residual_std_dev = if residuals.is_empty() {
+            0.0
+        } else {
+            (residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64).sqrt()
+        };
+        let z = normal_quantile(0.5 + self.confidence / 2.0);
+        let points = (1..=horizon)
+            .map(|step| {
+                let value = project(step);
+                let margin = z * residual_std_dev * self.variance_multiplier(step).sqrt();
+                ForecastPoint {
+                    step,
+                    value,
+                    lower: value - margin,
+                    upper: value + margin,
+                }
+            })
+            .collect();
+
+        Ok(Forecast {
+            points,
+            residuals,
+            residual_std_dev,
+            confidence: self.confidence,
+        })
+    }
+
+    /// Forecast and score the fit for model comparison
+    pub fn evaluate(&self, values: &[f64], horizon: usize) -> Result<ForecastResult> {
+        let forecast = self.forecast(values, horizon)?;
+        Ok(ForecastResult {
+            model: FittedModel::Smoothing(self.model),
+            aic: aic(&forecast.residuals, self.model.parameter_count()),
+            diagnostics: ResidualDiagnostics::from_residuals(&forecast.residuals),
+            forecast,
+        })
+    }
+
+    /// Forecast error variance `h` steps ahead relative to the one-step variance
+    fn variance_multiplier(&self, h: usize) -> f64 {
+        let weight = |j: usize| match self.model {
+            SmoothingModel::Simple { alpha } => alpha,
+            SmoothingModel::Double { alpha, beta } => alpha * (1.0 + j as f64 * beta),
+            SmoothingModel::Triple {
+                alpha,
+                beta,
+                gamma,
+                season_length,
+            } => {
+                let seasonal = if j.is_multiple_of(season_length) {
+                    gamma
+                } else {
+                    0.0
+                };
+                alpha * (1.0 + j as f64 * beta) + seasonal
+            }
+        };
+        1.0 + (1..h).map(|j| weight(j).powi(2)).sum::<f64>()
+    }
+}
+
+/// A model and the parameters it was fitted with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FittedModel {
+    Smoothing(SmoothingModel),
+    /// ARIMA(p, d, 0): AR coefficients for the series differenced `differences` times
+    Autoregressive {
+        differences: usize,
+        /// Mean of the differenced series
+        mean: f64,
+        coefficients: Vec<f64>,
+    },
+}
+
+/// Summary of one-step-ahead residuals for judging fit quality
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResidualDiagnostics {
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Residual autocorrelation at lags 1, 2, ...
+    pub autocorrelations: Vec<f64>,
+    /// Ljung-Box Q statistic over `autocorrelations`; large values mean the
+    /// model left structure in the residuals
+    pub ljung_box: f64,
+}
+
+impl ResidualDiagnostics {
+    /// Diagnose residuals using up to 10 lags
+    pub fn from_residuals(residuals: &[f64]) -> Self {
+        let n = residuals.len();
+        if n == 0 {
+            return Self {
+                mean: 0.0,
+                std_dev: 0.0,
+                autocorrelations: Vec::new(),
+                ljung_box: 0.0,
+            };
+        }
+        let mean = residuals.iter().sum::<f64>() / n as f64;
+        let variance = residuals.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n as f64;
+        let lags = (n / 4).min(10);
+        let autocorrelations: Vec<f64> = (1..=lags)
+            .map(|lag| {
+                if variance == 0.0 {
+                    return 0.0;
+                }
+                residuals[lag..]
+                    .iter()
+                    .zip(residuals)
+                    .map(|(a, b)| (a - mean) * (b - mean))
+                    .sum::<f64>()
+                    / (n as f64 * variance)
+            })
+            .collect();
+        let ljung_box = n as f64
+            * (n as f64 + 2.0)
+            * autocorrelations
+                .iter()
+                .enumerate()
+                .map(|(i, rho)| rho * rho / (n - i - 1) as f64)
+                .sum::<f64>();
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            autocorrelations,
+            ljung_box,
+        }
+    }
+}
+
+/// A fitted model, its forecast and fit statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForecastResult {
+    pub model: FittedModel,
+    /// Akaike information criterion; lower is better
+    pub aic: f64,
+    pub forecast: Forecast,
+    pub diagnostics: ResidualDiagnostics,
+}
+
+/// A model to try in [`select_model`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CandidateModel {
+    Smoothing(SmoothingModel),
+    Autoregressive { order: usize, differences: usize },
+}
+
+/// Fit every candidate and return the one with the lowest AIC
+///
+/// Candidates that cannot be fitted, e.g. because the series is too short,
+/// are skipped.
+pub fn select_model(
+    values: &[f64],
+    candidates: &[CandidateModel],
+    horizon: usize,
+    confidence: f64,
+) -> Result<ForecastResult> {
+    let mut best: Option<ForecastResult> = None;
+    for candidate in candidates {
+        let result = match *candidate {
+            CandidateModel::Smoothing(model) => Forecaster::new(model)
+                .with_confidence(confidence)
+                .evaluate(values, horizon),
+            CandidateModel::Autoregressive { order, differences } => ArForecaster::new(order)
+                .with_differences(differences)
+                .with_confidence(confidence)
+                .forecast(values, horizon),
+        };
+        match result {
+            Ok(result) if best.as_ref().is_none_or(|b| result.aic < b.aic) => best = Some(result),
+            Ok(_) => {}
+            Err(e) => debug!("Skipping forecast candidate {:?}: {}", candidate, e),
+        }
+    }
+    best.ok_or_else(|| Error::metrics("No candidate model could be fitted"))
+}
+
+/// AIC from conditional sum of squared residuals
+pub(crate) fn aic(residuals: &[f64], parameters: usize) -> f64 {
+    let n = residuals.len().max(1) as f64;
+    let variance = residuals.iter().map(|r| r * r).sum::<f64>() / n;
+    n * variance.max(f64::MIN_POSITIVE).ln() + 2.0 * parameters as f64
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_and_double_smoothing() {
+        // Test: Flat series forecast flat, linear series extrapolate their slope
+        let flat = Forecaster::new(SmoothingModel::Simple { alpha: 0.5 })
+            .forecast(&[10.0; 6], 3)
+            .unwrap();
+        assert!(flat.points.iter().all(|p| p.value == 10.0));
+        assert_eq!(flat.residual_std_dev, 0.0, "A flat series fits exactly");
+
+        let linear: Vec<f64> = (0..20).map(|i| 100.0 + 5.0 * i as f64).collect();
+        let forecast = Forecaster::new(SmoothingModel::Double {
+            alpha: 0.8,
+            beta: 0.2,
+        })
+        .forecast(&linear, 6)
+        .unwrap();
+        let last = forecast.points.last().unwrap();
+        assert_eq!(last.step, 6);
+        assert!(
+            (last.value - 225.0).abs() < 1e-6,
+            "Holt should extrapolate the trend, got {}",
+            last.value
+        );
+    }
+
+    #[test]
+    fn test_holt_winters_tracks_seasonality_with_widening_intervals() {
+        // Test: Triple smoothing repeats the seasonal pattern and intervals grow with horizon
+        let pattern = [10.0, 20.0, 30.0, 20.0];
+        let values: Vec<f64> = (0..24)
+            .map(|i| pattern[i % 4] + i as f64 + if i % 5 == 0 { 1.5 } else { 0.0 })
+            .collect();
+        let forecast = Forecaster::new(SmoothingModel::Triple {
+            alpha: 0.3,
+            beta: 0.1,
+            gamma: 0.3,
+            season_length: 4,
+        })
+        .with_confidence(0.9)
+        .forecast(&values, 8)
+        .unwrap();
+
+        let peak = forecast.points[2].value;
+        let trough = forecast.points[0].value;
+        assert!(
+            peak - trough > 10.0,
+            "Seasonal peak {} should stand above trough {}",
+            peak,
+            trough
+        );
+        let width = |p: &ForecastPoint| p.upper - p.lower;
+        assert!(width(&forecast.points[7]) > width(&forecast.points[0]));
+        assert_eq!(forecast.residuals.len(), 20);
+    }
+
+    #[test]
+    fn test_forecast_rejects_invalid_input() {
+        // Test: Bad parameters and short series are reported as metrics errors
+        let model = SmoothingModel::Triple {
+            alpha: 0.5,
+            beta: 0.5,
+            gamma: 0.5,
+            season_length: 7,
+        };
+        assert!(Forecaster::new(model).forecast(&[1.0; 10], 1).is_err());
+        assert!(
+            Forecaster::new(SmoothingModel::Simple { alpha: 1.5 })
+                .forecast(&[1.0], 1)
+                .is_err()
+        );
+        assert!(
+            Forecaster::new(SmoothingModel::Simple { alpha: 0.5 })
+                .forecast(&[1.0, f64::NAN], 1)
+                .is_err()
+        );
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_select_model_prefers_best_fit() {
+        // Test: AIC selection picks the trend model for a trending series and skips unfit candidates
+        let values: Vec<f64> = (0..40)
+            .map(|i| 50.0 + 2.0 * i as f64 + if i % 2 == 0 { 0.5 } else { -0.5 })
+            .collect();
+        let candidates = [
+            CandidateModel::Smoothing(SmoothingModel::Simple { alpha: 0.5 }),
+            CandidateModel::Smoothing(SmoothingModel::Double {
+                alpha: 0.5,
+                beta: 0.3,
+            }),
+            CandidateModel::Autoregressive {
+                order: 1,
+                differences: 1,
+            },
+            CandidateModel::Autoregressive {
+                order: 100,
+                differences: 0,
+            },
+        ];
+        let best = select_model(&values, &candidates, 3, 0.95).unwrap();
+        assert!(
+            matches!(best.model, FittedModel::Autoregressive { .. }),
+            "Alternating residuals are best captured by AR on differences, got {:?}",
+            best.model
+        );
+        assert!(best.diagnostics.autocorrelations.len() <= 10);
+        assert!(select_model(&values[..1], &candidates[2..], 3, 0.95).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Growth rates over elapsed time
+//!
+//! Collection runs are irregular, so growth is measured against the time
+//! actually elapsed between observations rather than a count of periods.
+//! [`TimeSeries::cagr`] annualizes the change across the whole series, and
+//! [`TimeSeries::period_growth`] reports growth per fixed-length period,
+//! annualizing a trailing partial period over its actual length.
+
+use super::timeseries::TimeSeries;
+use crate::error::{Error, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Average Gregorian year, so leap days do not skew annualized rates
+const MILLIS_PER_YEAR: f64 = 365.2425 * 86_400_000.0;
+
+/// Growth between the values interpolated at the ends of one period
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GrowthPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub start_value: f64,
+    pub end_value: f64,
+    /// Fractional change, e.g. `0.1` for +10%; `None` when the start value is not positive
+    pub growth: Option<f64>,
+    /// Compound annual rate equivalent to `growth` over this period's length
+    pub annualized: Option<f64>,
+    /// Whether the period was cut short by the end of the series
+    pub partial: bool,
+}
+
+impl TimeSeries {
+    /// Compound annual growth rate from the first to the last observation
+    ///
+    /// Years are measured from the timestamps, so gaps and uneven sampling
+    /// do not change the result. Needs a positive first value and a
+    /// non-negative last value.
+    pub fn cagr(&self) -> Result<f64> {
+        let (Some((start, start_value)), Some((end, end_value))) = (self.first(), self.last())
+        else {
+            return Err(Error::metrics("Growth needs at least 2 observations"));
+        };
+        if end <= start {
+            return Err(Error::metrics(
+                "Growth needs observations at different times",
+            ));
+        }
+        annualized(start_value, end_value, end - start).ok_or_else(|| {
+            Error::metrics(format!(
+                "Growth from {} to {} is undefined",
+                start_value, end_value
+            ))
+        })
+    }
+
+    /// Growth over consecutive periods of `period` starting at the first observation
+    ///
+    /// Values at period boundaries are interpolated between observations.
+    /// The last period ends at the last observation and is marked partial
+    /// when shorter than `period`.
+    pub fn period_growth(&self, period: TimeDelta) -> Result<Vec<GrowthPeriod>> {
+        if period <= TimeDelta::zero() {
+            return Err(Error::metrics("Growth period must be positive"));
+        }
+        let (Some((first, _)), Some((last, _))) = (self.first(), self.last()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut periods = Vec::new();
+        let mut start = first;
+        while start < last {
+            let full_end = start.checked_add_signed(period);
+            let end = full_end.map_or(last, |end| end.min(last));
+            let (Some(start_value), Some(end_value)) = (self.value_at(start), self.value_at(end))
+            else {
+                return Err(Error::metrics(format!(
+                    "No value to interpolate between {} and {}",
+                    start, end
+                )));
+            };
+            let growth = (start_value > 0.0).then(|| end_value / start_value - 1.0);
+            periods.push(GrowthPeriod {
+                start,
+                end,
+                start_value,
+                end_value,
+                growth: growth.filter(|g| g.is_finite()),
+                annualized: annualized(start_value, end_value, end - start),
+                partial: full_end.is_none_or(|full_end| full_end > last),
+            });
+            start = end;
+        }
+        Ok(periods)
+    }
+}
+
+/// Compound annual rate turning `from` into `to` over `elapsed`
+fn annualized(from: f64, to: f64, elapsed: TimeDelta) -> Option<f64> {
+    if !(from > 0.0 && to >= 0.0) || elapsed <= TimeDelta::zero() {
+        return None;
+    }
+    let years = elapsed.num_milliseconds() as f64 / MILLIS_PER_YEAR;
+    Some((to / from).powf(1.0 / years) - 1.0).filter(|rate| rate.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_cagr_uses_elapsed_time() {
+        // Test: Annualized growth depends on elapsed years, not on the number of observations
+        let start = at(2020, 1, 1);
+        let end = start + TimeDelta::milliseconds((2.0 * MILLIS_PER_YEAR) as i64);
+        let sparse = TimeSeries::new(vec![(start, 100.0), (end, 144.0)]).unwrap();
+        let dense = TimeSeries::new(vec![
+            (start, 100.0),
+            (at(2020, 2, 13), 101.0),
+            (at(2020, 3, 2), 107.0),
+            (at(2021, 8, 30), 130.0),
+            (end, 144.0),
+        ])
+        .unwrap();
+
+        assert!((sparse.cagr().unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(sparse.cagr().unwrap(), dense.cagr().unwrap());
+
+        let half_year = TimeSeries::new(vec![
+            (start, 100.0),
+            (
+                start + TimeDelta::milliseconds((MILLIS_PER_YEAR / 2.0) as i64),
+                110.0,
+            ),
+        ])
+        .unwrap();
+        assert!(
+            (half_year.cagr().unwrap() - 0.21).abs() < 1e-9,
+            "Half a year of +10% compounds to +21% a year"
+        );
+
+        assert!(
+            TimeSeries::new(vec![(start, 0.0), (end, 5.0)])
+                .unwrap()
+                .cagr()
+                .is_err()
+        );
+        assert!(TimeSeries::new(vec![(start, 5.0)]).unwrap().cagr().is_err());
+    }
+
+    #[test]
+    fn test_period_growth_with_partial_last_period() {
+        // Test: Periods interpolate boundary values and the trailing period is flagged partial
+        let series = TimeSeries::new(vec![
+            (at(2024, 1, 1), 100.0),
+            (at(2024, 1, 6), 150.0),
+            (at(2024, 1, 11), 200.0),
+            (at(2024, 1, 16), 250.0),
+        ])
+        .unwrap();
+        let periods = series.period_growth(TimeDelta::days(10)).unwrap();
+
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].end, at(2024, 1, 11));
+        assert_eq!(periods[0].growth, Some(1.0));
+        assert!(!periods[0].partial);
+        assert_eq!(periods[1].end, at(2024, 1, 16));
+        assert_eq!(periods[1].growth, Some(0.25));
+        assert!(periods[1].partial, "Last period is only 5 days long");
+        assert!(periods[1].annualized.unwrap() > periods[1].growth.unwrap());
+
+        assert!(series.period_growth(TimeDelta::zero()).is_err());
+        assert!(
+            TimeSeries::default()
+                .period_growth(TimeDelta::days(1))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
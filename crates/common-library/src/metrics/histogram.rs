@@ -0,0 +1,239 @@
+//! Bucketed distributions of metric values
+//!
+//! A [`Histogram`] counts values into buckets with fixed upper bounds plus a
+//! final overflow bucket. Histograms with the same bounds can be merged, and
+//! they serialize compactly so distributions from separate collection runs
+//! can be stored and compared.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// How bucket upper bounds are laid out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Buckets {
+    /// `count` buckets of equal `width` with the first ending at `start + width`
+    Linear {
+        start: f64,
+        width: f64,
+        count: usize,
+    },
+    /// `count` buckets ending at `start`, `start * factor`, `start * factor^2`, ...
+    Exponential {
+        start: f64,
+        factor: f64,
+        count: usize,
+    },
+    /// Explicit, strictly increasing upper bounds
+    Custom { bounds: Vec<f64> },
+}
+
+impl Buckets {
+    /// Upper bounds described by this layout
+    pub fn bounds(&self) -> Result<Vec<f64>> {
+        let bounds: Vec<f64> = match self {
+            Buckets::Linear {
+                start,
+                width,
+                count,
+            } => {
+                if *width <= 0.0 {
+                    return Err(Error::metrics("Linear bucket width must be positive"));
+                }
+                (1..=*count).map(|i| start + width * i as f64).collect()
+            }
+            Buckets::Exponential {
+                start,
+                factor,
+                count,
+            } => {
+                if *start <= 0.0 || *factor <= 1.0 {
+                    return Err(Error::metrics(
+                        "Exponential buckets need a positive start and a factor above 1",
+                    ));
+                }
+                (0..*count).map(|i| start * factor.powi(i as i32)).collect()
+            }
+            Buckets::Custom { bounds } => bounds.clone(),
+        };
+        if bounds.is_empty() {
+            return Err(Error::metrics("Histogram needs at least one bucket"));
+        }
+        if bounds.iter().any(|b| !b.is_finite()) || bounds.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(Error::metrics(
+                "Histogram bounds must be finite and strictly increasing",
+            ));
+        }
+        Ok(bounds)
+    }
+}
+
+/// Counts of values per bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    /// One count per bound plus the overflow bucket
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    /// An empty histogram with the given bucket layout
+    pub fn new(buckets: Buckets) -> Result<Self> {
+        let bounds = buckets.bounds()?;
+        Ok(Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            sum: 0.0,
+        })
+    }
+
+    /// Count a value in the first bucket whose upper bound is at least `value`
+    ///
+    /// Non-finite values are ignored.
+    pub fn record(&mut self, value: f64) {
+        self.record_n(value, 1);
+    }
+
+    /// Count a value `n` times
+    pub fn record_n(&mut self, value: f64, n: u64) {
+        if !value.is_finite() {
+            return;
+        }
+        let index = self.bounds.partition_point(|&bound| bound < value);
+        self.counts[index] += n;
+        self.sum += value * n as f64;
+    }
+
+    /// Add another histogram's counts; both must have identical bounds
+    pub fn merge(&mut self, other: &Histogram) -> Result<()> {
+        if self.bounds != other.bounds {
+            return Err(Error::metrics(
+                "Cannot merge histograms with different buckets",
+            ));
+        }
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.sum += other.sum;
+        Ok(())
+    }
+
+    /// Bucket upper bounds, excluding the overflow bucket
+    pub fn bounds(&self) -> &[f64] {
+        &self.bounds
+    }
+
+    /// `(upper bound, count)` per bucket; the overflow bucket's bound is infinity
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.bounds
+            .iter()
+            .copied()
+            .chain(std::iter::once(f64::INFINITY))
+            .zip(self.counts.iter().copied())
+    }
+
+    /// Total number of recorded values
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Sum of recorded values
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Mean of recorded values
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        (count > 0).then(|| self.sum / count as f64)
+    }
+
+    /// Share of values in each bucket, for comparing runs of different sizes
+    pub fn fractions(&self) -> Vec<f64> {
+        let count = self.count();
+        self.counts
+            .iter()
+            .map(|&c| {
+                if count == 0 {
+                    0.0
+                } else {
+                    c as f64 / count as f64
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_layouts() {
+        // Test: Each layout produces the expected bounds and rejects bad input
+        let linear = Buckets::Linear {
+            start: 0.0,
+            width: 10.0,
+            count: 3,
+        };
+        assert_eq!(linear.bounds().unwrap(), vec![10.0, 20.0, 30.0]);
+        let exponential = Buckets::Exponential {
+            start: 1.0,
+            factor: 10.0,
+            count: 4,
+        };
+        assert_eq!(
+            exponential.bounds().unwrap(),
+            vec![1.0, 10.0, 100.0, 1000.0]
+        );
+        assert!(
+            Buckets::Custom {
+                bounds: vec![5.0, 1.0]
+            }
+            .bounds()
+            .is_err(),
+            "Decreasing bounds should be rejected"
+        );
+        assert!(Buckets::Custom { bounds: vec![] }.bounds().is_err());
+    }
+
+    #[test]
+    fn test_record_merge_and_serialize() {
+        // Test: Values land in the right buckets and histograms merge and round-trip
+        let buckets = Buckets::Custom {
+            bounds: vec![100.0, 250.0, 1000.0],
+        };
+        let mut first = Histogram::new(buckets.clone()).unwrap();
+        for latency in [50.0, 100.0, 120.0, 5000.0, f64::NAN] {
+            first.record(latency);
+        }
+        let mut second = Histogram::new(buckets).unwrap();
+        second.record_n(300.0, 4);
+
+        first.merge(&second).unwrap();
+        let buckets: Vec<(f64, u64)> = first.buckets().collect();
+        assert_eq!(
+            buckets,
+            vec![(100.0, 2), (250.0, 1), (1000.0, 4), (f64::INFINITY, 1)],
+            "Bounds are inclusive upper limits"
+        );
+        assert_eq!(first.count(), 8);
+        assert_eq!(first.mean(), Some(6470.0 / 8.0));
+        assert_eq!(first.fractions()[2], 0.5);
+
+        let json = serde_json::to_string(&first).unwrap();
+        assert_eq!(serde_json::from_str::<Histogram>(&json).unwrap(), first);
+
+        let other = Histogram::new(Buckets::Linear {
+            start: 0.0,
+            width: 1.0,
+            count: 2,
+        })
+        .unwrap();
+        assert!(
+            first.merge(&other).is_err(),
+            "Mismatched buckets should not merge"
+        );
+    }
+}
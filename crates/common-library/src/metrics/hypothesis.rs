@@ -0,0 +1,241 @@
+//! Hypothesis tests for comparing cohorts
+//!
+//! Each test returns its statistic, a two-sided p-value and an effect size,
+//! so a comparison such as "selected projects have more contributors than
+//! rejected ones" reports both whether the difference is likely real and
+//! how large it is.
+
+use super::statistical::{normal_cdf, regularized_beta, regularized_gamma_p};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Outcome of a hypothesis test
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    pub statistic: f64,
+    /// Two-sided p-value
+    pub p_value: f64,
+    pub degrees_of_freedom: Option<f64>,
+    /// Cohen's d, rank-biserial correlation or Cramér's V, depending on the test
+    pub effect_size: f64,
+}
+
+impl TestResult {
+    /// Whether the result is significant at level `alpha`, e.g. 0.05
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// Welch's unequal-variance t-test on the means of two samples
+///
+/// The effect size is Cohen's d using the average of the two variances.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Result<TestResult> {
+    check_sample(a)?;
+    check_sample(b)?;
+    let (mean_a, var_a) = mean_and_variance(a);
+    let (mean_b, var_b) = mean_and_variance(b);
+    let (se_a, se_b) = (var_a / a.len() as f64, var_b / b.len() as f64);
+    let standard_error = (se_a + se_b).sqrt();
+    if standard_error == 0.0 {
+        return Err(Error::metrics("Both samples have zero variance"));
+    }
+
+    let t = (mean_a - mean_b) / standard_error;
+    let df = (se_a + se_b).powi(2)
+        / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+    Ok(TestResult {
+        statistic: t,
+        p_value: regularized_beta(df / 2.0, 0.5, df / (df + t * t)),
+        degrees_of_freedom: Some(df),
+        effect_size: (mean_a - mean_b) / ((var_a + var_b) / 2.0).sqrt(),
+    })
+}
+
+/// Mann-Whitney U test that values in `a` tend to differ from those in `b`
+///
+/// Uses the normal approximation with tie and continuity corrections. The
+/// statistic is U for `a`; the effect size is the rank-biserial correlation,
+/// positive when `a` tends to be larger.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Result<TestResult> {
+    check_sample(a)?;
+    check_sample(b)?;
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let mut combined: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&v| (v, true))
+        .chain(b.iter().map(|&v| (v, false)))
+        .collect();
+    combined.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut start = 0;
+    while start < combined.len() {
+        let mut end = start;
+        while end + 1 < combined.len() && combined[end + 1].0 == combined[start].0 {
+            end += 1;
+        }
+        let tied = (end - start + 1) as f64;
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        rank_sum_a += average_rank
+            * combined[start..=end]
+                .iter()
+                .filter(|(_, in_a)| *in_a)
+                .count() as f64;
+        tie_correction += tied.powi(3) - tied;
+        start = end + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let n = n1 + n2;
+    let mean = n1 * n2 / 2.0;
+    let sd = (n1 * n2 / 12.0 * ((n + 1.0) - tie_correction / (n * (n - 1.0)))).sqrt();
+    let p_value = if sd == 0.0 {
+        1.0
+    } else {
+        let z = ((u - mean).abs() - 0.5).max(0.0) / sd;
+        (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+    };
+    Ok(TestResult {
+        statistic: u,
+        p_value,
+        degrees_of_freedom: None,
+        effect_size: 2.0 * u / (n1 * n2) - 1.0,
+    })
+}
+
+/// Chi-square test of independence on a contingency table of counts
+///
+/// Rows and columns are e.g. cohort and category. The effect size is
+/// Cramér's V.
+pub fn chi_square_test(observed: &[Vec<f64>]) -> Result<TestResult> {
+    let columns = observed.first().map_or(0, Vec::len);
+    if observed.len() < 2 || columns < 2 || observed.iter().any(|row| row.len() != columns) {
+        return Err(Error::metrics(
+            "Contingency table needs at least 2 rows and 2 columns of equal length",
+        ));
+    }
+    if observed
+        .iter()
+        .flatten()
+        .any(|v| !v.is_finite() || *v < 0.0)
+    {
+        return Err(Error::metrics("Counts must be finite and non-negative"));
+    }
+    let row_totals: Vec<f64> = observed.iter().map(|row| row.iter().sum()).collect();
+    let column_totals: Vec<f64> = (0..columns)
+        .map(|j| observed.iter().map(|row| row[j]).sum())
+        .collect();
+    let total: f64 = row_totals.iter().sum();
+    if row_totals.iter().chain(&column_totals).any(|t| *t == 0.0) {
+        return Err(Error::metrics(
+            "Every row and column needs a non-zero total",
+        ));
+    }
+
+    let mut statistic = 0.0;
+    for (row, row_total) in observed.iter().zip(&row_totals) {
+        for (value, column_total) in row.iter().zip(&column_totals) {
+            let expected = row_total * column_total / total;
+            statistic += (value - expected).powi(2) / expected;
+        }
+    }
+    let df = ((observed.len() - 1) * (columns - 1)) as f64;
+    let smaller_dimension = (observed.len().min(columns) - 1) as f64;
+    Ok(TestResult {
+        statistic,
+        p_value: 1.0 - regularized_gamma_p(df / 2.0, statistic / 2.0),
+        degrees_of_freedom: Some(df),
+        effect_size: (statistic / (total * smaller_dimension)).sqrt(),
+    })
+}
+
+fn check_sample(sample: &[f64]) -> Result<()> {
+    if sample.len() < 2 {
+        return Err(Error::metrics("Each sample needs at least 2 values"));
+    }
+    if sample.iter().any(|v| !v.is_finite()) {
+        return Err(Error::metrics("Samples must not contain non-finite values"));
+    }
+    Ok(())
+}
+
+/// Mean and sample variance
+fn mean_and_variance(sample: &[f64]) -> (f64, f64) {
+    let n = sample.len() as f64;
+    let mean = sample.iter().sum::<f64>() / n;
+    let variance = sample.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_welch_t_test_matches_reference() {
+        // Test: Statistic, degrees of freedom and p-value match a reference calculation
+        let a = [
+            27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7,
+            21.4,
+        ];
+        let b = [
+            27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5,
+            24.4,
+        ];
+        let result = welch_t_test(&a, &b).unwrap();
+        assert_close(result.statistic, -2.46, 0.01);
+        assert_close(result.degrees_of_freedom.unwrap(), 24.99, 0.01);
+        assert_close(result.p_value, 0.021, 0.001);
+        assert!(result.is_significant(0.05));
+        assert!(result.effect_size < 0.0, "a has the smaller mean");
+        assert!(welch_t_test(&[1.0, 1.0], &[1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_mann_whitney_u() {
+        // Test: Separated samples give an extreme U and small p; identical ones are not significant
+        let low = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let high = [11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0];
+        let result = mann_whitney_u(&low, &high).unwrap();
+        assert_eq!(result.statistic, 0.0);
+        assert_eq!(result.effect_size, -1.0);
+        assert!(result.p_value < 0.001, "p was {}", result.p_value);
+
+        let same = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0], &[1.0, 2.0, 2.0, 3.0]).unwrap();
+        assert_eq!(same.effect_size, 0.0);
+        assert!(same.p_value > 0.9);
+    }
+
+    #[test]
+    fn test_chi_square_independence() {
+        // Test: The chi-square statistic and p-value match a known 2x2 table
+        let table = vec![vec![20.0, 30.0], vec![30.0, 20.0]];
+        let result = chi_square_test(&table).unwrap();
+        assert_close(result.statistic, 4.0, 1e-9);
+        assert_eq!(result.degrees_of_freedom, Some(1.0));
+        assert_close(result.p_value, 0.0455, 0.0001);
+        assert_close(result.effect_size, 0.2, 1e-9);
+        assert!(chi_square_test(&[vec![1.0, 2.0]]).is_err());
+        assert!(chi_square_test(&[vec![0.0, 0.0], vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    fn test_special_functions() {
+        // Test: Normal CDF and incomplete gamma agree with known values
+        assert_close(normal_cdf(1.959964), 0.975, 1e-6);
+        assert_close(normal_cdf(-1.0), 0.158655, 1e-6);
+        assert_close(regularized_gamma_p(1.0, 2.0), 1.0 - (-2.0f64).exp(), 1e-12);
+        assert_close(regularized_beta(2.0, 3.0, 0.4), 0.5248, 1e-9);
+    }
+}
@@ -0,0 +1,233 @@
+//! Filling gaps in time series
+//!
+//! Collection runs get skipped, leaving gaps that break rolling windows and
+//! period-over-period comparisons. [`TimeSeries::impute`] inserts points
+//! wherever consecutive observations are more than one expected interval
+//! apart, and flags each inserted point so later analysis can discount it.
+
+use super::timeseries::TimeSeries;
+use crate::error::{Error, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How missing values are estimated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImputationMethod {
+    /// Repeat the last observed value
+    ForwardFill,
+    /// Interpolate between the observations on either side
+    Linear,
+    /// Copy the value `period` intervals earlier, e.g. 7 for weekly
+    /// patterns in daily data; falls back to linear without that history
+    Seasonal { period: usize },
+}
+
+/// A series point that may have been imputed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ImputedPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+    pub imputed: bool,
+}
+
+/// A gap-free series with imputed points flagged
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImputedSeries {
+    pub points: Vec<ImputedPoint>,
+}
+
+impl ImputedSeries {
+    /// Number of imputed points
+    pub fn imputed_count(&self) -> usize {
+        self.points.iter().filter(|point| point.imputed).count()
+    }
+
+    /// Drop the flags, keeping observed and imputed values
+    ///
+    /// Fails if a point was given a non-finite value.
+    pub fn to_series(&self) -> Result<TimeSeries> {
+        TimeSeries::new(
+            self.points
+                .iter()
+                .map(|point| (point.timestamp, point.value))
+                .collect(),
+        )
+    }
+
+    /// Value within half an interval of `timestamp`
+    fn value_near(&self, timestamp: DateTime<Utc>, tolerance: TimeDelta) -> Option<f64> {
+        let index = self
+            .points
+            .partition_point(|point| point.timestamp < timestamp - tolerance);
+        self.points
+            .get(index)
+            .filter(|point| point.timestamp <= timestamp + tolerance)
+            .map(|point| point.value)
+    }
+}
+
+impl TimeSeries {
+    /// Fill gaps longer than `interval` using `method`
+    ///
+    /// Inserted points are spaced `interval` apart after the observation
+    /// preceding the gap. Nothing is added before the first or after the
+    /// last observation.
+    pub fn impute(&self, interval: TimeDelta, method: ImputationMethod) -> Result<ImputedSeries> {
+        if interval <= TimeDelta::zero() {
+            return Err(Error::metrics("Imputation interval must be positive"));
+        }
+        let lag = match method {
+            ImputationMethod::Seasonal { period: 0 } => {
+                return Err(Error::metrics("Seasonal period must be at least 1"));
+            }
+            ImputationMethod::Seasonal { period } => Some(
+                i32::try_from(period)
+                    .ok()
+                    .and_then(|period| interval.checked_mul(period))
+                    // `checked_mul` only guards the seconds field against `i64`
+                    .filter(|lag| *lag <= TimeDelta::MAX)
+                    .ok_or_else(|| {
+                        Error::metrics(format!(
+                            "Seasonal period of {} intervals is too long",
+                            period
+                        ))
+                    })?,
+            ),
+            _ => None,
+        };
+        let tolerance = interval / 2;
+        let mut output = ImputedSeries::default();
+        let mut points = self.points().iter().copied().peekable();
+        while let Some((timestamp, value)) = points.next() {
+            output.points.push(ImputedPoint {
+                timestamp,
+                value,
+                imputed: false,
+            });
+            let Some(&(next_time, next_value)) = points.peek() else {
+                break;
+            };
+            let span = (next_time - timestamp).num_milliseconds() as f64;
+            let mut missing_time = timestamp.checked_add_signed(interval);
+            while let Some(time) = missing_time.filter(|&time| time < next_time - tolerance) {
+                let fraction = (time - timestamp).num_milliseconds() as f64 / span;
+                // Weighted form cannot overflow for finite endpoints
+                let linear = value * (1.0 - fraction) + next_value * fraction;
+                let estimate = match (method, lag) {
+                    (ImputationMethod::ForwardFill, _) => value,
+                    (ImputationMethod::Seasonal { .. }, Some(lag)) => time
+                        .checked_sub_signed(lag)
+                        .and_then(|earlier| output.value_near(earlier, tolerance))
+                        .unwrap_or(linear),
+                    _ => linear,
+                };
+                output.points.push(ImputedPoint {
+                    timestamp: time,
+                    value: estimate,
+                    imputed: true,
+                });
+                missing_time = time.checked_add_signed(interval);
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 8, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_forward_fill_and_linear() {
+        // Test: Gaps are filled at the expected interval and flagged
+        let series = TimeSeries::new(vec![(day(1), 10.0), (day(4), 40.0), (day(5), 50.0)]).unwrap();
+
+        let filled = series
+            .impute(TimeDelta::days(1), ImputationMethod::ForwardFill)
+            .unwrap();
+        assert_eq!(
+            filled.to_series().unwrap().values(),
+            vec![10.0, 10.0, 10.0, 40.0, 50.0]
+        );
+        assert_eq!(filled.imputed_count(), 2);
+        assert!(filled.points[1].imputed && !filled.points[3].imputed);
+
+        let linear = series
+            .impute(TimeDelta::days(1), ImputationMethod::Linear)
+            .unwrap();
+        assert_eq!(
+            linear.to_series().unwrap().values(),
+            vec![10.0, 20.0, 30.0, 40.0, 50.0]
+        );
+        assert!(
+            series
+                .impute(TimeDelta::zero(), ImputationMethod::Linear)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_seasonal_imputation() {
+        // Test: Seasonal imputation copies the value one period back, else interpolates
+        let pattern = [5.0, 1.0, 9.0];
+        let mut points: Vec<_> = (1..=6)
+            .map(|d| (day(d), pattern[(d as usize - 1) % 3]))
+            .collect();
+        points.push((day(9), 9.0));
+        let series = TimeSeries::new(points).unwrap();
+
+        let filled = series
+            .impute(TimeDelta::days(1), ImputationMethod::Seasonal { period: 3 })
+            .unwrap();
+        assert_eq!(
+            filled.to_series().unwrap().values(),
+            vec![5.0, 1.0, 9.0, 5.0, 1.0, 9.0, 5.0, 1.0, 9.0]
+        );
+
+        let short = TimeSeries::new(vec![(day(1), 0.0), (day(3), 2.0)]).unwrap();
+        let filled = short
+            .impute(TimeDelta::days(1), ImputationMethod::Seasonal { period: 7 })
+            .unwrap();
+        assert_eq!(
+            filled.points[1].value, 1.0,
+            "No history falls back to linear"
+        );
+    }
+
+    #[test]
+    fn test_extreme_inputs_are_errors_not_panics() {
+        // Test: Oversized periods and non-finite points fail cleanly
+        let series = TimeSeries::new(vec![(day(1), 0.0), (day(3), 2.0)]).unwrap();
+        let period = i32::MAX as usize + 1;
+        assert!(
+            series
+                .impute(TimeDelta::days(1), ImputationMethod::Seasonal { period })
+                .is_err()
+        );
+        assert!(
+            series
+                .impute(
+                    TimeDelta::weeks(10_000),
+                    ImputationMethod::Seasonal { period: 1 << 30 }
+                )
+                .is_err(),
+            "Lag overflows TimeDelta"
+        );
+
+        let extremes = TimeSeries::new(vec![(day(1), -1e308), (day(3), 1e308)]).unwrap();
+        let filled = extremes
+            .impute(TimeDelta::days(1), ImputationMethod::Linear)
+            .unwrap();
+        assert!(filled.to_series().is_ok(), "Interpolation stays finite");
+
+        let mut edited = filled;
+        edited.points[1].value = f64::INFINITY;
+        assert!(edited.to_series().is_err());
+    }
+}
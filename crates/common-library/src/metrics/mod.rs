@@ -0,0 +1,68 @@
+//! Metrics functionality for the common library
+//!
+//! Provides timestamped series with rolling-window statistics, counter
+//! conversions, gap imputation and downsampling, streaming and exponentially
+//! weighted statistics, quantile sketches, histograms, density estimates,
+//! hypothesis tests, regression, seasonal decomposition, changepoint detection,
+//! elapsed-time growth rates, forecasts, contributor concentration,
+//! normalization and composite scoring for collected metrics, plus
+//! benchmarking, SLO tracking and export of internal metrics to StatsD or OTLP
+//! collectors. Results can be gathered into versioned reports.
+
+pub mod autoregressive;
+pub mod benchmark;
+pub mod changepoints;
+pub mod concentration;
+pub mod counters;
+pub mod decomposition;
+pub mod downsampling;
+pub mod ewma;
+pub mod export;
+pub mod forecasting;
+pub mod growth;
+pub mod histogram;
+pub mod hypothesis;
+pub mod imputation;
+pub mod normalization;
+pub mod regression;
+pub mod report;
+pub mod rolling;
+pub mod scoring;
+pub mod slo;
+pub mod statistical;
+pub mod timeseries;
+
+pub use autoregressive::ArForecaster;
+pub use benchmark::{
+    Benchmark, BenchmarkBaseline, BenchmarkComparison, BenchmarkConfig, BenchmarkResult,
+};
+pub use changepoints::{Changepoint, detect_changepoints};
+pub use concentration::ContributorConcentration;
+pub use decomposition::{Decomposition, decompose};
+pub use ewma::{EwmVar, Ewma, HalfLife};
+#[cfg(feature = "http")]
+pub use export::OtlpExporter;
+pub use export::{
+    MetricsExporter, MetricsRegistry, MetricsSnapshot, StatsdExporter, start_exporter,
+};
+pub use forecasting::{
+    CandidateModel, FittedModel, Forecast, ForecastPoint, ForecastResult, Forecaster,
+    ResidualDiagnostics, SmoothingModel, select_model,
+};
+pub use growth::GrowthPeriod;
+pub use histogram::{Buckets, Histogram};
+pub use hypothesis::{TestResult, chi_square_test, mann_whitney_u, welch_t_test};
+pub use imputation::{ImputationMethod, ImputedPoint, ImputedSeries};
+pub use normalization::{FittedPipeline, FittedStep, NormalizationPipeline, NormalizationStep};
+pub use regression::{RegressionCalculator, RegressionResult};
+pub use report::{MetricsReport, MetricsReportBuilder, REPORT_SCHEMA_VERSION};
+pub use rolling::{Rolling, RollingWindow};
+pub use scoring::{
+    ComponentScore, ProjectScore, ScoreComponent, ScoreConfig, ScoreEngine, ScoreTransform,
+};
+pub use slo::{SloEvent, SloObjective, SloStatus, SloTracker};
+pub use statistical::{
+    Bandwidth, CorrelationMethod, Distribution, DistributionFit, KernelDensity,
+    StreamingStatistics, TDigest, correlation_matrix, covariance_matrix, fit_distributions,
+};
+pub use timeseries::TimeSeries;
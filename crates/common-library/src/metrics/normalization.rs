@@ -0,0 +1,209 @@
+//! Fitted normalization pipelines
+//!
+//! A [`NormalizationPipeline`] lists steps such as winsorize → log → z-score.
+//! Fitting it to a cohort records each step's parameters (cut-offs, mean,
+//! standard deviation) in a [`FittedPipeline`], which serializes alongside
+//! scoring results and applies the identical transform to projects scored
+//! later.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// A normalization step as declared in configuration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NormalizationStep {
+    /// Clamp to the cohort's `lower` and `upper` quantiles, in `[0, 1]`
+    Winsorize { lower: f64, upper: f64 },
+    /// `ln(1 + x)`; negatives are treated as 0
+    Log1p,
+    /// Subtract the mean and divide by the standard deviation
+    ZScore,
+    /// Map the cohort's range onto 0–1
+    MinMax,
+}
+
+/// A step with the parameters learned from the fitting cohort
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FittedStep {
+    Winsorize { min: f64, max: f64 },
+    Log1p,
+    ZScore { mean: f64, std_dev: f64 },
+    MinMax { min: f64, max: f64 },
+}
+
+impl FittedStep {
+    /// Apply the step to one value
+    pub fn apply(&self, value: f64) -> f64 {
+        match *self {
+            FittedStep::Winsorize { min, max } => value.clamp(min, max),
+            FittedStep::Log1p => value.max(0.0).ln_1p(),
+            FittedStep::ZScore { mean, std_dev } => {
+                if std_dev > 0.0 {
+                    (value - mean) / std_dev
+                } else {
+                    0.0
+                }
+            }
+            FittedStep::MinMax { min, max } => {
+                if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Ordered normalization steps to fit to a cohort
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NormalizationPipeline {
+    pub steps: Vec<NormalizationStep>,
+}
+
+impl NormalizationPipeline {
+    /// A pipeline running `steps` in order
+    pub fn new(steps: Vec<NormalizationStep>) -> Self {
+        Self { steps }
+    }
+
+    /// Learn each step's parameters from `values`, feeding every step the previous step's output
+    pub fn fit(&self, values: &[f64]) -> Result<FittedPipeline> {
+        if values.is_empty() {
+            return Err(Error::metrics("Cannot fit normalization to no values"));
+        }
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(Error::metrics(
+                "Cannot fit normalization to non-finite values",
+            ));
+        }
+
+        let mut data = values.to_vec();
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let fitted = match *step {
+                NormalizationStep::Winsorize { lower, upper } => {
+                    if !(0.0..=1.0).contains(&lower)
+                        || !(0.0..=1.0).contains(&upper)
+                        || lower > upper
+                    {
+                        return Err(Error::metrics(
+                            "Winsorize quantiles must satisfy 0 <= lower <= upper <= 1",
+                        ));
+                    }
+                    let mut sorted = data.clone();
+                    sorted.sort_by(f64::total_cmp);
+                    FittedStep::Winsorize {
+                        min: quantile(&sorted, lower),
+                        max: quantile(&sorted, upper),
+                    }
+                }
+                NormalizationStep::Log1p => FittedStep::Log1p,
+                NormalizationStep::ZScore => {
+                    let n = data.len() as f64;
+                    let mean = data.iter().sum::<f64>() / n;
+                    let variance = data.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+                    FittedStep::ZScore {
+                        mean,
+                        std_dev: variance.sqrt(),
+                    }
+                }
+                NormalizationStep::MinMax => FittedStep::MinMax {
+                    min: data.iter().copied().fold(f64::INFINITY, f64::min),
+                    max: data.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                },
+            };
+            for value in data.iter_mut() {
+                *value = fitted.apply(*value);
+            }
+            steps.push(fitted);
+        }
+        Ok(FittedPipeline { steps })
+    }
+}
+
+/// A pipeline with learned parameters, ready to apply to new data
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FittedPipeline {
+    pub steps: Vec<FittedStep>,
+}
+
+impl FittedPipeline {
+    /// Normalize one value
+    pub fn transform(&self, value: f64) -> f64 {
+        self.steps
+            .iter()
+            .fold(value, |value, step| step.apply(value))
+    }
+
+    /// Normalize many values
+    pub fn transform_all(&self, values: &[f64]) -> Vec<f64> {
+        values.iter().map(|value| self.transform(*value)).collect()
+    }
+}
+
+/// Linearly interpolated quantile of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_and_reapply_pipeline() {
+        // Test: Fitted parameters come from the cohort and are reused for new values
+        let pipeline: NormalizationPipeline = serde_json::from_value(serde_json::json!({
+            "steps": [
+                {"type": "winsorize", "lower": 0.0, "upper": 0.75},
+                {"type": "log1p"},
+                {"type": "z_score"}
+            ]
+        }))
+        .unwrap();
+        let cohort = [0.0, 9.0, 99.0, 999.0, 1_000_000.0];
+        let fitted = pipeline.fit(&cohort).unwrap();
+        assert_eq!(
+            fitted.steps[0],
+            FittedStep::Winsorize {
+                min: 0.0,
+                max: 999.0
+            }
+        );
+
+        let normalized = fitted.transform_all(&cohort);
+        let mean = normalized.iter().sum::<f64>() / normalized.len() as f64;
+        assert!(mean.abs() < 1e-12, "Cohort should be centred");
+        assert_eq!(
+            fitted.transform(5_000_000.0),
+            normalized[4],
+            "Values beyond the cohort are capped at the fitted cut-off"
+        );
+
+        let json = serde_json::to_string(&fitted).unwrap();
+        let restored: FittedPipeline = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.transform(42.0), fitted.transform(42.0));
+    }
+
+    #[test]
+    fn test_degenerate_cohorts() {
+        // Test: Constant cohorts normalize to 0 and invalid input is rejected
+        let pipeline =
+            NormalizationPipeline::new(vec![NormalizationStep::MinMax, NormalizationStep::ZScore]);
+        let fitted = pipeline.fit(&[3.0, 3.0]).unwrap();
+        assert_eq!(fitted.transform(10.0), 0.0);
+        assert!(pipeline.fit(&[]).is_err());
+        assert!(pipeline.fit(&[f64::NAN]).is_err());
+        let bad = NormalizationPipeline::new(vec![NormalizationStep::Winsorize {
+            lower: 0.9,
+            upper: 0.1,
+        }]);
+        assert!(bad.fit(&[1.0, 2.0]).is_err());
+    }
+}
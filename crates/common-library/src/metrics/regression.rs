@@ -0,0 +1,277 @@
+//! Ordinary least squares regression
+//!
+//! [`RegressionCalculator`] fits simple, multiple and polynomial linear
+//! models by Householder QR decomposition, which stays stable for the
+//! poorly conditioned designs that polynomial terms produce.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+
+/// Coefficients and goodness of fit of a least squares model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionResult {
+    /// Intercept term, zero when fitted without one
+    pub intercept: f64,
+    /// One coefficient per feature; for polynomials, the x, x², ... terms
+    pub coefficients: Vec<f64>,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    /// Observed minus fitted value per observation
+    pub residuals: Vec<f64>,
+}
+
+impl RegressionResult {
+    /// Predicted value for one row of features
+    pub fn predict(&self, features: &[f64]) -> f64 {
+        self.intercept
+            + self
+                .coefficients
+                .iter()
+                .zip(features)
+                .map(|(b, x)| b * x)
+                .sum::<f64>()
+    }
+
+    /// Predicted value at `x` for a model from [`RegressionCalculator::polynomial`]
+    pub fn predict_polynomial(&self, x: f64) -> f64 {
+        self.predict(&powers(x, self.coefficients.len()))
+    }
+}
+
+/// Fits linear models by least squares
+#[derive(Debug, Clone)]
+pub struct RegressionCalculator {
+    fit_intercept: bool,
+}
+
+impl Default for RegressionCalculator {
+    fn default() -> Self {
+        Self {
+            fit_intercept: true,
+        }
+    }
+}
+
+impl RegressionCalculator {
+    /// A calculator that fits an intercept
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to fit an intercept term
+    pub fn with_intercept(mut self, fit_intercept: bool) -> Self {
+        self.fit_intercept = fit_intercept;
+        self
+    }
+
+    /// Fit `y = a + b·x`
+    pub fn simple(&self, x: &[f64], y: &[f64]) -> Result<RegressionResult> {
+        let rows: Vec<Vec<f64>> = x.iter().map(|&x| vec![x]).collect();
+        self.multiple(&rows, y)
+    }
+
+    /// Fit `y = a + b1·x1 + ... + bk·xk`, with one row of features per observation
+    pub fn multiple(&self, rows: &[Vec<f64>], y: &[f64]) -> Result<RegressionResult> {
+        if rows.len() != y.len() {
+            return Err(Error::metrics(format!(
+                "Got {} feature rows for {} observations",
+                rows.len(),
+                y.len()
+            )));
+        }
+        let features = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != features) {
+            return Err(Error::metrics("Feature rows have differing lengths"));
+        }
+        if rows.iter().flatten().chain(y).any(|v| !v.is_finite()) {
+            return Err(Error::metrics("Cannot regress non-finite values"));
+        }
+        let parameters = features + usize::from(self.fit_intercept);
+        if parameters == 0 || y.len() <= parameters {
+            return Err(Error::metrics(format!(
+                "Need more than {} observations to fit {} parameters",
+                parameters, parameters
+            )));
+        }
+
+        let mut columns: Vec<Vec<f64>> = Vec::with_capacity(parameters);
+        if self.fit_intercept {
+            columns.push(vec![1.0; y.len()]);
+        }
+        columns.extend((0..features).map(|j| rows.iter().map(|row| row[j]).collect()));
+        let mut solution = least_squares(columns, y.to_vec())?;
+
+        let intercept = if self.fit_intercept {
+            solution.remove(0)
+        } else {
+            0.0
+        };
+        let mut result = RegressionResult {
+            intercept,
+            coefficients: solution,
+            r_squared: 0.0,
+            adjusted_r_squared: 0.0,
+            residuals: Vec::new(),
+        };
+        result.residuals = rows
+            .iter()
+            .zip(y)
+            .map(|(row, y)| y - result.predict(row))
+            .collect();
+
+        let n = y.len() as f64;
+        let center = if self.fit_intercept {
+            y.iter().sum::<f64>() / n
+        } else {
+            0.0
+        };
+        let total: f64 = y.iter().map(|y| (y - center).powi(2)).sum();
+        let residual: f64 = result.residuals.iter().map(|r| r * r).sum();
+        result.r_squared = if total == 0.0 {
+            1.0
+        } else {
+            1.0 - residual / total
+        };
+        let dof_total = if self.fit_intercept { n - 1.0 } else { n };
+        result.adjusted_r_squared =
+            1.0 - (1.0 - result.r_squared) * dof_total / (n - parameters as f64);
+        Ok(result)
+    }
+
+    /// Fit `y = a + b1·x + b2·x² + ... + bd·x^d`
+    pub fn polynomial(&self, x: &[f64], y: &[f64], degree: usize) -> Result<RegressionResult> {
+        if degree == 0 {
+            return Err(Error::metrics("Polynomial degree must be at least 1"));
+        }
+        let rows: Vec<Vec<f64>> = x.iter().map(|&x| powers(x, degree)).collect();
+        self.multiple(&rows, y)
+    }
+}
+
+/// `[x, x², ..., x^degree]`
+fn powers(x: f64, degree: usize) -> Vec<f64> {
+    (1..=degree as i32).map(|p| x.powi(p)).collect()
+}
+
+/// Solve `min |A·b - y|` for column-major `A` via Householder QR
+fn least_squares(mut columns: Vec<Vec<f64>>, mut y: Vec<f64>) -> Result<Vec<f64>> {
+    let n = y.len();
+    let k = columns.len();
+    let scale = columns
+        .iter()
+        .flatten()
+        .fold(0.0f64, |max, v| max.max(v.abs()));
+
+    for j in 0..k {
+        let norm = columns[j][j..].iter().map(|v| v * v).sum::<f64>().sqrt();
+        if norm <= scale * 1e-12 * n as f64 {
+            return Err(Error::metrics(
+                "Features are linearly dependent; the model cannot be fitted",
+            ));
+        }
+        let alpha = if columns[j][j] > 0.0 { -norm } else { norm };
+        let mut v: Vec<f64> = columns[j][j..].to_vec();
+        v[0] -= alpha;
+        let v_norm_sq: f64 = v.iter().map(|x| x * x).sum();
+
+        let reflect = |target: &mut [f64]| {
+            let dot: f64 = v.iter().zip(target.iter()).map(|(a, b)| a * b).sum();
+            let factor = 2.0 * dot / v_norm_sq;
+            for (t, vi) in target.iter_mut().zip(&v) {
+                *t -= factor * vi;
+            }
+        };
+        for column in columns.iter_mut().skip(j) {
+            reflect(&mut column[j..]);
+        }
+        reflect(&mut y[j..]);
+    }
+
+    let mut solution = vec![0.0; k];
+    for i in (0..k).rev() {
+        let known: f64 = (i + 1..k).map(|j| columns[j][i] * solution[j]).sum();
+        solution[i] = (y[i] - known) / columns[i][i];
+    }
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-8,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_simple_and_multiple_regression() {
+        // Test: Exact linear data is recovered with R² of 1; noisy data scores lower
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let y: Vec<f64> = x.iter().map(|x| 3.0 + 2.0 * x).collect();
+        let fit = RegressionCalculator::new().simple(&x, &y).unwrap();
+        assert_close(fit.intercept, 3.0);
+        assert_close(fit.coefficients[0], 2.0);
+        assert_close(fit.r_squared, 1.0);
+        assert!(fit.residuals.iter().all(|r| r.abs() < 1e-9));
+
+        let rows = vec![
+            vec![1.0, 0.0],
+            vec![0.0, 1.0],
+            vec![1.0, 1.0],
+            vec![2.0, 1.0],
+            vec![1.0, 3.0],
+            vec![3.0, 2.0],
+        ];
+        let y: Vec<f64> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, r)| 1.0 + 0.5 * r[0] - 2.0 * r[1] + if i % 2 == 0 { 0.1 } else { -0.1 })
+            .collect();
+        let fit = RegressionCalculator::new().multiple(&rows, &y).unwrap();
+        assert!((fit.coefficients[1] + 2.0).abs() < 0.1);
+        assert!(fit.r_squared > 0.99 && fit.r_squared < 1.0);
+        assert!(fit.adjusted_r_squared < fit.r_squared);
+    }
+
+    #[test]
+    fn test_polynomial_and_no_intercept() {
+        // Test: Quadratic data fits exactly and models can be forced through the origin
+        let x: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let y: Vec<f64> = x.iter().map(|x| 1.0 - x + 0.5 * x * x).collect();
+        let fit = RegressionCalculator::new().polynomial(&x, &y, 2).unwrap();
+        assert_close(fit.intercept, 1.0);
+        assert_close(fit.coefficients[1], 0.5);
+        assert_close(fit.predict_polynomial(20.0), 181.0);
+
+        let fit = RegressionCalculator::new()
+            .with_intercept(false)
+            .simple(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0])
+            .unwrap();
+        assert_eq!(fit.intercept, 0.0);
+        assert_close(fit.coefficients[0], 2.0);
+    }
+
+    #[test]
+    fn test_regression_rejects_degenerate_input() {
+        // Test: Too few observations, mismatched lengths and collinear features are errors
+        let calculator = RegressionCalculator::new();
+        assert!(calculator.simple(&[1.0, 2.0], &[1.0, 2.0]).is_err());
+        assert!(calculator.simple(&[1.0, 2.0, 3.0], &[1.0]).is_err());
+        assert!(
+            calculator
+                .simple(&[2.0, 2.0, 2.0, 2.0], &[1.0, 2.0, 3.0, 4.0])
+                .is_err(),
+            "A constant feature is collinear with the intercept"
+        );
+        assert!(
+            calculator
+                .polynomial(&[1.0, 2.0, 3.0], &[1.0; 3], 0)
+                .is_err()
+        );
+    }
+}
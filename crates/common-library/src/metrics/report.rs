@@ -0,0 +1,232 @@
+//! Versioned metrics reports
+//!
+//! A [`MetricsReport`] gathers the results of several analyses, e.g.
+//! summary statistics, a trend fit and growth rates, into one document with
+//! a schema version. Each section holds the serialized form of any result
+//! type, so consumers read reports as JSON, or as flat CSV rows, instead of
+//! assembling output by hand.
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Version written to new reports; bumped when the layout changes incompatibly
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Named analysis results about one subject
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsReport {
+    pub schema_version: u32,
+    /// What the report describes, e.g. a repository name
+    pub subject: String,
+    pub generated_at: DateTime<Utc>,
+    pub sections: BTreeMap<String, Value>,
+}
+
+impl MetricsReport {
+    pub fn builder(subject: impl Into<String>) -> MetricsReportBuilder {
+        MetricsReportBuilder {
+            subject: subject.into(),
+            generated_at: Utc::now(),
+            sections: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    /// Deserialize one section into its result type
+    pub fn section<T: for<'de> Deserialize<'de>>(&self, name: &str) -> Result<T> {
+        let value = self
+            .sections
+            .get(name)
+            .ok_or_else(|| Error::metrics(format!("Report has no section {}", name)))?;
+        Ok(T::deserialize(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a report, rejecting schema versions newer than this library writes
+    pub fn from_json(json: &str) -> Result<Self> {
+        let report: MetricsReport = serde_json::from_str(json)?;
+        if report.schema_version > REPORT_SCHEMA_VERSION {
+            return Err(Error::metrics(format!(
+                "Report schema version {} is newer than supported version {}",
+                report.schema_version, REPORT_SCHEMA_VERSION
+            )));
+        }
+        Ok(report)
+    }
+
+    /// Flatten into `section,field,value` CSV rows under a header
+    ///
+    /// Nested fields are joined with `.` and array items are indexed, e.g.
+    /// `coefficients.0`. Nulls are written as empty values.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,field,value\n");
+        for (name, value) in &self.sections {
+            let mut rows = Vec::new();
+            flatten(value, String::new(), &mut rows);
+            for (field, value) in rows {
+                csv.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(name),
+                    csv_field(&field),
+                    csv_field(&value)
+                ));
+            }
+        }
+        csv
+    }
+}
+
+/// Collects sections for a [`MetricsReport`]
+#[derive(Debug)]
+pub struct MetricsReportBuilder {
+    subject: String,
+    generated_at: DateTime<Utc>,
+    sections: BTreeMap<String, Value>,
+    error: Option<Error>,
+}
+
+impl MetricsReportBuilder {
+    /// Add a result under `name`; serialization errors are reported by [`build`](Self::build)
+    pub fn section(mut self, name: impl Into<String>, result: &impl Serialize) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let name = name.into();
+        match serde_json::to_value(result) {
+            Ok(_) if self.sections.contains_key(&name) => {
+                self.error = Some(Error::metrics(format!("Duplicate report section {}", name)));
+            }
+            Ok(value) => {
+                self.sections.insert(name, value);
+            }
+            Err(e) => self.error = Some(e.into()),
+        }
+        self
+    }
+
+    /// Override the generation time, which defaults to when the builder was created
+    pub fn generated_at(mut self, generated_at: DateTime<Utc>) -> Self {
+        self.generated_at = generated_at;
+        self
+    }
+
+    pub fn build(self) -> Result<MetricsReport> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        Ok(MetricsReport {
+            schema_version: REPORT_SCHEMA_VERSION,
+            subject: self.subject,
+            generated_at: self.generated_at,
+            sections: self.sections,
+        })
+    }
+}
+
+fn flatten(value: &Value, path: String, rows: &mut Vec<(String, String)>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                flatten(value, child(key), rows);
+            }
+        }
+        Value::Array(items) => {
+            for (i, value) in items.iter().enumerate() {
+                flatten(value, child(&i.to_string()), rows);
+            }
+        }
+        Value::Null => rows.push((path, String::new())),
+        Value::String(s) => rows.push((path, s.clone())),
+        other => rows.push((path, other.to_string())),
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::statistical::StreamingStatistics;
+    use chrono::TimeZone;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Growth {
+        cagr: f64,
+        periods: Vec<Option<f64>>,
+    }
+
+    fn report() -> MetricsReport {
+        let stats: StreamingStatistics = [1.0, 2.0, 3.0].into_iter().collect();
+        MetricsReport::builder("tokio-rs/tokio")
+            .generated_at(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap())
+            .section("stars", &stats)
+            .section(
+                "growth",
+                &Growth {
+                    cagr: 0.25,
+                    periods: vec![Some(0.1), None],
+                },
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_report_round_trips_through_json() {
+        // Test: Sections deserialize back to their result types and versions are checked
+        let report = report();
+        assert_eq!(report.schema_version, REPORT_SCHEMA_VERSION);
+        let restored = MetricsReport::from_json(&report.to_json().unwrap()).unwrap();
+        assert_eq!(restored, report);
+
+        let stats: StreamingStatistics = restored.section("stars").unwrap();
+        assert_eq!(stats.mean(), Some(2.0));
+        assert!(restored.section::<Growth>("missing").is_err());
+
+        let mut future = serde_json::to_value(&report).unwrap();
+        future["schema_version"] = (REPORT_SCHEMA_VERSION + 1).into();
+        assert!(
+            MetricsReport::from_json(&future.to_string()).is_err(),
+            "Newer schema versions are rejected"
+        );
+
+        let duplicate = MetricsReport::builder("x")
+            .section("a", &1)
+            .section("a", &2)
+            .build();
+        assert!(duplicate.is_err(), "Section names must be unique");
+    }
+
+    #[test]
+    fn test_report_flattens_to_csv() {
+        // Test: Nested values become dotted field paths, nulls empty values
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "section,field,value");
+        assert!(lines.contains(&"growth,cagr,0.25"));
+        assert!(lines.contains(&"growth,periods.0,0.1"));
+        assert!(lines.contains(&"growth,periods.1,"));
+        assert!(lines.contains(&"stars,count,3"));
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,206 @@
+//! Rolling-window statistics over a [`TimeSeries`]
+//!
+//! `series.rolling(7).mean()` yields a new series whose value at each
+//! observation summarizes the window ending there, e.g. to smooth noisy
+//! daily download counts before classifying a trend. Windows are either a
+//! number of observations or a span of time, the latter being robust to
+//! irregular sampling. A window whose statistic overflows `f64` makes the
+//! whole computation fail rather than yield an infinite point.
+
+use super::statistical::StreamingStatistics;
+use super::timeseries::TimeSeries;
+use crate::error::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Extent of each rolling window, ending at the current observation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollingWindow {
+    /// The last `n` observations
+    Count(usize),
+    /// Observations within this span, exclusive of the start
+    Duration(TimeDelta),
+}
+
+impl From<usize> for RollingWindow {
+    fn from(count: usize) -> Self {
+        RollingWindow::Count(count)
+    }
+}
+
+impl From<TimeDelta> for RollingWindow {
+    fn from(span: TimeDelta) -> Self {
+        RollingWindow::Duration(span)
+    }
+}
+
+/// Builder for rolling statistics; created by [`TimeSeries::rolling`]
+#[derive(Debug, Clone)]
+pub struct Rolling<'a> {
+    points: &'a [(DateTime<Utc>, f64)],
+    window: RollingWindow,
+    min_periods: usize,
+}
+
+impl<'a> Rolling<'a> {
+    pub(crate) fn new(series: &'a TimeSeries, window: RollingWindow) -> Self {
+        let min_periods = match window {
+            RollingWindow::Count(count) => count.max(1),
+            RollingWindow::Duration(_) => 1,
+        };
+        Self {
+            points: series.points(),
+            window,
+            min_periods,
+        }
+    }
+
+    /// Emit a value only when the window holds at least `n` observations
+    ///
+    /// Defaults to the full count for count windows and 1 for duration windows.
+    pub fn min_periods(mut self, n: usize) -> Self {
+        self.min_periods = n.max(1);
+        self
+    }
+
+    /// Rolling mean
+    pub fn mean(&self) -> Result<TimeSeries> {
+        self.apply(|window| statistics(window).mean())
+    }
+
+    /// Rolling sum
+    pub fn sum(&self) -> Result<TimeSeries> {
+        self.apply(|window| Some(window.iter().map(|(_, value)| value).sum()))
+    }
+
+    /// Rolling sample standard deviation; windows of one observation are skipped
+    pub fn std(&self) -> Result<TimeSeries> {
+        self.apply(|window| statistics(window).sample_variance().map(f64::sqrt))
+    }
+
+    /// Rolling minimum
+    pub fn min(&self) -> Result<TimeSeries> {
+        self.apply(|window| statistics(window).min())
+    }
+
+    /// Rolling maximum
+    pub fn max(&self) -> Result<TimeSeries> {
+        self.apply(|window| statistics(window).max())
+    }
+
+    fn apply(
+        &self,
+        statistic: impl Fn(&[(DateTime<Utc>, f64)]) -> Option<f64>,
+    ) -> Result<TimeSeries> {
+        let mut output = TimeSeries::default();
+        let mut start = 0;
+        for (end, &(timestamp, _)) in self.points.iter().enumerate() {
+            start = match self.window {
+                RollingWindow::Count(count) => (end + 1).saturating_sub(count.max(1)),
+                RollingWindow::Duration(span) => {
+                    let mut start = start;
+                    while start < end && self.points[start].0 <= timestamp - span {
+                        start += 1;
+                    }
+                    start
+                }
+            };
+            let window = &self.points[start..=end];
+            if window.len() < self.min_periods {
+                continue;
+            }
+            if let Some(value) = statistic(window) {
+                output.push(timestamp, value)?;
+            }
+        }
+        Ok(output)
+    }
+}
+
+fn statistics(window: &[(DateTime<Utc>, f64)]) -> StreamingStatistics {
+    window.iter().map(|(_, value)| *value).collect()
+}
+
+impl TimeSeries {
+    /// Rolling statistics over windows ending at each observation
+    pub fn rolling(&self, window: impl Into<RollingWindow>) -> Rolling<'_> {
+        Rolling::new(self, window.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_count_windows() {
+        // Test: Count windows emit once full and summarize the trailing observations
+        let series = TimeSeries::new(
+            [4.0, 8.0, 6.0, 2.0, 10.0]
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (day(i as u32 + 1), *v))
+                .collect(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            series.rolling(3).mean().unwrap().values(),
+            vec![6.0, 16.0 / 3.0, 6.0]
+        );
+        assert_eq!(series.rolling(3).sum().unwrap().points()[0], (day(3), 18.0));
+        assert_eq!(
+            series.rolling(2).min().unwrap().values(),
+            vec![4.0, 6.0, 2.0, 2.0]
+        );
+        assert_eq!(
+            series.rolling(2).max().unwrap().values(),
+            vec![8.0, 8.0, 6.0, 10.0]
+        );
+        assert_eq!(
+            series.rolling(3).min_periods(1).mean().unwrap().len(),
+            5,
+            "Partial windows are emitted when allowed"
+        );
+        let std = series.rolling(2).std().unwrap().values();
+        assert!((std[0] - 8.0f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_duration_windows_handle_gaps() {
+        // Test: Duration windows only include observations within the span
+        let series = TimeSeries::new(vec![
+            (day(1), 1.0),
+            (day(2), 2.0),
+            (day(3), 3.0),
+            (day(10), 10.0),
+        ])
+        .unwrap();
+        let sums = series.rolling(TimeDelta::days(3)).sum().unwrap();
+        assert_eq!(
+            sums.values(),
+            vec![1.0, 3.0, 6.0, 10.0],
+            "The gap before day 10 leaves it alone in its window"
+        );
+    }
+
+    #[test]
+    fn test_extreme_values() {
+        // Test: Sums are exact rather than mean * count, and overflow is an error
+        let series = TimeSeries::new(vec![(day(1), 0.1), (day(2), 0.2), (day(3), 0.3)]).unwrap();
+        assert_eq!(
+            series.rolling(3).sum().unwrap().values(),
+            vec![0.1 + 0.2 + 0.3]
+        );
+
+        let huge = TimeSeries::new(vec![(day(1), 1e308), (day(2), 1e308)]).unwrap();
+        assert!(huge.rolling(2).sum().is_err(), "Sum overflows");
+        let spread = TimeSeries::new(vec![(day(1), -1e308), (day(2), 1e308)]).unwrap();
+        assert!(spread.rolling(2).std().is_err(), "Variance overflows");
+        assert_eq!(spread.rolling(2).max().unwrap().values(), vec![1e308]);
+    }
+}
@@ -0,0 +1,342 @@
+//! Configurable composite scores
+//!
+//! A [`ScoreEngine`] turns a project's raw metrics into one score. Each
+//! [`ScoreComponent`] transforms its metric, caps it to a range mapped onto
+//! 0–1, and contributes in proportion to its weight. Every score carries a
+//! per-component breakdown so rankings can be explained.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Transform applied to a raw metric before capping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreTransform {
+    #[default]
+    Identity,
+    /// `ln(1 + x)`, for heavy-tailed counts like stars or downloads; negatives count as 0
+    Log1p,
+    /// Square root; negatives count as 0
+    Sqrt,
+}
+
+impl ScoreTransform {
+    fn apply(self, value: f64) -> f64 {
+        match self {
+            ScoreTransform::Identity => value,
+            ScoreTransform::Log1p => value.max(0.0).ln_1p(),
+            ScoreTransform::Sqrt => value.max(0.0).sqrt(),
+        }
+    }
+}
+
+/// One weighted input to the composite score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreComponent {
+    /// Metric name looked up in each project's metrics
+    pub metric: String,
+    /// Relative weight; weights need not sum to 1
+    pub weight: f64,
+    #[serde(default)]
+    pub transform: ScoreTransform,
+    /// Transformed value scoring 0; lower values are capped
+    #[serde(default)]
+    pub floor: f64,
+    /// Transformed value scoring 1; higher values are capped
+    #[serde(default = "default_ceiling")]
+    pub ceiling: f64,
+    /// Score lower values higher, e.g. for open issue age
+    #[serde(default)]
+    pub invert: bool,
+    /// Normalized score used when the metric is missing; if unset, the
+    /// component is left out and the remaining weights are rescaled
+    #[serde(default)]
+    pub missing: Option<f64>,
+}
+
+fn default_ceiling() -> f64 {
+    1.0
+}
+
+fn default_scale() -> f64 {
+    100.0
+}
+
+/// Declarative scoring model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreConfig {
+    pub components: Vec<ScoreComponent>,
+    /// Score given to a project that maxes out every component
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+/// How one component contributed to a score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentScore {
+    pub metric: String,
+    /// Raw metric value, if present
+    pub raw: Option<f64>,
+    /// Component score in 0–1
+    pub normalized: f64,
+    /// Share of the total weight after leaving out missing components
+    pub weight: f64,
+    /// Points added to the final score
+    pub contribution: f64,
+}
+
+/// A project's composite score with its breakdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectScore {
+    pub project: String,
+    pub score: f64,
+    pub components: Vec<ComponentScore>,
+}
+
+/// Computes composite scores from a [`ScoreConfig`]
+#[derive(Debug, Clone)]
+pub struct ScoreEngine {
+    config: ScoreConfig,
+}
+
+impl ScoreEngine {
+    /// Validate the configuration and build an engine
+    pub fn new(config: ScoreConfig) -> Result<Self> {
+        if config.components.is_empty() {
+            return Err(Error::config("score config needs at least one component"));
+        }
+        if !(config.scale.is_finite() && config.scale > 0.0) {
+            return Err(Error::config("score scale must be > 0"));
+        }
+        let mut seen = HashSet::new();
+        for component in &config.components {
+            if !seen.insert(component.metric.as_str()) {
+                return Err(Error::config(format!(
+                    "duplicate score component: {}",
+                    component.metric
+                )));
+            }
+            if !(component.weight.is_finite() && component.weight >= 0.0) {
+                return Err(Error::config(format!(
+                    "score component {} weight must be >= 0",
+                    component.metric
+                )));
+            }
+            if !(component.floor.is_finite() && component.ceiling.is_finite())
+                || component.ceiling <= component.floor
+            {
+                return Err(Error::config(format!(
+                    "score component {} ceiling must exceed floor",
+                    component.metric
+                )));
+            }
+            if component
+                .missing
+                .is_some_and(|missing| !(0.0..=1.0).contains(&missing))
+            {
+                return Err(Error::config(format!(
+                    "score component {} missing score must be in 0-1",
+                    component.metric
+                )));
+            }
+        }
+        if config.components.iter().all(|c| c.weight == 0.0) {
+            return Err(Error::config("score component weights are all zero"));
+        }
+        Ok(Self { config })
+    }
+
+    /// The engine's configuration
+    pub fn config(&self) -> &ScoreConfig {
+        &self.config
+    }
+
+    /// Score one project from its named metrics
+    ///
+    /// Non-finite metric values are treated as missing.
+    pub fn score(&self, project: &str, metrics: &HashMap<String, f64>) -> Result<ProjectScore> {
+        let scored: Vec<(&ScoreComponent, Option<f64>, Option<f64>)> = self
+            .config
+            .components
+            .iter()
+            .map(|component| {
+                let raw = metrics
+                    .get(&component.metric)
+                    .copied()
+                    .filter(|v| v.is_finite());
+                let normalized = raw
+                    .map(|raw| normalize(component, raw))
+                    .or(component.missing);
+                (component, raw, normalized)
+            })
+            .collect();
+
+        let total_weight: f64 = scored
+            .iter()
+            .filter(|(_, _, normalized)| normalized.is_some())
+            .map(|(component, _, _)| component.weight)
+            .sum();
+        if total_weight == 0.0 {
+            return Err(Error::metrics(format!(
+                "Project {} has none of the weighted score metrics",
+                project
+            )));
+        }
+
+        let components: Vec<ComponentScore> = scored
+            .into_iter()
+            .map(|(component, raw, normalized)| {
+                let weight = if normalized.is_some() {
+                    component.weight / total_weight
+                } else {
+                    0.0
+                };
+                let normalized = normalized.unwrap_or(0.0);
+                ComponentScore {
+                    metric: component.metric.clone(),
+                    raw,
+                    normalized,
+                    weight,
+                    contribution: normalized * weight * self.config.scale,
+                }
+            })
+            .collect();
+        Ok(ProjectScore {
+            project: project.to_string(),
+            score: components.iter().map(|c| c.contribution).sum(),
+            components,
+        })
+    }
+
+    /// Score projects and order them from highest to lowest score
+    pub fn rank<'a>(
+        &self,
+        projects: impl IntoIterator<Item = (&'a str, &'a HashMap<String, f64>)>,
+    ) -> Result<Vec<ProjectScore>> {
+        let mut scores = projects
+            .into_iter()
+            .map(|(project, metrics)| self.score(project, metrics))
+            .collect::<Result<Vec<_>>>()?;
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(scores)
+    }
+}
+
+/// Transform, cap and map a raw value onto 0–1
+fn normalize(component: &ScoreComponent, raw: f64) -> f64 {
+    let value = component
+        .transform
+        .apply(raw)
+        .clamp(component.floor, component.ceiling);
+    let scaled = (value - component.floor) / (component.ceiling - component.floor);
+    if component.invert {
+        1.0 - scaled
+    } else {
+        scaled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> ScoreEngine {
+        let config: ScoreConfig = serde_json::from_value(serde_json::json!({
+            "components": [
+                {"metric": "stars", "weight": 2.0, "transform": "log1p", "ceiling": 10.0},
+                {"metric": "bus_factor", "weight": 1.0, "floor": 1.0, "ceiling": 5.0},
+                {"metric": "issue_age_days", "weight": 1.0, "ceiling": 365.0, "invert": true, "missing": 0.5}
+            ]
+        }))
+        .unwrap();
+        ScoreEngine::new(config).unwrap()
+    }
+
+    fn metrics(pairs: &[(&str, f64)]) -> HashMap<String, f64> {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_score_breakdown() {
+        // Test: Components are transformed, capped, weighted and explained
+        let score = engine()
+            .score(
+                "serde",
+                &metrics(&[
+                    ("stars", 1e6),
+                    ("bus_factor", 3.0),
+                    ("issue_age_days", 730.0),
+                ]),
+            )
+            .unwrap();
+        let stars = &score.components[0];
+        assert_eq!(stars.normalized, 1.0, "ln(1e6+1) exceeds the ceiling of 10");
+        assert_eq!(stars.weight, 0.5);
+        assert_eq!(score.components[1].normalized, 0.5);
+        assert_eq!(score.components[2].normalized, 0.0, "Inverted and capped");
+        assert!(
+            (score.score - 62.5).abs() < 1e-9,
+            "score was {}",
+            score.score
+        );
+    }
+
+    #[test]
+    fn test_missing_metrics_and_ranking() {
+        // Test: Missing metrics use their default or drop out, and ranking sorts by score
+        let engine = engine();
+        let sparse = metrics(&[("bus_factor", 5.0)]);
+        let score = engine.score("sparse", &sparse).unwrap();
+        assert_eq!(score.components[0].weight, 0.0, "Missing stars drop out");
+        assert_eq!(
+            score.components[2].normalized, 0.5,
+            "Missing default applies"
+        );
+        assert!((score.score - 75.0).abs() < 1e-9);
+
+        let weak = metrics(&[("bus_factor", 1.0), ("stars", 0.0)]);
+        let ranked = engine.rank([("weak", &weak), ("sparse", &sparse)]).unwrap();
+        assert_eq!(ranked[0].project, "sparse");
+        assert!(engine.score("empty", &HashMap::new()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        // Test: Bad weights, ranges and duplicates are configuration errors
+        let component = ScoreComponent {
+            metric: "stars".to_string(),
+            weight: 1.0,
+            transform: ScoreTransform::Identity,
+            floor: 0.0,
+            ceiling: 1.0,
+            invert: false,
+            missing: None,
+        };
+        let config = |components: Vec<ScoreComponent>| ScoreConfig {
+            components,
+            scale: 100.0,
+        };
+        assert!(ScoreEngine::new(config(vec![component.clone()])).is_ok());
+        assert!(ScoreEngine::new(config(vec![component.clone(), component.clone()])).is_err());
+        assert!(
+            ScoreEngine::new(config(vec![ScoreComponent {
+                ceiling: 0.0,
+                ..component.clone()
+            }]))
+            .is_err()
+        );
+        assert!(
+            ScoreEngine::new(config(vec![ScoreComponent {
+                weight: 0.0,
+                ..component.clone()
+            }]))
+            .is_err()
+        );
+        let engine = ScoreEngine::new(config(vec![component])).unwrap();
+        assert!(
+            engine.score("none", &HashMap::new()).is_err(),
+            "A project with no usable metrics cannot be scored"
+        );
+    }
+}
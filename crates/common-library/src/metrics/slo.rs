@@ -0,0 +1,318 @@
+//! Service level objective tracking
+//!
+//! An [`SloTracker`] consumes the outcome and latency of each operation and
+//! compares the recent error rate to what each objective allows (e.g. 99%
+//! of registry calls succeed within 2s). The ratio is the burn rate: 1.0
+//! spends the error budget exactly over the window, higher values spend it
+//! faster. Crossing an objective's alert threshold logs a warning and
+//! broadcasts an [`SloEvent`]. Status reports include the window's p99
+//! latency.
+
+use super::statistical::TDigest;
+use crate::error::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// A target share of good operations over a sliding window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloObjective {
+    /// Operation name passed to [`SloTracker::record`]
+    pub name: String,
+    /// Required share of good operations, e.g. 0.99
+    pub target: f64,
+    /// Successful operations slower than this count as bad
+    #[serde(default)]
+    pub latency_threshold: Option<Duration>,
+    /// Span of recent operations considered
+    pub window: Duration,
+    /// Burn rate at which a breach is reported
+    pub alert_burn_rate: f64,
+    /// Operations needed in the window before alerting
+    #[serde(default = "default_min_events")]
+    pub min_events: usize,
+}
+
+fn default_min_events() -> usize {
+    10
+}
+
+impl SloObjective {
+    /// Objective for `name` with a one-hour window, alerting at a burn rate of 2
+    pub fn new(name: impl Into<String>, target: f64) -> Self {
+        Self {
+            name: name.into(),
+            target,
+            latency_threshold: None,
+            window: Duration::from_secs(3600),
+            alert_burn_rate: 2.0,
+            min_events: default_min_events(),
+        }
+    }
+
+    /// Count successful operations slower than `threshold` as bad
+    pub fn with_latency_threshold(mut self, threshold: Duration) -> Self {
+        self.latency_threshold = Some(threshold);
+        self
+    }
+
+    /// Set the sliding window
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the burn rate that triggers a breach
+    pub fn with_alert_burn_rate(mut self, burn_rate: f64) -> Self {
+        self.alert_burn_rate = burn_rate;
+        self
+    }
+
+    /// Set the operations needed before alerting
+    pub fn with_min_events(mut self, min_events: usize) -> Self {
+        self.min_events = min_events;
+        self
+    }
+}
+
+/// Compliance of one objective over its current window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SloStatus {
+    pub objective: String,
+    pub total: usize,
+    pub good: usize,
+    /// Share of good operations; 1.0 with no operations
+    pub compliance: f64,
+    /// Error rate divided by the allowed error rate
+    pub burn_rate: f64,
+    /// Share of the window's error budget left, negative once overspent
+    pub budget_remaining: f64,
+    /// Estimated 99th percentile latency in the window
+    pub p99_latency: Option<Duration>,
+    pub at: DateTime<Utc>,
+}
+
+/// Change in an objective's alert state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "status", rename_all = "lowercase")]
+pub enum SloEvent {
+    Breached(SloStatus),
+    Recovered(SloStatus),
+}
+
+#[derive(Debug)]
+struct ObjectiveState {
+    objective: SloObjective,
+    /// (time, latency, good) per operation, oldest first
+    events: VecDeque<(Instant, Duration, bool)>,
+    breached: bool,
+}
+
+impl ObjectiveState {
+    fn status(&self) -> SloStatus {
+        let total = self.events.len();
+        let good = self.events.iter().filter(|(_, _, good)| *good).count();
+        let error_rate = if total == 0 {
+            0.0
+        } else {
+            (total - good) as f64 / total as f64
+        };
+        let burn_rate = error_rate / (1.0 - self.objective.target);
+        let mut latencies: TDigest = self
+            .events
+            .iter()
+            .map(|(_, latency, _)| latency.as_secs_f64())
+            .collect();
+        SloStatus {
+            objective: self.objective.name.clone(),
+            total,
+            good,
+            compliance: 1.0 - error_rate,
+            burn_rate,
+            budget_remaining: 1.0 - burn_rate,
+            p99_latency: latencies.quantile(0.99).map(Duration::from_secs_f64),
+            at: Utc::now(),
+        }
+    }
+}
+
+/// Tracks operations against a set of objectives; safe to share across tasks
+#[derive(Debug)]
+pub struct SloTracker {
+    objectives: Mutex<HashMap<String, ObjectiveState>>,
+    events: broadcast::Sender<SloEvent>,
+}
+
+impl SloTracker {
+    /// Track the given objectives, whose names must be unique
+    pub fn new(objectives: Vec<SloObjective>) -> Result<Self> {
+        let mut states = HashMap::new();
+        for objective in objectives {
+            if !(objective.target > 0.0 && objective.target < 1.0) {
+                return Err(Error::config(format!(
+                    "slo {} target must be between 0 and 1",
+                    objective.name
+                )));
+            }
+            if objective.window.is_zero()
+                || objective.alert_burn_rate.is_nan()
+                || objective.alert_burn_rate <= 0.0
+            {
+                return Err(Error::config(format!(
+                    "slo {} window and alert_burn_rate must be > 0",
+                    objective.name
+                )));
+            }
+            let name = objective.name.clone();
+            let state = ObjectiveState {
+                objective,
+                events: VecDeque::new(),
+                breached: false,
+            };
+            if states.insert(name.clone(), state).is_some() {
+                return Err(Error::config(format!("duplicate slo: {}", name)));
+            }
+        }
+        let (events, _) = broadcast::channel(64);
+        Ok(Self {
+            objectives: Mutex::new(states),
+            events,
+        })
+    }
+
+    /// Receive breach and recovery events
+    pub fn subscribe(&self) -> broadcast::Receiver<SloEvent> {
+        self.events.subscribe()
+    }
+
+    /// Record an operation that just finished; unknown operation names are ignored
+    pub fn record(&self, operation: &str, latency: Duration, success: bool) {
+        self.record_at(operation, Instant::now(), latency, success);
+    }
+
+    /// Record an operation that finished at `at`, e.g. when replaying a log
+    pub fn record_at(&self, operation: &str, at: Instant, latency: Duration, success: bool) {
+        let mut objectives = self.objectives.lock().expect("slo tracker poisoned");
+        let Some(state) = objectives.get_mut(operation) else {
+            return;
+        };
+        let good = success
+            && state
+                .objective
+                .latency_threshold
+                .is_none_or(|threshold| latency <= threshold);
+        state.events.push_back((at, latency, good));
+        if let Some(cutoff) = at.checked_sub(state.objective.window) {
+            while state
+                .events
+                .front()
+                .is_some_and(|(time, _, _)| *time <= cutoff)
+            {
+                state.events.pop_front();
+            }
+        }
+
+        let status = state.status();
+        let burning = status.total >= state.objective.min_events
+            && status.burn_rate >= state.objective.alert_burn_rate;
+        let event = match (burning, state.breached) {
+            (true, false) => {
+                warn!(
+                    "SLO {} breached: burn rate {:.2}, compliance {:.4} over {} operations",
+                    status.objective, status.burn_rate, status.compliance, status.total
+                );
+                SloEvent::Breached(status)
+            }
+            (false, true) => {
+                info!(
+                    "SLO {} recovered: burn rate {:.2}",
+                    status.objective, status.burn_rate
+                );
+                SloEvent::Recovered(status)
+            }
+            _ => return,
+        };
+        state.breached = burning;
+        // No subscribers is fine; the log line above still reports the change
+        let _ = self.events.send(event);
+    }
+
+    /// Current status of an objective
+    pub fn status(&self, objective: &str) -> Option<SloStatus> {
+        let objectives = self.objectives.lock().expect("slo tracker poisoned");
+        objectives.get(objective).map(ObjectiveState::status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> SloTracker {
+        SloTracker::new(vec![
+            SloObjective::new("registry_call", 0.9)
+                .with_latency_threshold(Duration::from_secs(2))
+                .with_window(Duration::from_secs(60))
+                .with_min_events(5),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_breach_and_recovery_events() {
+        // Test: Slow or failed calls burn the budget, emit a breach, then recover as they age out
+        let tracker = tracker();
+        let mut events = tracker.subscribe();
+        let start = Instant::now();
+        let fast = Duration::from_millis(100);
+
+        for i in 0..8 {
+            tracker.record_at("registry_call", start, fast, i != 7);
+        }
+        assert!(
+            events.try_recv().is_err(),
+            "12.5% errors is under the alert rate"
+        );
+
+        tracker.record_at("registry_call", start, Duration::from_secs(5), true);
+        let Ok(SloEvent::Breached(status)) = events.try_recv() else {
+            panic!("A slow success should count as bad and trigger a breach");
+        };
+        assert_eq!((status.total, status.good), (9, 7));
+        assert!(status.burn_rate >= 2.0 && status.budget_remaining < 0.0);
+        assert_eq!(status.p99_latency, Some(Duration::from_secs(5)));
+
+        let later = start + Duration::from_secs(61);
+        tracker.record_at("registry_call", later, fast, true);
+        assert!(matches!(events.try_recv(), Ok(SloEvent::Recovered(_))));
+        assert_eq!(tracker.status("registry_call").unwrap().total, 1);
+        tracker.record("unknown", fast, false);
+        assert!(tracker.status("unknown").is_none());
+    }
+
+    #[test]
+    fn test_invalid_objectives_are_rejected() {
+        // Test: Targets outside (0, 1) and duplicate names are configuration errors
+        assert!(SloTracker::new(vec![SloObjective::new("a", 1.0)]).is_err());
+        assert!(
+            SloTracker::new(vec![
+                SloObjective::new("a", 0.99),
+                SloObjective::new("a", 0.9)
+            ])
+            .is_err()
+        );
+        let objective: SloObjective = serde_json::from_value(serde_json::json!({
+            "name": "a",
+            "target": 0.99,
+            "latency_threshold": {"secs": 2, "nanos": 0},
+            "window": {"secs": 3600, "nanos": 0},
+            "alert_burn_rate": 14.4
+        }))
+        .unwrap();
+        assert_eq!(objective.min_events, 10);
+    }
+}
@@ -0,0 +1,1093 @@
+//! Statistical summaries over metric values
+//!
+//! [`StreamingStatistics`] keeps a running summary in constant memory using
+//! Welford's algorithm, so collectors can summarize millions of points
+//! without buffering them. [`TDigest`] does the same for quantiles, trading
+//! exactness for bounded memory and accuracy that is best in the tails.
+//! [`KernelDensity`] estimates a smooth density curve from a sample, e.g. to
+//! show where a candidate project falls in the ecosystem distribution.
+//! [`correlation_matrix`] and [`covariance_matrix`] relate several metrics
+//! observed across the same projects.
+//! [`fit_distributions`] ranks normal, log-normal and power-law fits to a
+//! sample, e.g. to tell whether a metric is heavy-tailed.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Running count, mean, variance, shape, min and max of a stream of values
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct StreamingStatistics {
+    count: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean
+    m2: f64,
+    /// Sums of cubed and fourth-power differences from the mean
+    m3: f64,
+    m4: f64,
+    min: f64,
+    max: f64,
+}
+
+impl StreamingStatistics {
+    /// An empty summary
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one value; non-finite values are ignored
+    pub fn push(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let n = self.count as f64;
+        let delta = value - self.mean;
+        let delta_n = delta / n;
+        let term = delta * delta_n * (n - 1.0);
+        self.mean += delta_n;
+        self.m4 += term * delta_n * delta_n * (n * n - 3.0 * n + 3.0)
+            + 6.0 * delta_n * delta_n * self.m2
+            - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+    }
+
+    /// Combine with a summary of another stream
+    pub fn merge(&mut self, other: &StreamingStatistics) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let (a, b, n) = (self.count as f64, other.count as f64, count as f64);
+        let delta = other.mean - self.mean;
+        let delta2 = delta * delta;
+        self.mean += delta * b / n;
+        self.m4 += other.m4
+            + delta2 * delta2 * a * b * (a * a - a * b + b * b) / (n * n * n)
+            + 6.0 * delta2 * (a * a * other.m2 + b * b * self.m2) / (n * n)
+            + 4.0 * delta * (a * other.m3 - b * self.m3) / n;
+        self.m3 += other.m3
+            + delta2 * delta * a * b * (a - b) / (n * n)
+            + 3.0 * delta * (a * other.m2 - b * self.m2) / n;
+        self.m2 += other.m2 + delta2 * a * b / n;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+
+    /// Number of values pushed
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Arithmetic mean, if any values were pushed
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Population variance
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    /// Sample variance, which needs at least two values
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1).then(|| self.m2 / (self.count - 1) as f64)
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Population skewness; undefined for constant values
+    pub fn skewness(&self) -> Option<f64> {
+        (self.count > 1 && self.m2 > 0.0)
+            .then(|| (self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5))
+    }
+
+    /// Population excess kurtosis (zero for a normal distribution)
+    pub fn kurtosis(&self) -> Option<f64> {
+        (self.count > 1 && self.m2 > 0.0)
+            .then(|| self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.0)
+    }
+
+    /// Smallest value pushed
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// Largest value pushed
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+}
+
+impl Extend<f64> for StreamingStatistics {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for StreamingStatistics {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(values);
+        stats
+    }
+}
+
+/// Default t-digest compression; larger values keep more centroids
+const DEFAULT_COMPRESSION: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// Mergeable quantile sketch (merging t-digest)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    #[serde(default)]
+    buffer: Vec<Centroid>,
+    count: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_COMPRESSION)
+    }
+}
+
+impl TDigest {
+    /// An empty digest; `compression` bounds the centroid count to roughly twice its value
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression: compression.max(10.0),
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Digest a slice of values
+    pub fn from_slice(values: &[f64]) -> Self {
+        values.iter().copied().collect()
+    }
+
+    /// Add one value; non-finite values are ignored
+    pub fn push(&mut self, value: f64) {
+        self.push_centroid(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+    }
+
+    /// Combine with a digest of another stream
+    pub fn merge(&mut self, other: &TDigest) {
+        for centroid in other.centroids.iter().chain(&other.buffer) {
+            self.push_centroid(*centroid);
+        }
+    }
+
+    /// Number of values digested
+    pub fn count(&self) -> u64 {
+        self.count as u64
+    }
+
+    /// Estimated value below which a fraction `q` of values fall, if any were pushed
+    pub fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.flush();
+        if self.centroids.is_empty() || q.is_nan() {
+            return None;
+        }
+        if q <= 0.0 {
+            return Some(self.min);
+        }
+        if q >= 1.0 {
+            return Some(self.max);
+        }
+
+        let target = q * self.count;
+        let first = self.centroids[0];
+        if target < first.weight / 2.0 {
+            return Some(interpolate(
+                self.min,
+                first.mean,
+                target / (first.weight / 2.0),
+            ));
+        }
+        let mut cumulative = first.weight / 2.0;
+        for pair in self.centroids.windows(2) {
+            let step = (pair[0].weight + pair[1].weight) / 2.0;
+            if target < cumulative + step {
+                return Some(interpolate(
+                    pair[0].mean,
+                    pair[1].mean,
+                    (target - cumulative) / step,
+                ));
+            }
+            cumulative += step;
+        }
+        let last = self.centroids[self.centroids.len() - 1];
+        let remaining = (last.weight / 2.0).max(f64::MIN_POSITIVE);
+        Some(interpolate(
+            last.mean,
+            self.max,
+            ((target - cumulative) / remaining).min(1.0),
+        ))
+    }
+
+    /// Estimated `p`th percentile, e.g. `percentile(99.0)`
+    pub fn percentile(&mut self, p: f64) -> Option<f64> {
+        self.quantile(p / 100.0)
+    }
+
+    fn push_centroid(&mut self, centroid: Centroid) {
+        if !centroid.mean.is_finite() || centroid.weight <= 0.0 {
+            return;
+        }
+        self.min = self.min.min(centroid.mean);
+        self.max = self.max.max(centroid.mean);
+        self.count += centroid.weight;
+        self.buffer.push(centroid);
+        if self.buffer.len() >= (self.compression * 5.0) as usize {
+            self.flush();
+        }
+    }
+
+    /// Merge buffered values into the centroid list
+    fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut all = std::mem::take(&mut self.buffer);
+        all.append(&mut self.centroids);
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let mut merged: Vec<Centroid> = Vec::with_capacity(self.compression as usize * 2);
+        let mut current = all[0];
+        let mut weight_before = 0.0;
+        let mut limit = self.weight_limit(weight_before);
+        for next in &all[1..] {
+            if weight_before + current.weight + next.weight <= limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                merged.push(current);
+                current = *next;
+                limit = self.weight_limit(weight_before);
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// Largest cumulative weight a centroid starting at `weight_before` may reach
+    ///
+    /// Uses the arcsine scale function, which keeps centroids small near the
+    /// tails so extreme quantiles stay accurate.
+    fn weight_limit(&self, weight_before: f64) -> f64 {
+        let q = weight_before / self.count;
+        let k = self.compression / (2.0 * PI) * (2.0 * q - 1.0).asin();
+        let q_limit = ((2.0 * PI * (k + 1.0) / self.compression)
+            .min(PI / 2.0)
+            .sin()
+            + 1.0)
+            / 2.0;
+        q_limit * self.count
+    }
+}
+
+impl Extend<f64> for TDigest {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for TDigest {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut digest = Self::default();
+        digest.extend(values);
+        digest
+    }
+}
+
+fn interpolate(from: f64, to: f64, fraction: f64) -> f64 {
+    from + (to - from) * fraction
+}
+
+/// Kernel bandwidth selection
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bandwidth {
+    /// Silverman's rule of thumb, robust to heavy tails via the IQR
+    Silverman,
+    /// Scott's rule, `1.06 σ n^(-1/5)`
+    Scott,
+    /// An explicit bandwidth
+    Fixed(f64),
+}
+
+/// Gaussian kernel density estimate of a sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KernelDensity {
+    samples: Vec<f64>,
+    bandwidth: f64,
+}
+
+impl KernelDensity {
+    /// Estimate from `samples`; non-finite values are dropped
+    pub fn new(samples: &[f64], bandwidth: Bandwidth) -> Result<Self> {
+        let mut samples: Vec<f64> = samples.iter().copied().filter(|v| v.is_finite()).collect();
+        if samples.len() < 2 {
+            return Err(Error::metrics("Kernel density needs at least 2 values"));
+        }
+        samples.sort_by(f64::total_cmp);
+
+        let n = samples.len() as f64;
+        let std_dev = samples
+            .iter()
+            .copied()
+            .collect::<StreamingStatistics>()
+            .sample_variance()
+            .unwrap_or_default()
+            .sqrt();
+        let bandwidth = match bandwidth {
+            Bandwidth::Fixed(h) => h,
+            Bandwidth::Scott => 1.06 * std_dev * n.powf(-0.2),
+            Bandwidth::Silverman => {
+                let iqr = sorted_quantile(&samples, 0.75) - sorted_quantile(&samples, 0.25);
+                let spread = if iqr > 0.0 {
+                    std_dev.min(iqr / 1.34)
+                } else {
+                    std_dev
+                };
+                0.9 * spread * n.powf(-0.2)
+            }
+        };
+        if !(bandwidth.is_finite() && bandwidth > 0.0) {
+            return Err(Error::metrics(
+                "Kernel bandwidth must be positive; constant samples need a fixed bandwidth",
+            ));
+        }
+        Ok(Self { samples, bandwidth })
+    }
+
+    /// Bandwidth in use
+    pub fn bandwidth(&self) -> f64 {
+        self.bandwidth
+    }
+
+    /// Estimated density at `x`
+    pub fn density(&self, x: f64) -> f64 {
+        let norm = 1.0 / (self.samples.len() as f64 * self.bandwidth * (2.0 * PI).sqrt());
+        norm * self
+            .samples
+            .iter()
+            .map(|s| (-0.5 * ((x - s) / self.bandwidth).powi(2)).exp())
+            .sum::<f64>()
+    }
+
+    /// Estimated share of the distribution at or below `x`
+    pub fn cdf(&self, x: f64) -> f64 {
+        self.samples
+            .iter()
+            .map(|s| normal_cdf((x - s) / self.bandwidth))
+            .sum::<f64>()
+            / self.samples.len() as f64
+    }
+
+    /// `(x, density)` at `points` evenly spaced positions spanning the sample plus three bandwidths
+    pub fn curve(&self, points: usize) -> Vec<(f64, f64)> {
+        let low = self.samples[0] - 3.0 * self.bandwidth;
+        let high = self.samples[self.samples.len() - 1] + 3.0 * self.bandwidth;
+        let step = if points > 1 {
+            (high - low) / (points - 1) as f64
+        } else {
+            0.0
+        };
+        (0..points)
+            .map(|i| {
+                let x = low + step * i as f64;
+                (x, self.density(x))
+            })
+            .collect()
+    }
+}
+
+/// Linearly interpolated quantile of sorted values
+fn sorted_quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    interpolate(sorted[below], sorted[above], position - below as f64)
+}
+
+/// Inverse of the standard normal CDF, e.g. `normal_quantile(0.975) ≈ 1.96`
+///
+/// Uses Acklam's rational approximation (relative error below 1.2e-9).
+/// Returns NaN outside `(0, 1)`.
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if !(p > 0.0 && p < 1.0) {
+        return f64::NAN;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - P_LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Standard normal CDF
+pub fn normal_cdf(z: f64) -> f64 {
+    if z.is_nan() {
+        return f64::NAN;
+    }
+    let tail = regularized_gamma_p(0.5, z * z / 2.0);
+    if z >= 0.0 {
+        0.5 * (1.0 + tail)
+    } else {
+        0.5 * (1.0 - tail)
+    }
+}
+
+const SPECIAL_EPSILON: f64 = 1e-14;
+const SPECIAL_MAX_ITERATIONS: usize = 500;
+const SPECIAL_TINY: f64 = 1e-300;
+
+/// Natural log of the gamma function (Lanczos approximation, g = 7)
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| {
+            sum + c / (x + i as f64 + 1.0)
+        });
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Regularized lower incomplete gamma function P(a, x)
+pub(crate) fn regularized_gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut ap = a;
+        for _ in 0..SPECIAL_MAX_ITERATIONS {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * SPECIAL_EPSILON {
+                break;
+            }
+        }
+        sum * prefix
+    } else {
+        // Continued fraction for Q(a, x), evaluated with Lentz's method
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / SPECIAL_TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..SPECIAL_MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < SPECIAL_TINY {
+                d = SPECIAL_TINY;
+            }
+            c = b + an / c;
+            if c.abs() < SPECIAL_TINY {
+                c = SPECIAL_TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < SPECIAL_EPSILON {
+                break;
+            }
+        }
+        1.0 - prefix * h
+    }
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+pub(crate) fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let clamp = |v: f64| {
+        if v.abs() < SPECIAL_TINY {
+            SPECIAL_TINY
+        } else {
+            v
+        }
+    };
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..SPECIAL_MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < SPECIAL_EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Correlation coefficient computed by [`correlation_matrix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrelationMethod {
+    /// Linear correlation of the values
+    #[default]
+    Pearson,
+    /// Pearson correlation of the ranks, for monotonic relationships and outliers
+    Spearman,
+}
+
+/// Sample covariance between every pair of datasets
+///
+/// Datasets are paired by position, e.g. stars and downloads of the same
+/// projects, so all must have the same length of at least 2.
+pub fn covariance_matrix<D: AsRef<[f64]>>(datasets: &[D]) -> Result<Vec<Vec<f64>>> {
+    let centered = centered_datasets(datasets)?;
+    let n = centered[0].len() as f64;
+    let mut matrix = vec![vec![0.0; centered.len()]; centered.len()];
+    for i in 0..centered.len() {
+        for j in i..centered.len() {
+            let covariance = centered[i]
+                .iter()
+                .zip(&centered[j])
+                .map(|(x, y)| x * y)
+                .sum::<f64>()
+                / (n - 1.0);
+            if !covariance.is_finite() {
+                return Err(Error::metrics(format!(
+                    "Covariance of datasets {} and {} overflowed",
+                    i, j
+                )));
+            }
+            matrix[i][j] = covariance;
+            matrix[j][i] = covariance;
+        }
+    }
+    Ok(matrix)
+}
+
+/// Correlation between every pair of datasets
+///
+/// Same input rules as [`covariance_matrix`]. Entries involving a
+/// constant dataset are `None`, since its correlation is undefined.
+pub fn correlation_matrix<D: AsRef<[f64]>>(
+    datasets: &[D],
+    method: CorrelationMethod,
+) -> Result<Vec<Vec<Option<f64>>>> {
+    let covariance = match method {
+        CorrelationMethod::Pearson => covariance_matrix(datasets)?,
+        CorrelationMethod::Spearman => {
+            let ranked: Vec<Vec<f64>> = datasets.iter().map(|d| ranks(d.as_ref())).collect();
+            covariance_matrix(&ranked)?
+        }
+    };
+    Ok((0..covariance.len())
+        .map(|i| {
+            (0..covariance.len())
+                .map(|j| {
+                    let scale = (covariance[i][i] * covariance[j][j]).sqrt();
+                    (scale > 0.0).then(|| (covariance[i][j] / scale).clamp(-1.0, 1.0))
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Datasets with their means subtracted, after checking they can be paired
+fn centered_datasets<D: AsRef<[f64]>>(datasets: &[D]) -> Result<Vec<Vec<f64>>> {
+    let Some(first) = datasets.first() else {
+        return Err(Error::metrics("At least one dataset is required"));
+    };
+    let n = first.as_ref().len();
+    if n < 2 {
+        return Err(Error::metrics("Datasets need at least 2 values"));
+    }
+    datasets
+        .iter()
+        .enumerate()
+        .map(|(i, dataset)| {
+            let values = dataset.as_ref();
+            if values.len() != n {
+                return Err(Error::metrics(format!(
+                    "Dataset {} has {} values, expected {}",
+                    i,
+                    values.len(),
+                    n
+                )));
+            }
+            if values.iter().any(|v| !v.is_finite()) {
+                return Err(Error::metrics(format!(
+                    "Dataset {} contains non-finite values",
+                    i
+                )));
+            }
+            let mean = values.iter().sum::<f64>() / n as f64;
+            Ok(values.iter().map(|v| v - mean).collect())
+        })
+        .collect()
+}
+
+/// 1-based ranks, with tied values sharing their average rank
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        for &index in &order[start..=end] {
+            ranks[index] = average_rank;
+        }
+        start = end + 1;
+    }
+    ranks
+}
+
+/// A parametric distribution fitted to a sample
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Distribution {
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /// `ln(x)` is normal with mean `mu` and standard deviation `sigma`
+    LogNormal {
+        mu: f64,
+        sigma: f64,
+    },
+    /// Continuous power law `p(x) ∝ x^-alpha` for `x >= x_min`
+    PowerLaw {
+        alpha: f64,
+        x_min: f64,
+    },
+}
+
+impl Distribution {
+    /// Share of the distribution at or below `x`
+    pub fn cdf(&self, x: f64) -> f64 {
+        match *self {
+            Distribution::Normal { mean, std_dev } => normal_cdf((x - mean) / std_dev),
+            Distribution::LogNormal { mu, sigma } if x > 0.0 => normal_cdf((x.ln() - mu) / sigma),
+            Distribution::LogNormal { .. } => 0.0,
+            Distribution::PowerLaw { alpha, x_min } if x >= x_min => {
+                1.0 - (x / x_min).powf(1.0 - alpha)
+            }
+            Distribution::PowerLaw { .. } => 0.0,
+        }
+    }
+}
+
+/// A fitted distribution with its goodness of fit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DistributionFit {
+    pub distribution: Distribution,
+    /// Kolmogorov-Smirnov distance between the sample and the fitted CDF; lower is better
+    pub ks_statistic: f64,
+}
+
+/// Fit normal, log-normal and power-law distributions by maximum likelihood
+///
+/// Fits are ordered best first by Kolmogorov-Smirnov distance. Log-normal
+/// and power-law fits need strictly positive samples and are omitted
+/// otherwise; the power law takes the sample minimum as `x_min`.
+/// Non-finite samples are rejected, as in [`covariance_matrix`].
+pub fn fit_distributions(samples: &[f64]) -> Result<Vec<DistributionFit>> {
+    if samples.iter().any(|v| !v.is_finite()) {
+        return Err(Error::metrics("Samples contain non-finite values"));
+    }
+    let mut samples = samples.to_vec();
+    if samples.len() < 2 {
+        return Err(Error::metrics(
+            "Distribution fitting needs at least 2 values",
+        ));
+    }
+    samples.sort_by(f64::total_cmp);
+
+    let mut candidates = Vec::new();
+    let stats: StreamingStatistics = samples.iter().copied().collect();
+    if let (Some(mean), Some(std_dev)) = (stats.mean(), stats.std_dev())
+        && std_dev > 0.0
+    {
+        candidates.push(Distribution::Normal { mean, std_dev });
+    }
+    let x_min = samples[0];
+    if x_min > 0.0 {
+        let logs: StreamingStatistics = samples.iter().map(|v| v.ln()).collect();
+        if let (Some(mu), Some(sigma)) = (logs.mean(), logs.std_dev())
+            && sigma > 0.0
+        {
+            candidates.push(Distribution::LogNormal { mu, sigma });
+        }
+        let log_ratio: f64 = samples.iter().map(|v| (v / x_min).ln()).sum();
+        if log_ratio > 0.0 {
+            let alpha = 1.0 + samples.len() as f64 / log_ratio;
+            candidates.push(Distribution::PowerLaw { alpha, x_min });
+        }
+    }
+    if candidates.is_empty() {
+        return Err(Error::metrics(
+            "Cannot fit a distribution to constant values",
+        ));
+    }
+
+    let mut fits: Vec<DistributionFit> = candidates
+        .into_iter()
+        .map(|distribution| DistributionFit {
+            distribution,
+            ks_statistic: ks_statistic(&samples, &distribution),
+        })
+        .collect();
+    fits.sort_by(|a, b| a.ks_statistic.total_cmp(&b.ks_statistic));
+    Ok(fits)
+}
+
+/// Largest gap between the empirical CDF of sorted values and `distribution`
+fn ks_statistic(sorted: &[f64], distribution: &Distribution) -> f64 {
+    let n = sorted.len() as f64;
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let cdf = distribution.cdf(x);
+            ((i + 1) as f64 / n - cdf).max(cdf - i as f64 / n)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("statistic should be defined");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_streaming_statistics_summary() {
+        // Test: Running summary matches the closed-form statistics
+        let stats: StreamingStatistics = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0, f64::NAN]
+            .into_iter()
+            .collect();
+        assert_eq!(stats.count(), 8, "NaN should be ignored");
+        assert_close(stats.mean(), 5.0);
+        assert_close(stats.variance(), 4.0);
+        assert_close(stats.std_dev(), 2.0);
+        assert_close(stats.sample_variance(), 32.0 / 7.0);
+        assert_eq!(stats.min(), Some(2.0));
+        assert_eq!(stats.max(), Some(9.0));
+
+        let empty = StreamingStatistics::new();
+        assert_eq!(empty.mean(), None, "Empty summary has no mean");
+        assert_eq!(empty.sample_variance(), None);
+    }
+
+    #[test]
+    fn test_merge_matches_single_pass() {
+        // Test: Merging partial summaries equals summarizing all values at once
+        let values: Vec<f64> = (0..100).map(|i| (i as f64 * 0.37).sin() * 50.0).collect();
+        let whole: StreamingStatistics = values.iter().copied().collect();
+        let mut left: StreamingStatistics = values[..30].iter().copied().collect();
+        let right: StreamingStatistics = values[30..].iter().copied().collect();
+        left.merge(&right);
+        left.merge(&StreamingStatistics::new());
+
+        assert_eq!(left.count(), whole.count());
+        assert_close(left.mean(), whole.mean().unwrap());
+        assert_close(left.variance(), whole.variance().unwrap());
+        assert_eq!(left.min(), whole.min());
+        assert_eq!(left.max(), whole.max());
+        assert_close(left.skewness(), whole.skewness().unwrap());
+        assert_close(left.kurtosis(), whole.kurtosis().unwrap());
+    }
+
+    #[test]
+    fn test_covariance_and_correlation_matrices() {
+        // Test: Pearson captures linear relationships, Spearman monotonic ones
+        let stars = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let downloads = [10.0, 20.0, 30.0, 40.0, 50.0];
+        let issues = [1.0, 8.0, 27.0, 64.0, 1000.0];
+        let constant = [7.0; 5];
+        let datasets = [&stars[..], &downloads[..], &issues[..], &constant[..]];
+
+        let covariance = covariance_matrix(&datasets).unwrap();
+        assert_close(Some(covariance[0][0]), 2.5);
+        assert_close(Some(covariance[0][1]), 25.0);
+        assert_eq!(covariance[1][0], covariance[0][1], "Matrix is symmetric");
+        assert_eq!(covariance[3][3], 0.0);
+
+        let pearson = correlation_matrix(&datasets, CorrelationMethod::Pearson).unwrap();
+        assert_close(pearson[0][1], 1.0);
+        assert!(
+            pearson[0][2].unwrap() < 0.9,
+            "Outlier weakens linear correlation"
+        );
+        assert_eq!(pearson[0][3], None, "Constant data has no correlation");
+        assert_eq!(pearson[3][3], None);
+
+        let spearman = correlation_matrix(&datasets, CorrelationMethod::Spearman).unwrap();
+        assert_close(spearman[0][2], 1.0);
+        let reversed = [3.0, 3.0, 2.0, 1.0];
+        let tied = correlation_matrix(
+            &[[1.0, 2.0, 3.0, 4.0], reversed],
+            CorrelationMethod::Spearman,
+        )
+        .unwrap();
+        assert!(tied[0][1].unwrap() < -0.9, "Ties share an average rank");
+
+        assert!(covariance_matrix(&[&stars[..], &stars[..4]]).is_err());
+        assert!(covariance_matrix::<&[f64]>(&[]).is_err());
+        assert!(covariance_matrix(&[[1.0, f64::NAN]]).is_err());
+        assert!(covariance_matrix(&[[f64::MAX, -f64::MAX]]).is_err());
+    }
+
+    #[test]
+    fn test_shape_statistics() {
+        // Test: Skewness and kurtosis match the closed forms and are undefined for constants
+        let symmetric: StreamingStatistics = [1.0, 2.0, 3.0, 4.0, 5.0].into_iter().collect();
+        assert_close(symmetric.skewness(), 0.0);
+        assert_close(symmetric.kurtosis(), 6.8 / 4.0 - 3.0);
+
+        let skewed: StreamingStatistics = [1.0, 1.0, 1.0, 1.0, 10.0].into_iter().collect();
+        assert_close(skewed.skewness(), 1.5);
+        assert_close(skewed.kurtosis(), 0.25);
+
+        let constant: StreamingStatistics = [3.0, 3.0, 3.0].into_iter().collect();
+        assert_eq!(constant.skewness(), None, "Constant values have no shape");
+        assert_eq!(constant.kurtosis(), None);
+    }
+
+    #[test]
+    fn test_fit_distributions_prefers_matching_family() {
+        // Test: Heavy-tailed samples are best fit by a power law, symmetric ones by a normal
+        let pareto: Vec<f64> = (1..=500)
+            .map(|i| (1.0 - i as f64 / 501.0).powf(-1.0 / 1.5))
+            .collect();
+        let fits = fit_distributions(&pareto).unwrap();
+        assert_eq!(fits.len(), 3);
+        match fits[0].distribution {
+            Distribution::PowerLaw { alpha, .. } => {
+                assert!((alpha - 2.5).abs() < 0.1, "alpha was {}", alpha)
+            }
+            other => panic!("Expected a power law, got {:?}", other),
+        }
+
+        let normal: Vec<f64> = (1..=500)
+            .map(|i| normal_quantile(i as f64 / 501.0) - 10.0)
+            .collect();
+        let fits = fit_distributions(&normal).unwrap();
+        assert_eq!(fits.len(), 1, "Negative samples only admit a normal fit");
+        assert!(fits[0].ks_statistic < 0.01);
+
+        assert!(fit_distributions(&[2.0, 2.0]).is_err());
+        assert!(fit_distributions(&[1.0]).is_err());
+        assert!(
+            fit_distributions(&[1.0, 2.0, f64::NAN]).is_err(),
+            "Non-finite samples are rejected"
+        );
+    }
+
+    #[test]
+    fn test_tdigest_quantiles_on_uniform_values() {
+        // Test: Estimated quantiles of a shuffled uniform range are close to exact
+        let values: Vec<f64> = (0..100_000)
+            .map(|i| ((i * 7919) % 100_000) as f64)
+            .collect();
+        let mut digest = TDigest::from_slice(&values);
+
+        assert_eq!(digest.count(), 100_000);
+        assert_eq!(digest.quantile(0.0), Some(0.0), "q=0 should be the minimum");
+        assert_eq!(
+            digest.quantile(1.0),
+            Some(99_999.0),
+            "q=1 should be the maximum"
+        );
+        for (q, expected) in [
+            (0.5, 50_000.0),
+            (0.9, 90_000.0),
+            (0.99, 99_000.0),
+            (0.001, 100.0),
+        ] {
+            let estimate = digest.quantile(q).unwrap();
+            assert!(
+                (estimate - expected).abs() < 100_000.0 * 0.005,
+                "q={} estimated {} expected ~{}",
+                q,
+                estimate,
+                expected
+            );
+        }
+        assert!(
+            digest.centroids.len() <= 2 * DEFAULT_COMPRESSION as usize,
+            "Centroid count should stay bounded"
+        );
+        assert_eq!(
+            TDigest::default().quantile(0.5),
+            None,
+            "Empty digest has no quantiles"
+        );
+    }
+
+    #[test]
+    fn test_tdigest_merge_and_serde() {
+        // Test: Merged digests estimate the combined distribution and survive serialization
+        let mut low: TDigest = (0..5000).map(|i| i as f64).collect();
+        let high: TDigest = (5000..10_000).map(|i| i as f64).collect();
+        low.merge(&high);
+        let json = serde_json::to_string(&low).unwrap();
+        let mut restored: TDigest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.count(), 10_000);
+        let median = restored.percentile(50.0).unwrap();
+        assert!((median - 5000.0).abs() < 50.0, "median was {}", median);
+        let mut single = TDigest::from_slice(&[42.0]);
+        assert_eq!(single.percentile(99.0), Some(42.0));
+    }
+
+    #[test]
+    fn test_kernel_density() {
+        // Test: The density integrates to one, peaks near the data and its CDF ranks values
+        let samples: Vec<f64> = (0..200).map(|i| ((i * 37) % 200) as f64 / 20.0).collect();
+        let kde = KernelDensity::new(&samples, Bandwidth::Silverman).unwrap();
+        let curve = kde.curve(400);
+        let step = curve[1].0 - curve[0].0;
+        let area: f64 = curve.iter().map(|(_, density)| density * step).sum();
+        assert!((area - 1.0).abs() < 0.01, "Density integrated to {}", area);
+        assert!(kde.density(5.0) > kde.density(20.0));
+        assert!((kde.cdf(5.0) - 0.5).abs() < 0.05);
+
+        let fixed = KernelDensity::new(&[1.0, 1.0], Bandwidth::Fixed(0.5)).unwrap();
+        assert_eq!(fixed.bandwidth(), 0.5);
+        assert!(
+            KernelDensity::new(&[1.0, 1.0], Bandwidth::Scott).is_err(),
+            "Constant samples have no data-driven bandwidth"
+        );
+        assert!(KernelDensity::new(&[1.0], Bandwidth::Silverman).is_err());
+    }
+}
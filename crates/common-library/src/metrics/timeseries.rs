@@ -0,0 +1,191 @@
+//! Timestamped metric series
+//!
+//! A [`TimeSeries`] keeps each value with the instant it was observed, so
+//! irregularly sampled data (missed collection runs, event-driven counts)
+//! is not mistaken for evenly spaced data. Use [`TimeSeries::resample`] to
+//! produce the regular grid that index-based APIs such as forecasting expect.
+
+use super::regression::{RegressionCalculator, RegressionResult};
+use crate::error::{Error, Result};
+use crate::storage::MetricPoint;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Observations ordered by timestamp
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeSeries {
+    points: Vec<(DateTime<Utc>, f64)>,
+}
+
+impl TimeSeries {
+    /// Build a series, sorting by timestamp; non-finite values are rejected
+    pub fn new(mut points: Vec<(DateTime<Utc>, f64)>) -> Result<Self> {
+        if let Some((timestamp, _)) = points.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(Error::metrics(format!("Non-finite value at {}", timestamp)));
+        }
+        points.sort_by_key(|(timestamp, _)| *timestamp);
+        Ok(Self { points })
+    }
+
+    /// Add an observation, keeping timestamp order
+    pub fn push(&mut self, timestamp: DateTime<Utc>, value: f64) -> Result<()> {
+        if !value.is_finite() {
+            return Err(Error::metrics(format!("Non-finite value at {}", timestamp)));
+        }
+        let index = self.points.partition_point(|(t, _)| *t <= timestamp);
+        self.points.insert(index, (timestamp, value));
+        Ok(())
+    }
+
+    /// Number of observations
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether there are no observations
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Observations, oldest first
+    pub fn points(&self) -> &[(DateTime<Utc>, f64)] {
+        &self.points
+    }
+
+    /// Values without timestamps, oldest first
+    pub fn values(&self) -> Vec<f64> {
+        self.points.iter().map(|(_, value)| *value).collect()
+    }
+
+    /// Oldest observation
+    pub fn first(&self) -> Option<(DateTime<Utc>, f64)> {
+        self.points.first().copied()
+    }
+
+    /// Newest observation
+    pub fn last(&self) -> Option<(DateTime<Utc>, f64)> {
+        self.points.last().copied()
+    }
+
+    /// Observations with `start <= timestamp < end`
+    pub fn range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> TimeSeries {
+        let from = self.points.partition_point(|(t, _)| *t < start);
+        let to = self.points.partition_point(|(t, _)| *t < end);
+        TimeSeries {
+            points: self.points[from..to.max(from)].to_vec(),
+        }
+    }
+
+    /// Value at `timestamp`, linearly interpolated between neighbours
+    ///
+    /// Returns `None` outside the observed span.
+    pub fn value_at(&self, timestamp: DateTime<Utc>) -> Option<f64> {
+        let index = self.points.partition_point(|(t, _)| *t < timestamp);
+        let (after_time, after) = *self.points.get(index)?;
+        if after_time == timestamp {
+            return Some(after);
+        }
+        let (before_time, before) = *self.points.get(index.checked_sub(1)?)?;
+        let span = (after_time - before_time).num_milliseconds() as f64;
+        let offset = (timestamp - before_time).num_milliseconds() as f64;
+        Some(before + (after - before) * offset / span)
+    }
+
+    /// Evenly spaced series from the first timestamp by `interval`, interpolating values
+    pub fn resample(&self, interval: TimeDelta) -> Result<TimeSeries> {
+        if interval <= TimeDelta::zero() {
+            return Err(Error::metrics("Resample interval must be positive"));
+        }
+        let (Some((start, _)), Some((end, _))) = (self.first(), self.last()) else {
+            return Ok(TimeSeries::default());
+        };
+        let mut points = Vec::new();
+        let mut timestamp = start;
+        while timestamp <= end {
+            if let Some(value) = self.value_at(timestamp) {
+                points.push((timestamp, value));
+            }
+            timestamp += interval;
+        }
+        Ok(TimeSeries { points })
+    }
+
+    /// Least squares line through the points with time measured in days
+    ///
+    /// The slope is the change per day regardless of sampling intervals.
+    pub fn linear_trend(&self) -> Result<RegressionResult> {
+        let start = self
+            .first()
+            .ok_or_else(|| Error::metrics("Cannot fit a trend to an empty series"))?
+            .0;
+        let days: Vec<f64> = self
+            .points
+            .iter()
+            .map(|(t, _)| (*t - start).num_milliseconds() as f64 / 86_400_000.0)
+            .collect();
+        RegressionCalculator::new().simple(&days, &self.values())
+    }
+}
+
+impl From<&[MetricPoint]> for TimeSeries {
+    fn from(points: &[MetricPoint]) -> Self {
+        let mut points: Vec<(DateTime<Utc>, f64)> = points
+            .iter()
+            .filter(|point| point.value.is_finite())
+            .map(|point| (point.timestamp, point.value))
+            .collect();
+        points.sort_by_key(|(timestamp, _)| *timestamp);
+        Self { points }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_irregular_series_trend_and_resample() {
+        // Test: Gaps in sampling do not distort the per-day trend, and resampling fills them
+        let series = TimeSeries::new(vec![
+            (day(10), 100.0),
+            (day(1), 10.0),
+            (day(2), 20.0),
+            (day(4), 40.0),
+        ])
+        .unwrap();
+        assert_eq!(series.first(), Some((day(1), 10.0)), "Points are sorted");
+
+        let trend = series.linear_trend().unwrap();
+        assert!(
+            (trend.coefficients[0] - 10.0).abs() < 1e-9,
+            "Slope should be 10 per day, got {}",
+            trend.coefficients[0]
+        );
+
+        let daily = series.resample(TimeDelta::days(1)).unwrap();
+        assert_eq!(daily.len(), 10);
+        assert_eq!(daily.value_at(day(7)), Some(70.0));
+        assert_eq!(series.value_at(day(11)), None);
+        assert_eq!(series.range(day(2), day(10)).values(), vec![20.0, 40.0]);
+    }
+
+    #[test]
+    fn test_push_and_conversion() {
+        // Test: Pushes keep order, non-finite values are rejected, stored points convert
+        let mut series = TimeSeries::default();
+        series.push(day(3), 3.0).unwrap();
+        series.push(day(1), 1.0).unwrap();
+        assert!(series.push(day(2), f64::INFINITY).is_err());
+        assert_eq!(series.values(), vec![1.0, 3.0]);
+
+        let stored = [MetricPoint::new(day(5), 5.0), MetricPoint::new(day(4), 4.0)];
+        let converted = TimeSeries::from(&stored[..]);
+        assert_eq!(converted.values(), vec![4.0, 5.0]);
+        assert!(TimeSeries::new(vec![(day(1), f64::NAN)]).is_err());
+    }
+}
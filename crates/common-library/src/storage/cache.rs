@@ -0,0 +1,557 @@
+//! Key-value cache stores with per-entry TTLs
+//!
+//! A [`CacheStore`] maps string keys to byte values that may expire. The
+//! in-memory store suits a single run; the file-backed store keeps entries
+//! across restarts so expensive registry lookups are not repeated.
+
+use crate::error::{Error, Result};
+use crate::utils::crypto;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Key-value store whose entries may expire
+pub trait CacheStore: Send + Sync {
+    /// Look up a value; expired entries are treated as missing
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Store a value, expiring after `ttl` if given
+    fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()>;
+
+    /// Remove a value
+    fn delete(&self, key: &str) -> Result<()>;
+
+    /// Remove all values
+    fn clear(&self) -> Result<()>;
+
+    /// Drop expired entries, returning how many were removed
+    fn purge_expired(&self) -> Result<usize>;
+}
+
+/// JSON helpers for any [`CacheStore`]
+pub trait CacheStoreExt: CacheStore {
+    /// Look up and deserialize a JSON value
+    fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Serialize and store a JSON value
+    fn set_json<T: Serialize + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.set(key, serde_json::to_vec(value)?, ttl)
+    }
+}
+
+impl<S: CacheStore + ?Sized> CacheStoreExt for S {}
+
+/// Return the cached value for `key`, or compute, store, and return it
+pub async fn memoize<S, T, F, Fut>(
+    store: &S,
+    key: &str,
+    ttl: Option<Duration>,
+    fetch: F,
+) -> Result<T>
+where
+    S: CacheStore + ?Sized,
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    if let Some(value) = store.get_json(key)? {
+        return Ok(value);
+    }
+    let value = fetch().await?;
+    store.set_json(key, &value, ttl)?;
+    Ok(value)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredValue {
+    key: String,
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "base64_bytes")]
+    value: Vec<u8>,
+}
+
+impl StoredValue {
+    fn new(key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Self {
+        Self {
+            key: key.to_string(),
+            // A TTL too large to represent never expires
+            expires_at: ttl
+                .and_then(|ttl| chrono::Duration::from_std(ttl).ok())
+                .and_then(|ttl| Utc::now().checked_add_signed(ttl)),
+            value,
+        }
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// In-memory cache store
+#[derive(Debug, Default)]
+pub struct MemoryCacheStore {
+    entries: RwLock<HashMap<String, StoredValue>>,
+}
+
+impl MemoryCacheStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored entries, including expired ones not yet purged
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Check whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, HashMap<String, StoredValue>>> {
+        self.entries
+            .write()
+            .map_err(|_| Error::storage("cache store lock poisoned"))
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|_| Error::storage("cache store lock poisoned"))?;
+        Ok(entries
+            .get(key)
+            .filter(|entry| !entry.is_expired(Utc::now()))
+            .map(|entry| entry.value.clone()))
+    }
+
+    fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        self.write()?
+            .insert(key.to_string(), StoredValue::new(key, value, ttl));
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.write()?.remove(key);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.write()?.clear();
+        Ok(())
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut entries = self.write()?;
+        let before = entries.len();
+        entries.retain(|_, entry| !entry.is_expired(now));
+        Ok(before - entries.len())
+    }
+}
+
+/// File-backed cache store keeping one JSON file per key
+///
+/// Entries are written to a temporary file and renamed into place, so a
+/// crash never leaves a half-written entry; an unreadable entry is treated
+/// as a miss and removed. With a byte budget set, the least recently used
+/// entries are evicted once the files on disk exceed it. Recency survives
+/// restarts through file modification times.
+#[derive(Debug, Clone)]
+pub struct FileCacheStore {
+    dir: PathBuf,
+    max_bytes: Option<u64>,
+    index: Arc<Mutex<LruIndex>>,
+}
+
+/// Entry sizes ordered by last use, loaded from disk on first access
+#[derive(Debug, Default)]
+struct LruIndex {
+    loaded: bool,
+    /// Path to `(size, last use)`
+    entries: HashMap<PathBuf, (u64, u64)>,
+    by_use: BTreeMap<u64, PathBuf>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl LruIndex {
+    fn touch(&mut self, path: &Path, size: u64) {
+        self.remove(path);
+        self.clock += 1;
+        self.entries.insert(path.to_path_buf(), (size, self.clock));
+        self.by_use.insert(self.clock, path.to_path_buf());
+        self.total_bytes += size;
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some((size, used)) = self.entries.remove(path) {
+            self.by_use.remove(&used);
+            self.total_bytes -= size;
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<PathBuf> {
+        let (_, path) = self.by_use.pop_first()?;
+        if let Some((size, _)) = self.entries.remove(&path) {
+            self.total_bytes -= size;
+        }
+        Some(path)
+    }
+}
+
+impl FileCacheStore {
+    /// Create a store rooted at `dir`, creating the directory if necessary
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        crate::utils::fs::ensure_dir(&dir)?;
+        Ok(Self {
+            dir,
+            max_bytes: None,
+            index: Arc::default(),
+        })
+    }
+
+    /// Evict least recently used entries to keep the store under `max_bytes`
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Directory holding the entry files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Total size of the entry files written or found by this store
+    pub fn size_bytes(&self) -> Result<u64> {
+        Ok(self.lock_index()?.total_bytes)
+    }
+
+    /// The recency index, loaded from the directory on first use
+    fn lock_index(&self) -> Result<std::sync::MutexGuard<'_, LruIndex>> {
+        let mut index = self
+            .index
+            .lock()
+            .map_err(|_| Error::storage("cache store lock poisoned"))?;
+        if !index.loaded {
+            let mut files = Vec::new();
+            for path in self.entry_files()? {
+                let metadata = std::fs::metadata(&path)?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                files.push((modified, path, metadata.len()));
+            }
+            files.sort();
+            for (_, path, size) in files {
+                index.touch(&path, size);
+            }
+            index.loaded = true;
+        }
+        Ok(index)
+    }
+
+    /// Record a use of `path` and evict old entries if over budget
+    fn record_use(&self, path: &Path, size: u64) -> Result<()> {
+        let mut index = self.lock_index()?;
+        index.touch(path, size);
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+        while index.total_bytes > max_bytes {
+            let Some(oldest) = index.pop_oldest() else {
+                break;
+            };
+            debug!("Evicting cache entry {}", oldest.display());
+            Self::remove_file(&oldest)?;
+        }
+        Ok(())
+    }
+
+    fn remove_entry(&self, path: &Path) -> Result<()> {
+        self.lock_index()?.remove(path);
+        Self::remove_file(path)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{}.json",
+            hex::encode(Sha256::digest(key.as_bytes()))
+        ))
+    }
+
+    /// Read an entry file, removing it if it cannot be parsed
+    fn read_entry(&self, path: &Path) -> Result<Option<StoredValue>> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        match serde_json::from_slice(&data) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => {
+                warn!("Removing corrupt cache entry {}: {}", path.display(), e);
+                self.remove_entry(path)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn entry_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    fn remove_file(path: &Path) -> Result<()> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl CacheStore for FileCacheStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.entry_path(key);
+        let Some(entry) = self.read_entry(&path)? else {
+            return Ok(None);
+        };
+        if entry.key != key {
+            return Ok(None);
+        }
+        if entry.is_expired(Utc::now()) {
+            self.remove_entry(&path)?;
+            return Ok(None);
+        }
+        if self.max_bytes.is_some() {
+            // Persist recency for the next run; a failure only affects eviction order
+            if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            self.record_use(&path, std::fs::metadata(&path)?.len())?;
+        }
+        Ok(Some(entry.value))
+    }
+
+    fn set(&self, key: &str, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let path = self.entry_path(key);
+        let data = serde_json::to_vec(&StoredValue::new(key, value, ttl))?;
+        crate::utils::fs::write_file_atomic(&path, &data)?;
+        self.record_use(&path, data.len() as u64)
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        self.remove_entry(&self.entry_path(key))
+    }
+
+    fn clear(&self) -> Result<()> {
+        for path in self.entry_files()? {
+            self.remove_entry(&path)?;
+        }
+        Ok(())
+    }
+
+    fn purge_expired(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut removed = 0;
+        for path in self.entry_files()? {
+            // Unreadable entries are dropped along with expired ones
+            let expired = self
+                .read_entry(&path)
+                .map(|entry| entry.is_none_or(|entry| entry.is_expired(now)))
+                .unwrap_or(true);
+            if expired {
+                self.remove_entry(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Serde adapter storing bytes as a base64 string
+pub(crate) mod base64_bytes {
+    use super::crypto;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&crypto::encode_base64(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        crypto::decode_base64(&encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(store: &dyn CacheStore) {
+        store.set("npm:left-pad", b"1.3.0".to_vec(), None).unwrap();
+        store
+            .set("npm:stale", b"0.1.0".to_vec(), Some(Duration::ZERO))
+            .unwrap();
+        store
+            .set_json(
+                "pypi:requests",
+                &vec!["2.31.0"],
+                Some(Duration::from_secs(3600)),
+            )
+            .unwrap();
+
+        assert_eq!(store.get("npm:left-pad").unwrap(), Some(b"1.3.0".to_vec()));
+        assert_eq!(
+            store.get("npm:stale").unwrap(),
+            None,
+            "Expired entries should be missing"
+        );
+        assert_eq!(
+            store.get_json::<Vec<String>>("pypi:requests").unwrap(),
+            Some(vec!["2.31.0".to_string()])
+        );
+
+        store.delete("npm:left-pad").unwrap();
+        assert_eq!(store.get("npm:left-pad").unwrap(), None);
+        store.clear().unwrap();
+        assert_eq!(store.get("pypi:requests").unwrap(), None);
+    }
+
+    #[test]
+    fn test_memory_store_ttl() {
+        // Test: The memory store expires entries and purges them on request
+        let store = MemoryCacheStore::new();
+        exercise(&store);
+
+        store.set("a", vec![1], Some(Duration::ZERO)).unwrap();
+        store.set("b", vec![2], None).unwrap();
+        assert_eq!(store.purge_expired().unwrap(), 1);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_file_store_persists_across_instances() {
+        // Test: The file store survives reopening and drops expired entries
+        let dir = std::env::temp_dir().join(format!("cache-store-{}", crypto::generate_uuid()));
+        let store = FileCacheStore::new(&dir).unwrap();
+        exercise(&store);
+
+        store.set("kept", b"yes".to_vec(), None).unwrap();
+        store
+            .set("gone", b"no".to_vec(), Some(Duration::ZERO))
+            .unwrap();
+        let reopened = FileCacheStore::new(&dir).unwrap();
+        assert_eq!(reopened.get("kept").unwrap(), Some(b"yes".to_vec()));
+        assert_eq!(reopened.purge_expired().unwrap(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_evicts_least_recently_used() {
+        // Test: Writes beyond the byte budget evict the least recently read entries
+        let dir = std::env::temp_dir().join(format!("cache-lru-{}", crypto::generate_uuid()));
+        let value = vec![0u8; 100];
+        let entry_size = serde_json::to_vec(&StoredValue::new("a", value.clone(), None))
+            .unwrap()
+            .len() as u64;
+        let store = FileCacheStore::new(&dir)
+            .unwrap()
+            .with_max_bytes(entry_size * 3);
+
+        for key in ["a", "b", "c"] {
+            store.set(key, value.clone(), None).unwrap();
+        }
+        assert!(
+            store.get("a").unwrap().is_some(),
+            "Reading a marks it as used"
+        );
+        store.set("d", value.clone(), None).unwrap();
+
+        assert!(
+            store.get("b").unwrap().is_none(),
+            "b was least recently used"
+        );
+        for key in ["a", "c", "d"] {
+            assert!(store.get(key).unwrap().is_some(), "{} should be kept", key);
+        }
+        assert!(store.size_bytes().unwrap() <= entry_size * 3);
+
+        let reopened = FileCacheStore::new(&dir)
+            .unwrap()
+            .with_max_bytes(entry_size * 3);
+        assert_eq!(reopened.size_bytes().unwrap(), entry_size * 3);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_store_recovers_from_corrupt_entries() {
+        // Test: A corrupt entry reads as a miss and is removed; sizes are tracked without a budget
+        let dir = std::env::temp_dir().join(format!("cache-corrupt-{}", crypto::generate_uuid()));
+        let store = FileCacheStore::new(&dir).unwrap();
+        store.set("good", b"value".to_vec(), None).unwrap();
+        let good_size = store.size_bytes().unwrap();
+        assert!(good_size > 0, "Size is tracked without max_bytes");
+
+        let corrupt = store.entry_path("bad");
+        std::fs::write(&corrupt, b"{\"key\": \"bad\", trunc").unwrap();
+        assert_eq!(store.get("bad").unwrap(), None, "Corrupt entry is a miss");
+        assert!(!corrupt.exists(), "Corrupt entry should be deleted");
+
+        store.set("bad", b"fixed".to_vec(), None).unwrap();
+        assert_eq!(store.get("bad").unwrap(), Some(b"fixed".to_vec()));
+        store.delete("good").unwrap();
+        assert_eq!(
+            store.size_bytes().unwrap(),
+            std::fs::metadata(&corrupt).unwrap().len()
+        );
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "No temporary files are left behind"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memoize_fetches_once() {
+        // Test: Memoized lookups only run the fetch on a cache miss
+        let store = MemoryCacheStore::new();
+        let mut calls = 0;
+        for _ in 0..2 {
+            let value: u32 = memoize(&store, "downloads", None, || {
+                calls += 1;
+                async { Ok(42) }
+            })
+            .await
+            .unwrap();
+            assert_eq!(value, 42);
+        }
+        assert_eq!(calls, 1, "The second lookup should hit the cache");
+    }
+}
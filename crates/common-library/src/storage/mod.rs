@@ -0,0 +1,11 @@
+//! Storage functionality for the common library
+//!
+//! Provides key-value cache stores with per-entry expiry, shared by the HTTP
+//! response cache and collectors memoizing registry metadata, and
+//! time-partitioned storage for metric snapshots.
+
+pub mod cache;
+pub mod timeseries;
+
+pub use cache::{CacheStore, CacheStoreExt, FileCacheStore, MemoryCacheStore, memoize};
+pub use timeseries::{MetricPoint, Partitioning, TimeSeriesStore};
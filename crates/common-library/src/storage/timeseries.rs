@@ -0,0 +1,317 @@
+//! Partitioned storage for timestamped metric points
+//!
+//! Each series (e.g. `npm/left-pad/downloads`) is a directory of
+//! newline-delimited JSON files, one per day or month. Appends only touch
+//! the partitions they land in, range queries read only overlapping
+//! partitions, and old data is pruned by deleting whole files.
+
+use crate::error::{Error, Result};
+use chrono::{DateTime, Months, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const PARTITION_EXTENSION: &str = "ndjson";
+
+/// One observation of a metric series
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+impl MetricPoint {
+    /// Create a point
+    pub fn new(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self { timestamp, value }
+    }
+}
+
+/// Time span covered by one partition file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Partitioning {
+    #[default]
+    Daily,
+    Monthly,
+}
+
+impl Partitioning {
+    /// Partition name holding `timestamp`, e.g. `2024-03-09` or `2024-03`
+    fn key(self, timestamp: DateTime<Utc>) -> String {
+        match self {
+            Partitioning::Daily => timestamp.format("%Y-%m-%d").to_string(),
+            Partitioning::Monthly => timestamp.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// `[start, end)` covered by a partition name
+    fn bounds(self, key: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let (start, end) = match self {
+            Partitioning::Daily => {
+                let day = NaiveDate::parse_from_str(key, "%Y-%m-%d").ok()?;
+                (day, day + TimeDelta::days(1))
+            }
+            Partitioning::Monthly => {
+                let month = NaiveDate::parse_from_str(&format!("{}-01", key), "%Y-%m-%d").ok()?;
+                (month, month.checked_add_months(Months::new(1))?)
+            }
+        };
+        Some((
+            start.and_hms_opt(0, 0, 0)?.and_utc(),
+            end.and_hms_opt(0, 0, 0)?.and_utc(),
+        ))
+    }
+}
+
+/// Append-only metric storage partitioned by time
+#[derive(Debug, Clone)]
+pub struct TimeSeriesStore {
+    dir: PathBuf,
+    partitioning: Partitioning,
+}
+
+impl TimeSeriesStore {
+    /// Open a store rooted at `dir`, creating the directory if necessary
+    pub fn new(dir: impl Into<PathBuf>, partitioning: Partitioning) -> Result<Self> {
+        let dir = dir.into();
+        crate::utils::fs::ensure_dir(&dir)?;
+        Ok(Self { dir, partitioning })
+    }
+
+    /// Directory holding the series
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append points to a series
+    pub fn append(&self, series: &str, points: &[MetricPoint]) -> Result<()> {
+        let mut partitions: BTreeMap<String, Vec<&MetricPoint>> = BTreeMap::new();
+        for point in points {
+            if !point.value.is_finite() {
+                return Err(Error::storage(format!(
+                    "Non-finite value for {} at {}",
+                    series, point.timestamp
+                )));
+            }
+            partitions
+                .entry(self.partitioning.key(point.timestamp))
+                .or_default()
+                .push(point);
+        }
+
+        let series_dir = self.series_dir(series);
+        crate::utils::fs::ensure_dir(&series_dir)?;
+        for (key, points) in partitions {
+            let mut lines = Vec::new();
+            for point in points {
+                serde_json::to_writer(&mut lines, point)?;
+                lines.push(b'\n');
+            }
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(partition_path(&series_dir, &key))?
+                .write_all(&lines)?;
+        }
+        Ok(())
+    }
+
+    /// Points of a series with `start <= timestamp < end`, oldest first
+    pub fn range(
+        &self,
+        series: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<MetricPoint>> {
+        let mut points = Vec::new();
+        for (key, path) in self.partitions(series)? {
+            let Some((from, to)) = self.partitioning.bounds(&key) else {
+                continue;
+            };
+            if to <= start || from >= end {
+                continue;
+            }
+            let data = std::fs::read_to_string(&path)?;
+            for line in data.lines().filter(|line| !line.trim().is_empty()) {
+                let point: MetricPoint = serde_json::from_str(line).map_err(|e| {
+                    Error::storage(format!("Corrupt point in {}: {}", path.display(), e))
+                })?;
+                if point.timestamp >= start && point.timestamp < end {
+                    points.push(point);
+                }
+            }
+        }
+        points.sort_by_key(|point| point.timestamp);
+        Ok(points)
+    }
+
+    /// Names of all stored series
+    pub fn series(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir()
+                && let Some(name) = entry.file_name().to_str().and_then(decode_name)
+            {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Delete partitions that end at or before `cutoff`, returning how many were removed
+    ///
+    /// A partition straddling the cutoff is kept whole.
+    pub fn prune_before(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        let mut removed = 0;
+        for series in self.series()? {
+            for (key, path) in self.partitions(&series)? {
+                if self
+                    .partitioning
+                    .bounds(&key)
+                    .is_some_and(|(_, end)| end <= cutoff)
+                {
+                    std::fs::remove_file(path)?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    fn series_dir(&self, series: &str) -> PathBuf {
+        self.dir.join(encode_name(series))
+    }
+
+    /// Partition names and files of a series, oldest first
+    fn partitions(&self, series: &str) -> Result<Vec<(String, PathBuf)>> {
+        let dir = self.series_dir(series);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut partitions = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|ext| ext == PARTITION_EXTENSION)
+                && let Some(key) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                partitions.push((key.to_string(), path.clone()));
+            }
+        }
+        partitions.sort();
+        Ok(partitions)
+    }
+}
+
+fn partition_path(series_dir: &Path, key: &str) -> PathBuf {
+    series_dir.join(format!("{}.{}", key, PARTITION_EXTENSION))
+}
+
+/// Percent-encode a series name into a single safe path component
+fn encode_name(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn decode_name(encoded: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut chars = encoded.bytes();
+    while let Some(byte) = chars.next() {
+        if byte == b'%' {
+            let hex = [chars.next()?, chars.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn temp_store(partitioning: Partitioning) -> TimeSeriesStore {
+        let dir = std::env::temp_dir().join(format!("timeseries-{}", crypto::generate_uuid()));
+        TimeSeriesStore::new(dir, partitioning).unwrap()
+    }
+
+    #[test]
+    fn test_append_and_range_query() {
+        // Test: Points land in daily partitions and range queries filter by time
+        let store = temp_store(Partitioning::Daily);
+        let series = "npm/left-pad/downloads";
+        store
+            .append(
+                series,
+                &[
+                    MetricPoint::new(at(2024, 3, 2, 12), 30.0),
+                    MetricPoint::new(at(2024, 3, 1, 12), 10.0),
+                ],
+            )
+            .unwrap();
+        store
+            .append(series, &[MetricPoint::new(at(2024, 3, 1, 18), 20.0)])
+            .unwrap();
+
+        let points = store
+            .range(series, at(2024, 3, 1, 13), at(2024, 3, 3, 0))
+            .unwrap();
+        let values: Vec<f64> = points.iter().map(|p| p.value).collect();
+        assert_eq!(
+            values,
+            vec![20.0, 30.0],
+            "Points should be filtered and sorted"
+        );
+        assert_eq!(store.series().unwrap(), vec![series.to_string()]);
+        assert_eq!(store.partitions(series).unwrap().len(), 2);
+        assert!(
+            store
+                .append(series, &[MetricPoint::new(at(2024, 3, 1, 0), f64::NAN)])
+                .is_err()
+        );
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_prune_removes_whole_partitions() {
+        // Test: Only monthly partitions entirely before the cutoff are deleted
+        let store = temp_store(Partitioning::Monthly);
+        for month in 1..=3 {
+            store
+                .append(
+                    "github/stars",
+                    &[MetricPoint::new(at(2024, month, 10, 0), month as f64)],
+                )
+                .unwrap();
+        }
+
+        assert_eq!(store.prune_before(at(2024, 2, 15, 0)).unwrap(), 1);
+        let remaining = store
+            .range("github/stars", at(2024, 1, 1, 0), at(2025, 1, 1, 0))
+            .unwrap();
+        assert_eq!(
+            remaining.len(),
+            2,
+            "February straddles the cutoff and is kept"
+        );
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+}
@@ -1,7 +1,7 @@
 //! Utility functions for the common library
 
 use crate::error::{Error, Result};
-use base64::{engine::general_purpose, Engine as _};
+use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, NaiveDateTime, Utc};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
@@ -37,8 +37,8 @@ pub mod date {
         let duration = time
             .duration_since(UNIX_EPOCH)
             .map_err(|e| Error::generic(format!("Invalid system time: {}", e)))?;
-        Ok(DateTime::from_timestamp(duration.as_secs() as i64, 0)
-            .ok_or_else(|| Error::generic("Invalid timestamp"))?)
+        DateTime::from_timestamp(duration.as_secs() as i64, 0)
+            .ok_or_else(|| Error::generic("Invalid timestamp"))
     }
 }
 
@@ -99,8 +99,8 @@ pub mod compression {
 
     /// Compress data using gzip
     pub fn compress_gzip(data: &[u8]) -> Result<Vec<u8>> {
-        use flate2::write::GzEncoder;
         use flate2::Compression;
+        use flate2::write::GzEncoder;
         use std::io::Write;
 
         let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
@@ -124,12 +124,38 @@ pub mod compression {
             .map_err(|e| Error::generic(format!("Failed to decompress data: {}", e)))?;
         Ok(result)
     }
+
+    /// Compress data using brotli
+    #[cfg(feature = "brotli")]
+    pub fn compress_brotli(data: &[u8]) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder
+            .write_all(data)
+            .map_err(|e| Error::generic(format!("Failed to compress data: {}", e)))?;
+        encoder
+            .flush()
+            .map_err(|e| Error::generic(format!("Failed to finish compression: {}", e)))?;
+        Ok(encoder.into_inner())
+    }
+
+    /// Decompress brotli data
+    #[cfg(feature = "brotli")]
+    pub fn decompress_brotli(data: &[u8]) -> Result<Vec<u8>> {
+        use std::io::Read;
+
+        let mut decoder = brotli::Decompressor::new(data, 4096);
+        let mut result = Vec::new();
+        decoder
+            .read_to_end(&mut result)
+            .map_err(|e| Error::generic(format!("Failed to decompress data: {}", e)))?;
+        Ok(result)
+    }
 }
 
 /// String utilities
 pub mod string {
-    use super::*;
-
     /// Truncate a string to the specified length with ellipsis
     pub fn truncate(s: &str, max_len: usize) -> String {
         if s.len() <= max_len {
@@ -147,9 +173,7 @@ pub mod string {
     /// Convert a string to snake_case
     pub fn to_snake_case(s: &str) -> String {
         let mut result = String::new();
-        let mut chars = s.chars().peekable();
-
-        while let Some(c) = chars.next() {
+        for c in s.chars() {
             if c.is_uppercase() && !result.is_empty() {
                 result.push('_');
             }
@@ -210,6 +234,39 @@ pub mod fs {
         Ok(metadata.len())
     }
 
+    /// Replace a file atomically by writing a temporary file beside it and renaming it
+    ///
+    /// On Unix the file is created with mode `0o600`, readable only by its owner.
+    pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+        use std::io::Write;
+
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| Error::generic(format!("Invalid file path {}", path.display())))?;
+        let temp = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            file_name.to_string_lossy(),
+            crypto::generate_uuid()
+        ));
+        let write = || -> std::io::Result<()> {
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut file = options.open(&temp)?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, path)
+        };
+        write().map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            Error::generic(format!("Failed to write {}: {}", path.display(), e))
+        })
+    }
+
     /// Check if a path is a file
     pub fn is_file(path: &Path) -> bool {
         path.is_file()
@@ -223,8 +280,6 @@ pub mod fs {
 
 /// Validation utilities
 pub mod validation {
-    use super::*;
-
     /// Validate an email address format
     pub fn is_valid_email(email: &str) -> bool {
         email.contains('@')
@@ -358,13 +413,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_write_file_atomic_replaces_file() {
+        // Test: Atomic writes replace the contents and leave no temporary files behind
+        let dir = std::env::temp_dir().join(format!("write-atomic-{}", crypto::generate_uuid()));
+        fs::ensure_dir(&dir).unwrap();
+        let path = dir.join("state.json");
+
+        fs::write_file_atomic(&path, b"first").unwrap();
+        fs::write_file_atomic(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(
+            std::fs::read_dir(&dir).unwrap().count(),
+            1,
+            "Temporary file should be renamed away"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "File should be private to its owner");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_fs_utilities() {
         // Test: File system utilities work correctly
         use std::path::Path;
 
         let test_path = Path::new("/tmp/test_dir");
-        let result = fs::ensure_dir(test_path);
+        let _result = fs::ensure_dir(test_path);
         // Note: This test might fail on some systems, so we'll just check that the function exists
         // In a real test environment, you'd use a temporary directory
 
@@ -4,7 +4,7 @@
 
 use anyhow::Result;
 use clap::Parser;
-use tracing::{info, error};
+use tracing::info;
 
 /// Repository Intelligence CLI
 #[derive(Parser, Debug)]